@@ -143,6 +143,7 @@ pub unsafe fn borrow_account_data<T>(account: &AccountInfo) -> Result<&T, Percol
 /// # Returns
 /// * `Ok(&mut T)` if the account data can be safely cast to &mut T
 /// * `Err(PercolatorError)` if validation fails
+#[allow(clippy::mut_from_ref)]
 pub unsafe fn borrow_account_data_mut<T>(account: &AccountInfo) -> Result<&mut T, PercolatorError> {
     let mut data = account.try_borrow_mut_data().map_err(|_| PercolatorError::InvalidAccount)?;
 
@@ -0,0 +1,67 @@
+//! Commit-reveal hashing for reservations
+//!
+//! A taker can bind a reservation to its order by passing a commitment hash
+//! to Reserve and revealing the salt at Commit. The hash is SHA-256 over a
+//! fixed little-endian preimage:
+//!
+//! | offset | field          | type       |
+//! |--------|----------------|------------|
+//! | 0      | route_id       | `u64`      |
+//! | 8      | instrument_idx | `u16`      |
+//! | 10     | side           | `u8`       |
+//! | 11     | qty            | `u64`      |
+//! | 19     | limit_px       | `u64`      |
+//! | 27     | salt           | `[u8; 16]` |
+//!
+//! An all-zero commitment hash opts out of verification.
+
+use crate::types::Side;
+use crate::wire::WireWriter;
+
+/// Length of the commitment preimage in bytes
+pub const COMMITMENT_PREIMAGE_LEN: usize = 8 + 2 + 1 + 8 + 8 + 16;
+
+/// Commitment hash that skips verification at commit
+pub const NO_COMMITMENT: [u8; 32] = [0; 32];
+
+/// SHA-256 digest (the `sol_sha256` syscall on-chain)
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    #[cfg(target_os = "solana")]
+    {
+        let mut out = [0u8; 32];
+        let vals = [data];
+        // SAFETY: `vals` is one valid slice and `out` is a 32-byte buffer
+        unsafe {
+            pinocchio::syscalls::sol_sha256(vals.as_ptr() as *const u8, vals.len() as u64, out.as_mut_ptr());
+        }
+        out
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    {
+        sha2_const_stable::Sha256::new().update(data).finalize()
+    }
+}
+
+/// Commitment hash of a taker order
+///
+/// Clients compute this before Reserve; the slab recomputes it at Commit from
+/// the reservation and the revealed salt.
+pub fn compute_commitment(
+    route_id: u64,
+    instrument_idx: u16,
+    side: Side,
+    qty: u64,
+    limit_px: u64,
+    salt: &[u8; 16],
+) -> [u8; 32] {
+    let mut preimage = [0u8; COMMITMENT_PREIMAGE_LEN];
+    let mut w = WireWriter::new(&mut preimage);
+    w.write_u64(route_id);
+    w.write_u16(instrument_idx);
+    w.write_u8(side as u8);
+    w.write_u64(qty);
+    w.write_u64(limit_px);
+    w.write_bytes(salt);
+    sha256(&preimage)
+}
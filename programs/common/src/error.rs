@@ -1,5 +1,7 @@
 //! Error types
 
+use pinocchio::program_error::ProgramError;
+
 /// Program errors
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -12,6 +14,8 @@ pub enum PercolatorError {
     InsufficientFunds = 4,
     Overflow = 5,
     Underflow = 6,
+    AlreadyInitialized = 7,
+    Unauthorized = 8,
 
     // Router errors (100-199)
     InvalidSlab = 100,
@@ -38,6 +42,7 @@ pub enum PercolatorError {
     InvalidPrice = 210,
     InvalidQuantity = 211,
     PoolFull = 212,
+    ReservationStale = 213,
 
     // Matching errors (300-399)
     InvalidSide = 300,
@@ -46,11 +51,18 @@ pub enum PercolatorError {
     InvalidOrderState = 303,
     BookCorrupted = 304,
     ReservedQtyExceeded = 305,
+    PostOnlyWouldCross = 306,
+    ReduceOnlyViolation = 307,
 
     // Risk errors (400-499)
     InsufficientMargin = 400,
     BelowMaintenanceMargin = 401,
     InvalidRiskParams = 402,
+    FundingNotDue = 403,
+    OracleStale = 404,
+    OracleConfidenceTooWide = 405,
+    InvalidOracle = 406,
+    NotLiquidatable = 407,
 
     // Anti-toxicity errors (500-599)
     KillBandExceeded = 500,
@@ -59,6 +71,7 @@ pub enum PercolatorError {
     InvalidCommitment = 503,
     JitPenaltyApplied = 504,
     RoundtripDetected = 505,
+    BatchNotDue = 506,
 }
 
 impl From<PercolatorError> for u64 {
@@ -66,3 +79,9 @@ impl From<PercolatorError> for u64 {
         e as u64
     }
 }
+
+impl From<PercolatorError> for ProgramError {
+    fn from(e: PercolatorError) -> ProgramError {
+        ProgramError::Custom(e as u32)
+    }
+}
@@ -4,6 +4,9 @@ pub mod types;
 pub mod math;
 pub mod error;
 pub mod account;
+pub mod wire;
+pub mod oracle;
+pub mod commitment;
 
 #[cfg(test)]
 mod tests;
@@ -12,3 +15,6 @@ pub use types::*;
 pub use math::*;
 pub use error::*;
 pub use account::*;
+pub use wire::*;
+pub use oracle::*;
+pub use commitment::*;
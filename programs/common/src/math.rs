@@ -20,7 +20,7 @@ pub fn mul_u64_u128(a: u64, b: u128) -> u128 {
 #[inline]
 pub fn div_ceil_u128(numerator: u128, denominator: u64) -> u128 {
     let denom = denominator as u128;
-    (numerator + denom - 1) / denom
+    numerator.div_ceil(denom)
 }
 
 /// Divide u128 by u64, rounding down
@@ -70,6 +70,31 @@ pub fn calculate_funding_payment(qty: i64, cum_funding_current: i128, cum_fundin
     qty_i128 * (cum_funding_current - cum_funding_entry)
 }
 
+/// Milliseconds per funding rate period (rates are quoted per hour)
+pub const FUNDING_PERIOD_MS: u64 = 3_600_000;
+
+/// Calculate cumulative funding accrued over `elapsed_ms`
+/// Accrual = index_price * rate_bps / 10_000 * elapsed_ms / FUNDING_PERIOD_MS
+///
+/// The result is per unit of position qty, in price units, matching
+/// `calculate_funding_payment`. Positive rates mean longs pay shorts.
+#[inline]
+pub fn calculate_funding_accrual(rate_bps: i64, index_price: u64, elapsed_ms: u64) -> i128 {
+    let numerator = (index_price as i128) * (rate_bps as i128) * (elapsed_ms as i128);
+    numerator / (10_000 * FUNDING_PERIOD_MS as i128)
+}
+
+/// Kill band check: whether `|mark_now / mark_ref - 1| > band_bps`
+///
+/// Evaluated exactly as `|mark_now - mark_ref| * 10_000 > band_bps * mark_ref`,
+/// so the slab at commit and the router pre-checking a reservation always
+/// reach the same answer. A move of exactly `band_bps` is inside the band.
+#[inline]
+pub fn exceeds_kill_band(mark_ref: u64, mark_now: u64, band_bps: u64) -> bool {
+    let moved = mark_now.abs_diff(mark_ref) as u128;
+    moved * 10_000 > (band_bps as u128) * (mark_ref as u128)
+}
+
 /// Check if price is within tick alignment
 #[inline]
 pub fn is_tick_aligned(price: u64, tick: u64) -> bool {
@@ -97,7 +122,7 @@ pub fn round_to_lot(qty: u64, lot: u64) -> u64 {
 /// Calculate IM requirement: |qty| * contract_size * mark_price * imr
 #[inline]
 pub fn calculate_im(qty: i64, contract_size: u64, mark_price: u64, imr_bps: u64) -> u128 {
-    let abs_qty = qty.abs() as u64;
+    let abs_qty = qty.unsigned_abs();
     let notional = mul_u64(abs_qty, contract_size);
     let notional_value = mul_u64_u128(mark_price, notional);
     // imr_bps is in basis points (1 bp = 0.01%)
@@ -107,7 +132,7 @@ pub fn calculate_im(qty: i64, contract_size: u64, mark_price: u64, imr_bps: u64)
 /// Calculate MM requirement: |qty| * contract_size * mark_price * mmr
 #[inline]
 pub fn calculate_mm(qty: i64, contract_size: u64, mark_price: u64, mmr_bps: u64) -> u128 {
-    let abs_qty = qty.abs() as u64;
+    let abs_qty = qty.unsigned_abs();
     let notional = mul_u64(abs_qty, contract_size);
     let notional_value = mul_u64_u128(mark_price, notional);
     // mmr_bps is in basis points (1 bp = 0.01%)
@@ -129,7 +154,7 @@ mod tests {
         assert_eq!(qty, 150);
         let vwap = calculate_vwap(notional, qty);
         // VWAP should be (100*50000 + 50*51000) / 150 = 50333.33...
-        assert!(vwap >= 50_333 && vwap <= 50_334);
+        assert!((50_333..=50_334).contains(&vwap));
     }
 
     #[test]
@@ -0,0 +1,115 @@
+//! Oracle price records
+//!
+//! Slabs read index prices from a Pyth-style price account. The record is a
+//! fixed little-endian layout at the start of the account data, so any
+//! program (or a local stand-in account in tests) can publish it:
+//!
+//! | offset | field          | type      |
+//! |--------|----------------|-----------|
+//! | 0      | magic          | `[u8; 8]` (`b"PERCORC1"`) |
+//! | 8      | price          | `i64`     |
+//! | 16     | conf           | `u64`     |
+//! | 24     | expo           | `i32`     |
+//! | 28     | _padding       | `u32`     |
+//! | 32     | publish_time   | `i64` (unix seconds) |
+//!
+//! `price` and `conf` are scaled by `10^expo`.
+
+use crate::error::PercolatorError;
+use crate::math::PRICE_DECIMALS;
+use crate::wire::{WireReader, WireWriter};
+
+/// Decoded oracle price record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    pub const MAGIC: &'static [u8; 8] = b"PERCORC1";
+    pub const LEN: usize = 8 + 8 + 8 + 4 + 4 + 8;
+
+    /// Decode the record from the start of an oracle account's data
+    ///
+    /// Trailing bytes are ignored so publishers may append their own fields.
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let header = data.get(..Self::LEN).ok_or(PercolatorError::InvalidOracle)?;
+        let mut r = WireReader::new(header);
+
+        if &r.read_bytes::<8>()? != Self::MAGIC {
+            return Err(PercolatorError::InvalidOracle);
+        }
+        let price = r.read_i64()?;
+        let conf = r.read_u64()?;
+        let expo = r.read_u32()? as i32;
+        let _padding = r.read_u32()?;
+        let publish_time = r.read_i64()?;
+        r.finish()?;
+
+        Ok(Self {
+            price,
+            conf,
+            expo,
+            publish_time,
+        })
+    }
+
+    /// Encode the record (used by publishers and tests)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_bytes(Self::MAGIC);
+        w.write_i64(self.price);
+        w.write_u64(self.conf);
+        w.write_u32(self.expo as u32);
+        w.write_u32(0);
+        w.write_i64(self.publish_time);
+        buf
+    }
+
+    /// Publish time in milliseconds (0 if before the epoch)
+    pub fn publish_ms(&self) -> u64 {
+        (self.publish_time.max(0) as u64).saturating_mul(1_000)
+    }
+
+    /// Price and confidence rescaled to slab price units (`PRICE_DECIMALS`)
+    pub fn to_price_units(&self) -> Result<(u64, u64), PercolatorError> {
+        if self.price <= 0 {
+            return Err(PercolatorError::InvalidPrice);
+        }
+
+        let price = rescale(self.price as u64, self.expo)?;
+        let conf = rescale(self.conf, self.expo)?;
+        if price == 0 {
+            return Err(PercolatorError::InvalidPrice);
+        }
+        Ok((price, conf))
+    }
+}
+
+/// Rescale `value * 10^expo` to `10^-PRICE_DECIMALS` units
+fn rescale(value: u64, expo: i32) -> Result<u64, PercolatorError> {
+    let shift = expo + PRICE_DECIMALS as i32;
+    if shift >= 0 {
+        10u64
+            .checked_pow(shift as u32)
+            .and_then(|m| value.checked_mul(m))
+            .ok_or(PercolatorError::Overflow)
+    } else {
+        Ok(10u64
+            .checked_pow(shift.unsigned_abs())
+            .map_or(0, |d| value / d))
+    }
+}
+
+/// Confidence interval as basis points of price (rounded up)
+pub fn confidence_bps(price: u64, conf: u64) -> u64 {
+    if price == 0 {
+        return u64::MAX;
+    }
+    let bps = (conf as u128 * 10_000).div_ceil(price as u128);
+    bps.min(u64::MAX as u128) as u64
+}
@@ -19,7 +19,7 @@ mod math_tests {
         assert_eq!(qty, 150);
         let vwap = calculate_vwap(notional, qty);
         // VWAP = (100*50000 + 50*51000) / 150 = 50,333.33...
-        assert!(vwap >= 50_333 && vwap <= 50_334);
+        assert!((50_333..=50_334).contains(&vwap));
     }
 
     #[test]
@@ -64,6 +64,29 @@ mod math_tests {
         assert_eq!(payment, 5000);
     }
 
+    #[test]
+    fn test_funding_accrual() {
+        // 10 bps/hour on 50,000 for one hour = 50
+        assert_eq!(calculate_funding_accrual(10, 50_000, FUNDING_PERIOD_MS), 50);
+        // Half an hour accrues half
+        assert_eq!(calculate_funding_accrual(10, 50_000, FUNDING_PERIOD_MS / 2), 25);
+        // Negative rate: shorts pay longs
+        assert_eq!(calculate_funding_accrual(-10, 50_000, FUNDING_PERIOD_MS), -50);
+        assert_eq!(calculate_funding_accrual(10, 50_000, 0), 0);
+    }
+
+    #[test]
+    fn test_kill_band() {
+        // 100 bps band around 50,000 is [49,500, 50,500] inclusive
+        assert!(!exceeds_kill_band(50_000, 50_000, 100));
+        assert!(!exceeds_kill_band(50_000, 50_500, 100));
+        assert!(!exceeds_kill_band(50_000, 49_500, 100));
+        assert!(exceeds_kill_band(50_000, 50_501, 100));
+        assert!(exceeds_kill_band(50_000, 49_499, 100));
+        // Zero band rejects any move
+        assert!(exceeds_kill_band(50_000, 50_001, 0));
+    }
+
     #[test]
     fn test_tick_alignment() {
         assert!(is_tick_aligned(50_000, 1000));
@@ -187,3 +210,99 @@ mod type_tests {
         assert!(!pos.used);
     }
 }
+
+#[cfg(test)]
+mod oracle_tests {
+    use crate::error::PercolatorError;
+    use crate::oracle::*;
+
+    fn sample() -> OraclePrice {
+        OraclePrice {
+            price: 5_000_012_345_678,
+            conf: 2_500_000_000,
+            expo: -8,
+            publish_time: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_oracle_roundtrip() {
+        let record = sample();
+        assert_eq!(OraclePrice::unpack(&record.pack()), Ok(record));
+
+        // Publisher-specific trailing bytes are ignored
+        let mut data = [0u8; OraclePrice::LEN + 16];
+        data[..OraclePrice::LEN].copy_from_slice(&record.pack());
+        assert_eq!(OraclePrice::unpack(&data), Ok(record));
+        assert_eq!(record.publish_ms(), 1_700_000_000_000);
+    }
+
+    #[test]
+    fn test_oracle_rejects_bad_data() {
+        let mut data = sample().pack();
+        assert_eq!(
+            OraclePrice::unpack(&data[..OraclePrice::LEN - 1]),
+            Err(PercolatorError::InvalidOracle)
+        );
+
+        data[0] ^= 0xFF;
+        assert_eq!(OraclePrice::unpack(&data), Err(PercolatorError::InvalidOracle));
+    }
+
+    #[test]
+    fn test_oracle_price_units() {
+        // expo -8 -> 6 decimals truncates the last two digits
+        assert_eq!(sample().to_price_units(), Ok((50_000_123_456, 25_000_000)));
+
+        let coarse = OraclePrice { price: 50_000, conf: 5, expo: 0, ..sample() };
+        assert_eq!(coarse.to_price_units(), Ok((50_000_000_000, 5_000_000)));
+
+        let negative = OraclePrice { price: -1, ..sample() };
+        assert_eq!(negative.to_price_units(), Err(PercolatorError::InvalidPrice));
+
+        let huge = OraclePrice { expo: 20, ..sample() };
+        assert_eq!(huge.to_price_units(), Err(PercolatorError::Overflow));
+    }
+
+    #[test]
+    fn test_confidence_bps() {
+        assert_eq!(confidence_bps(50_000, 50), 10);
+        assert_eq!(confidence_bps(50_000, 51), 11);
+        assert_eq!(confidence_bps(0, 1), u64::MAX);
+    }
+}
+
+#[cfg(test)]
+mod commitment_tests {
+    use crate::commitment::*;
+    use crate::types::Side;
+
+    const SALT: [u8; 16] = [7; 16];
+
+    #[test]
+    fn test_sha256_known_vector() {
+        // SHA-256("abc")
+        let digest = sha256(b"abc");
+        assert_eq!(&digest[..4], &[0xba, 0x78, 0x16, 0xbf]);
+        assert_eq!(&digest[28..], &[0xf2, 0x00, 0x15, 0xad]);
+    }
+
+    #[test]
+    fn test_commitment_deterministic() {
+        let a = compute_commitment(9, 1, Side::Buy, 100, 50_000, &SALT);
+        let b = compute_commitment(9, 1, Side::Buy, 100, 50_000, &SALT);
+        assert_eq!(a, b);
+        assert_ne!(a, NO_COMMITMENT);
+    }
+
+    #[test]
+    fn test_commitment_binds_every_field() {
+        let base = compute_commitment(9, 1, Side::Buy, 100, 50_000, &SALT);
+        assert_ne!(base, compute_commitment(10, 1, Side::Buy, 100, 50_000, &SALT));
+        assert_ne!(base, compute_commitment(9, 2, Side::Buy, 100, 50_000, &SALT));
+        assert_ne!(base, compute_commitment(9, 1, Side::Sell, 100, 50_000, &SALT));
+        assert_ne!(base, compute_commitment(9, 1, Side::Buy, 101, 50_000, &SALT));
+        assert_ne!(base, compute_commitment(9, 1, Side::Buy, 100, 50_010, &SALT));
+        assert_ne!(base, compute_commitment(9, 1, Side::Buy, 100, 50_000, &[8; 16]));
+    }
+}
@@ -1,5 +1,6 @@
 //! Common types shared between Router and Slab programs
 
+use crate::error::PercolatorError;
 use pinocchio::pubkey::Pubkey;
 
 /// Maximum number of slabs in the registry
@@ -14,6 +15,9 @@ pub const MAX_ACCOUNTS: usize = 5_000;
 /// Maximum number of orders per slab
 pub const MAX_ORDERS: usize = 30_000;
 
+/// Maximum number of price levels per slab (every level holds an order)
+pub const MAX_PRICE_LEVELS: usize = MAX_ORDERS;
+
 /// Maximum number of positions per slab
 pub const MAX_POSITIONS: usize = 30_000;
 
@@ -41,6 +45,18 @@ pub enum Side {
     Sell = 1,
 }
 
+impl TryFrom<u8> for Side {
+    type Error = PercolatorError;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(Side::Buy),
+            1 => Ok(Side::Sell),
+            _ => Err(PercolatorError::InvalidSide),
+        }
+    }
+}
+
 /// Time in force
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -51,6 +67,108 @@ pub enum TimeInForce {
     FOK = 2, // Fill or kill
 }
 
+impl TryFrom<u8> for TimeInForce {
+    type Error = PercolatorError;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(TimeInForce::GTC),
+            1 => Ok(TimeInForce::IOC),
+            2 => Ok(TimeInForce::FOK),
+            _ => Err(PercolatorError::InvalidTimeInForce),
+        }
+    }
+}
+
+/// Self-trade prevention mode, applied when a taker walks into its own order
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StpMode {
+    /// Pull the taker's own resting order and keep walking
+    #[default]
+    CancelResting = 0,
+    /// Stop the walk; the taker's remaining qty is cancelled
+    CancelTaker = 1,
+    /// Shrink both sides by the overlapping qty without trading
+    DecrementBoth = 2,
+    /// Leave the own order untouched and keep walking past it
+    Skip = 3,
+}
+
+impl TryFrom<u8> for StpMode {
+    type Error = PercolatorError;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(StpMode::CancelResting),
+            1 => Ok(StpMode::CancelTaker),
+            2 => Ok(StpMode::DecrementBoth),
+            3 => Ok(StpMode::Skip),
+            _ => Err(PercolatorError::InvalidInstruction),
+        }
+    }
+}
+
+/// Aggressor Roundtrip Guard mode, applied when a taker's leg offsets its own
+/// earlier aggressive leg in the same batch at non-negative PnL
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArgMode {
+    /// Guard disabled; the ledger is not written
+    Off = 0,
+    /// Charge the `as_fee_k` sandwich tax on the overlapping qty
+    #[default]
+    Tax = 1,
+    /// Drop the overlapping qty from the fill
+    Clip = 2,
+}
+
+impl TryFrom<u8> for ArgMode {
+    type Error = PercolatorError;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(ArgMode::Off),
+            1 => Ok(ArgMode::Tax),
+            2 => Ok(ArgMode::Clip),
+            _ => Err(PercolatorError::InvalidInstruction),
+        }
+    }
+}
+
+/// What commit does when a reserved maker order has shrunk or gone since
+/// reserve
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShrinkPolicy {
+    /// Fail the whole commit with `ReservationStale`
+    #[default]
+    Fail = 0,
+    /// Fill what is still there and report the shortfall (FOK still fails)
+    Reduce = 1,
+}
+
+impl TryFrom<u8> for ShrinkPolicy {
+    type Error = PercolatorError;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(ShrinkPolicy::Fail),
+            1 => Ok(ShrinkPolicy::Reduce),
+            _ => Err(PercolatorError::InvalidInstruction),
+        }
+    }
+}
+
+/// Taker order flag: reject instead of taking liquidity
+pub const ORDER_FLAG_POST_ONLY: u8 = 1 << 0;
+
+/// Taker order flag: clip size so the position can only shrink
+pub const ORDER_FLAG_REDUCE_ONLY: u8 = 1 << 1;
+
+/// All defined taker order flags
+pub const ORDER_FLAGS_ALL: u8 = ORDER_FLAG_POST_ONLY | ORDER_FLAG_REDUCE_ONLY;
+
 /// Maker class
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -83,12 +201,14 @@ pub struct AccountState {
     pub mm: u128,
     /// Head of position linked list
     pub position_head: u32,
+    /// Head of resting order linked list
+    pub order_head: u32,
     /// Account index
     pub index: u32,
     /// Account active flag
     pub active: bool,
     /// Padding
-    pub _padding: [u8; 7],
+    pub _padding: [u8; 3],
 }
 
 /// Instrument definition
@@ -105,6 +225,18 @@ pub struct Instrument {
     pub lot: u64,
     /// Current index price (from oracle)
     pub index_price: u64,
+    /// Oracle price account (all zeros: no oracle, listing price is kept)
+    pub oracle: Pubkey,
+    /// Publish time of the current index price (milliseconds)
+    pub index_publish_ts: u64,
+    /// Mark price used for PnL and margin (index plus clamped premium)
+    pub mark_price: u64,
+    /// Time-averaged book premium over index (basis points)
+    pub premium_ema_bps: i64,
+    /// Book premium at the last sample, held until the next one (basis points)
+    pub premium_sample_bps: i64,
+    /// Last premium sample timestamp
+    pub premium_ts: u64,
     /// Current funding rate (basis points per hour)
     pub funding_rate: i64,
     /// Cumulative funding
@@ -119,6 +251,20 @@ pub struct Instrument {
     pub bids_pending_head: u32,
     /// Pending asks head
     pub asks_pending_head: u32,
+    /// Root of the bids price-level tree
+    pub bids_levels: u32,
+    /// Root of the asks price-level tree
+    pub asks_levels: u32,
+    /// Root of the pending bids price-level tree
+    pub bids_pending_levels: u32,
+    /// Root of the pending asks price-level tree
+    pub asks_pending_levels: u32,
+    /// Head of the long positions list
+    pub longs_head: u32,
+    /// Head of the short positions list
+    pub shorts_head: u32,
+    /// Head of this batch's aggressor ledger entries
+    pub aggressor_head: u32,
     /// Current epoch
     pub epoch: u16,
     /// Instrument index
@@ -127,6 +273,10 @@ pub struct Instrument {
     pub batch_open_ms: u64,
     /// Freeze until timestamp
     pub freeze_until_ms: u64,
+    /// Worst frozen bid price while the freeze is active (0: bids not frozen)
+    pub bids_freeze_px: u64,
+    /// Worst frozen ask price while the freeze is active (0: asks not frozen)
+    pub asks_freeze_px: u64,
 }
 
 /// Order in the book
@@ -163,6 +313,10 @@ pub struct Order {
     pub next: u32,
     /// Previous order in book
     pub prev: u32,
+    /// Next order of the same account
+    pub next_in_account: u32,
+    /// Previous order of the same account
+    pub prev_in_account: u32,
     /// Next in freelist
     pub next_free: u32,
     /// Used flag
@@ -171,6 +325,45 @@ pub struct Order {
     pub _padding: [u8; 3],
 }
 
+/// Price level: the run of same-price orders in one book list
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PriceLevel {
+    /// Level price
+    pub price: u64,
+    /// First (highest priority) order at this price
+    pub head: u32,
+    /// Last order at this price
+    pub tail: u32,
+    /// Orders queued at this price
+    pub count: u32,
+    /// Next in freelist
+    pub next_free: u32,
+    /// Used flag
+    pub used: bool,
+    /// Padding
+    pub _padding: [u8; 7],
+}
+
+/// Inner node of a price-level crit-bit tree
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LevelNode {
+    /// Child references (inner node index, or price level index tagged with `LEVEL_LEAF`)
+    pub children: [u32; 2],
+    /// Highest price bit that differs between the two subtrees
+    pub crit_bit: u8,
+    /// Used flag
+    pub used: bool,
+    /// Padding
+    pub _padding: [u8; 2],
+    /// Next in freelist
+    pub next_free: u32,
+}
+
+/// Tag marking a crit-bit child reference as a price level
+pub const LEVEL_LEAF: u32 = 1 << 31;
+
 /// Position
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default)]
@@ -189,6 +382,10 @@ pub struct Position {
     pub last_funding: i128,
     /// Next position for this account
     pub next_in_account: u32,
+    /// Next position on the same side of this instrument
+    pub next_in_instrument: u32,
+    /// Previous position on the same side of this instrument
+    pub prev_in_instrument: u32,
     /// Position index in pool
     pub index: u32,
     /// Used flag
@@ -203,6 +400,8 @@ pub struct Position {
 pub struct Slice {
     /// Order index being reserved
     pub order_idx: u32,
+    /// Order ID at reserve time (detects a freed and reused order slot)
+    pub order_id: u64,
     /// Quantity reserved from this order
     pub qty: u64,
     /// Next slice in reservation
@@ -229,10 +428,18 @@ pub struct Reservation {
     pub instrument_idx: u16,
     /// Side
     pub side: Side,
-    /// Padding
-    pub _padding: u8,
+    /// Time in force of the taker order
+    pub tif: TimeInForce,
     /// Quantity to fill
     pub qty: u64,
+    /// Quantity the taker still wants (after reduce-only clipping and STP)
+    pub qty_requested: u64,
+    /// Taker order qty as submitted (bound by `commitment_hash`)
+    pub qty_order: u64,
+    /// Taker limit price
+    pub limit_px: u64,
+    /// Mark price at reserve time (kill band reference)
+    pub mark_px: u64,
     /// VWAP price of reserved slices
     pub vwap_px: u64,
     /// Worst price in reservation
@@ -255,8 +462,21 @@ pub struct Reservation {
     pub used: bool,
     /// Committed flag
     pub committed: bool,
+    /// Taker order flags (`ORDER_FLAG_*`)
+    pub flags: u8,
     /// Padding
-    pub _padding2: [u8; 6],
+    pub _padding2: [u8; 5],
+}
+
+/// Kind of a trade ring record
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TradeKind {
+    /// Book fill between a maker order and a taker
+    #[default]
+    Fill = 0,
+    /// Auto-deleveraging close against a bankrupt account
+    Adl = 1,
 }
 
 /// Trade record in ring buffer
@@ -265,16 +485,18 @@ pub struct Reservation {
 pub struct Trade {
     /// Timestamp
     pub ts: u64,
-    /// Maker order ID
+    /// Maker order ID (ADL: index of the deleveraged account)
     pub order_id_maker: u64,
-    /// Taker order ID / route ID
+    /// Taker order ID / route ID (ADL: index of the bankrupt account)
     pub order_id_taker: u64,
     /// Instrument index
     pub instrument_idx: u16,
     /// Side (from taker perspective)
     pub side: Side,
+    /// Fill or ADL record
+    pub kind: TradeKind,
     /// Padding
-    pub _padding: [u8; 5],
+    pub _padding: [u8; 4],
     /// Price
     pub price: u64,
     /// Quantity
@@ -303,10 +525,12 @@ pub struct AggressorEntry {
     pub sell_qty: u64,
     /// Sell notional this batch
     pub sell_notional: u128,
+    /// Next entry on the same instrument
+    pub next_in_instrument: u32,
     /// Used flag
     pub used: bool,
     /// Padding
-    pub _padding: [u8; 7],
+    pub _padding: [u8; 3],
 }
 
 /// Maximum aggressor ledger entries (shared pool, not per account-instrument)
@@ -315,10 +539,11 @@ pub const MAX_AGGRESSOR_ENTRIES: usize = 4_000;
 // Size checks to ensure we're within 10 MB for slab
 const _: () = {
     const fn check_size() {
-        let total = 0
-            + (MAX_ACCOUNTS * core::mem::size_of::<AccountState>())
+        let total = (MAX_ACCOUNTS * core::mem::size_of::<AccountState>())
             + (MAX_INSTRUMENTS * core::mem::size_of::<Instrument>())
             + (MAX_ORDERS * core::mem::size_of::<Order>())
+            + (MAX_PRICE_LEVELS * core::mem::size_of::<PriceLevel>())
+            + (MAX_PRICE_LEVELS * core::mem::size_of::<LevelNode>())
             + (MAX_POSITIONS * core::mem::size_of::<Position>())
             + (MAX_RESERVATIONS * core::mem::size_of::<Reservation>())
             + (MAX_SLICES * core::mem::size_of::<Slice>())
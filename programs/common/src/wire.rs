@@ -0,0 +1,142 @@
+//! Instruction wire-format helpers
+//!
+//! Instruction arguments and return data share one encoding:
+//! - a leading layout version byte, checked by the decoder
+//! - fixed-width little-endian fields, packed without padding
+//! - no trailing bytes
+
+use crate::error::PercolatorError;
+use pinocchio::pubkey::Pubkey;
+
+/// Cursor over little-endian instruction bytes
+pub struct WireReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> WireReader<'a> {
+    /// Create a reader positioned at the start of `data`
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    /// Read and check the layout version byte
+    pub fn read_version(&mut self, expected: u8) -> Result<(), PercolatorError> {
+        if self.read_u8()? != expected {
+            return Err(PercolatorError::InvalidInstruction);
+        }
+        Ok(())
+    }
+
+    /// Read a fixed-size byte array
+    pub fn read_bytes<const N: usize>(&mut self) -> Result<[u8; N], PercolatorError> {
+        let end = self
+            .offset
+            .checked_add(N)
+            .ok_or(PercolatorError::InvalidInstruction)?;
+        let bytes = self
+            .data
+            .get(self.offset..end)
+            .ok_or(PercolatorError::InvalidInstruction)?;
+
+        let mut out = [0u8; N];
+        out.copy_from_slice(bytes);
+        self.offset = end;
+        Ok(out)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, PercolatorError> {
+        Ok(self.read_bytes::<1>()?[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, PercolatorError> {
+        Ok(u16::from_le_bytes(self.read_bytes()?))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, PercolatorError> {
+        Ok(u32::from_le_bytes(self.read_bytes()?))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, PercolatorError> {
+        Ok(u64::from_le_bytes(self.read_bytes()?))
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, PercolatorError> {
+        Ok(i64::from_le_bytes(self.read_bytes()?))
+    }
+
+    pub fn read_u128(&mut self) -> Result<u128, PercolatorError> {
+        Ok(u128::from_le_bytes(self.read_bytes()?))
+    }
+
+    pub fn read_i128(&mut self) -> Result<i128, PercolatorError> {
+        Ok(i128::from_le_bytes(self.read_bytes()?))
+    }
+
+    pub fn read_pubkey(&mut self) -> Result<Pubkey, PercolatorError> {
+        self.read_bytes()
+    }
+
+    /// Ensure every byte was consumed
+    pub fn finish(self) -> Result<(), PercolatorError> {
+        if self.offset != self.data.len() {
+            return Err(PercolatorError::InvalidInstruction);
+        }
+        Ok(())
+    }
+}
+
+/// Cursor writing little-endian fields into a caller-sized buffer
+///
+/// Callers size the buffer from the layout's `LEN` constant, so writes past
+/// the end are a programming error and panic.
+pub struct WireWriter<'a> {
+    buf: &'a mut [u8],
+    offset: usize,
+}
+
+impl<'a> WireWriter<'a> {
+    /// Create a writer positioned at the start of `buf`
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, offset: 0 }
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        let end = self.offset + bytes.len();
+        self.buf[self.offset..end].copy_from_slice(bytes);
+        self.offset = end;
+    }
+
+    pub fn write_u8(&mut self, v: u8) {
+        self.write_bytes(&[v]);
+    }
+
+    pub fn write_u16(&mut self, v: u16) {
+        self.write_bytes(&v.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, v: u32) {
+        self.write_bytes(&v.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, v: u64) {
+        self.write_bytes(&v.to_le_bytes());
+    }
+
+    pub fn write_i64(&mut self, v: i64) {
+        self.write_bytes(&v.to_le_bytes());
+    }
+
+    pub fn write_u128(&mut self, v: u128) {
+        self.write_bytes(&v.to_le_bytes());
+    }
+
+    pub fn write_i128(&mut self, v: i128) {
+        self.write_bytes(&v.to_le_bytes());
+    }
+
+    /// Number of bytes written so far
+    pub fn position(&self) -> usize {
+        self.offset
+    }
+}
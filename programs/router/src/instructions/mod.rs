@@ -1,4 +1,4 @@
-/// Router instruction handlers
+//! Router instruction handlers
 
 pub mod deposit;
 pub mod withdraw;
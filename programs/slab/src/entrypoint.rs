@@ -2,15 +2,27 @@
 
 use pinocchio::{
     account_info::AccountInfo,
+    cpi::set_return_data,
     entrypoint,
     msg,
+    program_error::ProgramError,
     pubkey::Pubkey,
+    sysvars::{clock::Clock, Sysvar},
     ProgramResult,
 };
+use pinocchio_log::log;
 
-use crate::instructions::SlabInstruction;
+use crate::instructions::{
+    self, AddInstrumentArgs, BatchOpenArgs, CancelAllArgs, CancelArgs, CancelOrderArgs, CommitArgs,
+    InitializeArgs, ModifyOrderArgs, PlaceOrderArgs, ReserveArgs, SetParamsArgs, SlabInstruction,
+    SettleFundingArgs, UpdateFundingArgs, UpdateIndexPriceArgs, ReapExpiredArgs,
+    LiquidationCallArgs, TopUpInsuranceArgs,
+};
 use crate::state::SlabState;
-use percolator_common::{PercolatorError, validate_owner, validate_writable, borrow_account_data_mut};
+use percolator_common::{
+    PercolatorError, validate_owner, validate_signer, validate_writable, borrow_account_data,
+    borrow_account_data_mut,
+};
 
 entrypoint!(process_instruction);
 
@@ -34,8 +46,20 @@ pub fn process_instruction(
         3 => SlabInstruction::BatchOpen,
         4 => SlabInstruction::Initialize,
         5 => SlabInstruction::AddInstrument,
+        6 => SlabInstruction::PlaceOrder,
+        7 => SlabInstruction::CancelOrder,
+        8 => SlabInstruction::CancelAll,
+        9 => SlabInstruction::ModifyOrder,
+        10 => SlabInstruction::SetParams,
+        11 => SlabInstruction::UpdateFunding,
+        12 => SlabInstruction::SettleFunding,
+        13 => SlabInstruction::UpdateIndexPrice,
+        14 => SlabInstruction::ReapExpired,
+        15 => SlabInstruction::LiquidationCall,
+        16 => SlabInstruction::QueryInsurance,
+        17 => SlabInstruction::TopUpInsurance,
         _ => {
-            msg!("Error: Unknown instruction: {}", discriminator);
+            log!("Error: Unknown instruction: {}", discriminator);
             return Err(PercolatorError::InvalidInstruction.into());
         }
     };
@@ -66,40 +90,144 @@ pub fn process_instruction(
             msg!("Instruction: AddInstrument");
             process_add_instrument(program_id, accounts, &instruction_data[1..])
         }
+        SlabInstruction::PlaceOrder => {
+            msg!("Instruction: PlaceOrder");
+            process_place_order(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::CancelOrder => {
+            msg!("Instruction: CancelOrder");
+            process_cancel_order(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::CancelAll => {
+            msg!("Instruction: CancelAll");
+            process_cancel_all(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::ModifyOrder => {
+            msg!("Instruction: ModifyOrder");
+            process_modify_order(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::SetParams => {
+            msg!("Instruction: SetParams");
+            process_set_params(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::UpdateFunding => {
+            msg!("Instruction: UpdateFunding");
+            process_update_funding(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::SettleFunding => {
+            msg!("Instruction: SettleFunding");
+            process_settle_funding(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::UpdateIndexPrice => {
+            msg!("Instruction: UpdateIndexPrice");
+            process_update_index_price(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::ReapExpired => {
+            msg!("Instruction: ReapExpired");
+            process_reap_expired(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::LiquidationCall => {
+            msg!("Instruction: LiquidationCall");
+            process_liquidation_call(program_id, accounts, &instruction_data[1..])
+        }
+        SlabInstruction::QueryInsurance => {
+            msg!("Instruction: QueryInsurance");
+            process_query_insurance(program_id, accounts)
+        }
+        SlabInstruction::TopUpInsurance => {
+            msg!("Instruction: TopUpInsurance");
+            process_top_up_insurance(program_id, accounts, &instruction_data[1..])
+        }
     }
 }
 
 // Instruction processors with account validation
 
+/// Borrow the slab state held in account 0
+///
+/// Checks ownership, writability and the header magic/version before
+/// handing out the zero-copy view.
+#[allow(clippy::mut_from_ref)]
+fn load_slab<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo]) -> Result<&'a mut SlabState, ProgramError> {
+    let slab_account = &accounts[0];
+    validate_owner(slab_account, program_id)?;
+    validate_writable(slab_account)?;
+
+    // SAFETY: We've validated ownership and the account should contain SlabState
+    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+    if !slab.header.validate() {
+        msg!("Error: Slab account is not initialized");
+        return Err(PercolatorError::InvalidAccount.into());
+    }
+
+    Ok(slab)
+}
+
+/// Borrow the slab state held in account 0 for reading
+///
+/// Same checks as `load_slab`, except the account need not be writable.
+fn load_slab_readonly<'a>(program_id: &Pubkey, accounts: &'a [AccountInfo]) -> Result<&'a SlabState, ProgramError> {
+    let slab_account = &accounts[0];
+    validate_owner(slab_account, program_id)?;
+
+    // SAFETY: We've validated ownership and the account should contain SlabState
+    let slab = unsafe { borrow_account_data::<SlabState>(slab_account)? };
+    if !slab.header.validate() {
+        msg!("Error: Slab account is not initialized");
+        return Err(PercolatorError::InvalidAccount.into());
+    }
+
+    Ok(slab)
+}
+
+/// Current cluster time in milliseconds
+fn current_ts_ms() -> Result<u64, ProgramError> {
+    let clock = Clock::get()?;
+    Ok((clock.unix_timestamp.max(0) as u64).saturating_mul(1_000))
+}
+
 /// Process reserve instruction
 ///
 /// Expected accounts:
 /// 0. `[writable]` Slab state account
-/// 1. `[signer]` User account
-/// 2. `[]` Router program (for CPI validation)
+/// 1. `[signer]` Owner of `account_idx`
+///
+/// The owner's signature carries through the router's CPI, so routed
+/// reserves are checked the same way as direct ones.
+///
+/// Data: [`ReserveArgs`]. Return data: [`ReserveResult`].
 fn process_reserve(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    // Validate account count
-    if accounts.len() < 1 {
-        msg!("Error: Reserve instruction requires at least 1 account");
+    if accounts.len() < 2 {
+        msg!("Error: Reserve instruction requires at least 2 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
-    // Account 0: Slab state (must be writable and owned by this program)
-    let slab_account = &accounts[0];
-    validate_owner(slab_account, program_id)?;
-    validate_writable(slab_account)?;
+    let slab = load_slab(program_id, accounts)?;
 
-    // Deserialize slab state
-    // SAFETY: We've validated ownership and the account should contain SlabState
-    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+    let owner = &accounts[1];
+    validate_signer(owner)?;
+
+    let args = ReserveArgs::unpack(data)?;
 
-    // TODO: Parse instruction data for reserve parameters
-    // Expected: account_idx (u32), instrument_idx (u16), side (u8), qty (u64),
-    //           limit_px (u64), ttl_ms (u64), commitment_hash ([u8; 32]), route_id (u64)
-    // For now, return Ok to pass compilation
-    let _ = (slab, data);
+    // Reservation expiry is measured from the slab clock
+    slab.header.update_timestamp(current_ts_ms()?);
 
-    msg!("Reserve instruction validated - implementation pending");
+    let result = instructions::process_reserve(
+        slab,
+        owner.key(),
+        args.account_idx,
+        args.instrument_idx,
+        args.side,
+        args.qty,
+        args.limit_px,
+        args.ttl_ms,
+        args.commitment_hash,
+        args.route_id,
+        args.tif,
+        args.flags,
+    )?;
+
+    set_return_data(&result.pack());
     Ok(())
 }
 
@@ -107,23 +235,26 @@ fn process_reserve(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -
 ///
 /// Expected accounts:
 /// 0. `[writable]` Slab state account
-/// 1. `[signer]` User account
+/// 1. `[signer]` Owner of the reserving account
+///
+/// Data: [`CommitArgs`]. Return data: [`CommitResult`].
 fn process_commit(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    if accounts.len() < 1 {
-        msg!("Error: Commit instruction requires at least 1 account");
+    if accounts.len() < 2 {
+        msg!("Error: Commit instruction requires at least 2 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
-    let slab_account = &accounts[0];
-    validate_owner(slab_account, program_id)?;
-    validate_writable(slab_account)?;
+    let slab = load_slab(program_id, accounts)?;
 
-    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+    let owner = &accounts[1];
+    validate_signer(owner)?;
+
+    let args = CommitArgs::unpack(data)?;
 
-    // TODO: Parse data for hold_id (u64) and current_ts (u64)
-    let _ = (slab, data);
+    let result =
+        instructions::process_commit(slab, owner.key(), args.hold_id, &args.salt, current_ts_ms()?)?;
 
-    msg!("Commit instruction validated - implementation pending");
+    set_return_data(&result.pack());
     Ok(())
 }
 
@@ -131,23 +262,23 @@ fn process_commit(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) ->
 ///
 /// Expected accounts:
 /// 0. `[writable]` Slab state account
-/// 1. `[signer]` User account
+/// 1. `[signer]` Owner of the reserving account
+///
+/// Data: [`CancelArgs`].
 fn process_cancel(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    if accounts.len() < 1 {
-        msg!("Error: Cancel instruction requires at least 1 account");
+    if accounts.len() < 2 {
+        msg!("Error: Cancel instruction requires at least 2 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
-    let slab_account = &accounts[0];
-    validate_owner(slab_account, program_id)?;
-    validate_writable(slab_account)?;
+    let slab = load_slab(program_id, accounts)?;
 
-    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+    let owner = &accounts[1];
+    validate_signer(owner)?;
 
-    // TODO: Parse data for hold_id (u64)
-    let _ = (slab, data);
+    let args = CancelArgs::unpack(data)?;
 
-    msg!("Cancel instruction validated - implementation pending");
+    instructions::process_cancel(slab, owner.key(), args.hold_id)?;
     Ok(())
 }
 
@@ -155,34 +286,38 @@ fn process_cancel(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) ->
 ///
 /// Expected accounts:
 /// 0. `[writable]` Slab state account
-/// 1. `[signer]` Authority account (for permissioned batch opening)
+/// 1. `[signer]` Caller (LP owner, or any keeper once the batch window ends)
+///
+/// Data: [`BatchOpenArgs`].
 fn process_batch_open(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    if accounts.len() < 1 {
-        msg!("Error: BatchOpen instruction requires at least 1 account");
+    if accounts.len() < 2 {
+        msg!("Error: BatchOpen instruction requires at least 2 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
-    let slab_account = &accounts[0];
-    validate_owner(slab_account, program_id)?;
-    validate_writable(slab_account)?;
+    let slab = load_slab(program_id, accounts)?;
 
-    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+    let caller = &accounts[1];
+    validate_signer(caller)?;
 
-    // TODO: Parse data for instrument_idx (u16) and current_ts (u64)
-    let _ = (slab, data);
+    let args = BatchOpenArgs::unpack(data)?;
 
-    msg!("BatchOpen instruction validated - implementation pending");
+    let now = current_ts_ms()?;
+    slab.header.update_timestamp(now);
+    instructions::process_batch_open(slab, caller.key(), args.instrument_idx, now)?;
     Ok(())
 }
 
 /// Process initialize instruction
 ///
 /// Expected accounts:
-/// 0. `[writable]` Slab state account (uninitialized)
-/// 1. `[signer]` Payer/authority
+/// 0. `[writable]` Slab state account (uninitialized, pre-allocated by the payer)
+/// 1. `[signer]` LP owner
+///
+/// Data: [`InitializeArgs`].
 fn process_initialize(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    if accounts.len() < 1 {
-        msg!("Error: Initialize instruction requires at least 1 account");
+    if accounts.len() < 2 {
+        msg!("Error: Initialize instruction requires at least 2 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
@@ -190,12 +325,15 @@ fn process_initialize(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]
     validate_owner(slab_account, program_id)?;
     validate_writable(slab_account)?;
 
-    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+    let lp_owner = &accounts[1];
+    validate_signer(lp_owner)?;
 
-    // TODO: Initialize slab state with default values
-    let _ = (slab, data);
+    let args = InitializeArgs::unpack(data)?;
 
-    msg!("Initialize instruction validated - implementation pending");
+    let mut slab_data = slab_account.try_borrow_mut_data()?;
+    instructions::process_initialize(&mut slab_data, program_id, lp_owner.key(), &args)?;
+
+    msg!("Slab initialized");
     Ok(())
 }
 
@@ -203,22 +341,323 @@ fn process_initialize(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]
 ///
 /// Expected accounts:
 /// 0. `[writable]` Slab state account
-/// 1. `[signer]` Authority
+/// 1. `[signer]` LP owner
+///
+/// Data: [`AddInstrumentArgs`]. Return data: [`AddInstrumentResult`].
 fn process_add_instrument(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
-    if accounts.len() < 1 {
-        msg!("Error: AddInstrument instruction requires at least 1 account");
+    if accounts.len() < 2 {
+        msg!("Error: AddInstrument instruction requires at least 2 accounts");
         return Err(PercolatorError::InvalidInstruction.into());
     }
 
-    let slab_account = &accounts[0];
-    validate_owner(slab_account, program_id)?;
-    validate_writable(slab_account)?;
+    let slab = load_slab(program_id, accounts)?;
 
-    let slab = unsafe { borrow_account_data_mut::<SlabState>(slab_account)? };
+    let authority = &accounts[1];
+    validate_signer(authority)?;
+
+    let args = AddInstrumentArgs::unpack(data)?;
+    let result = instructions::process_add_instrument(slab, authority.key(), &args)?;
+
+    set_return_data(&result.pack());
+    Ok(())
+}
+
+/// Process place order instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` Order owner
+///
+/// Data: [`PlaceOrderArgs`]. Return data: [`PlaceOrderResult`].
+fn process_place_order(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: PlaceOrder instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab = load_slab(program_id, accounts)?;
+
+    let owner = &accounts[1];
+    validate_signer(owner)?;
+
+    let args = PlaceOrderArgs::unpack(data)?;
+
+    let now = current_ts_ms()?;
+    slab.header.update_timestamp(now);
+    let result = instructions::process_place_order(slab, owner.key(), &args, now)?;
+
+    set_return_data(&result.pack());
+    Ok(())
+}
+
+/// Process cancel order instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` Order owner
+///
+/// Data: [`CancelOrderArgs`]. Return data: [`CancelOrderResult`].
+fn process_cancel_order(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: CancelOrder instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab = load_slab(program_id, accounts)?;
+
+    let owner = &accounts[1];
+    validate_signer(owner)?;
+
+    let args = CancelOrderArgs::unpack(data)?;
+
+    // Freeze windows are checked against the slab clock
+    slab.header.update_timestamp(current_ts_ms()?);
+    let result = instructions::process_cancel_order(slab, owner.key(), &args)?;
+
+    set_return_data(&result.pack());
+    Ok(())
+}
+
+/// Process cancel all instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` Order owner
+///
+/// Data: [`CancelAllArgs`]. Return data: [`CancelAllResult`].
+fn process_cancel_all(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: CancelAll instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab = load_slab(program_id, accounts)?;
+
+    let owner = &accounts[1];
+    validate_signer(owner)?;
+
+    let args = CancelAllArgs::unpack(data)?;
+
+    // Freeze windows are checked against the slab clock
+    slab.header.update_timestamp(current_ts_ms()?);
+    let result = instructions::process_cancel_all(slab, owner.key(), &args)?;
+
+    set_return_data(&result.pack());
+    Ok(())
+}
+
+/// Process modify order instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` Order owner
+///
+/// Data: [`ModifyOrderArgs`]. Return data: [`ModifyOrderResult`].
+fn process_modify_order(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: ModifyOrder instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab = load_slab(program_id, accounts)?;
+
+    let owner = &accounts[1];
+    validate_signer(owner)?;
+
+    let args = ModifyOrderArgs::unpack(data)?;
+
+    let now = current_ts_ms()?;
+    slab.header.update_timestamp(now);
+    let result = instructions::process_modify_order(slab, owner.key(), &args, now)?;
+
+    set_return_data(&result.pack());
+    Ok(())
+}
+
+/// Process set params instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` LP owner
+///
+/// Data: [`SetParamsArgs`].
+fn process_set_params(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: SetParams instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab = load_slab(program_id, accounts)?;
+
+    let authority = &accounts[1];
+    validate_signer(authority)?;
+
+    let args = SetParamsArgs::unpack(data)?;
+
+    instructions::process_set_params(slab, authority.key(), &args)?;
+    Ok(())
+}
+
+/// Process update funding instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+///
+/// Data: [`UpdateFundingArgs`]. Return data: [`FundingResult`].
+fn process_update_funding(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.is_empty() {
+        msg!("Error: UpdateFunding instruction requires at least 1 account");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab = load_slab(program_id, accounts)?;
+    let args = UpdateFundingArgs::unpack(data)?;
+
+    let now = current_ts_ms()?;
+    slab.header.update_timestamp(now);
+    let result = instructions::process_update_funding(slab, &args, now)?;
+
+    set_return_data(&result.pack());
+    Ok(())
+}
+
+/// Process settle funding instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+///
+/// Data: [`SettleFundingArgs`]. Return data: [`SettleFundingResult`].
+fn process_settle_funding(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.is_empty() {
+        msg!("Error: SettleFunding instruction requires at least 1 account");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab = load_slab(program_id, accounts)?;
+    let args = SettleFundingArgs::unpack(data)?;
+
+    let result = instructions::process_settle_funding(slab, &args)?;
+
+    set_return_data(&result.pack());
+    Ok(())
+}
+
+/// Process update index price instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[]` Oracle price account bound to the instrument
+///
+/// Data: [`UpdateIndexPriceArgs`]. Return data: [`IndexPriceResult`].
+fn process_update_index_price(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: UpdateIndexPrice instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab = load_slab(program_id, accounts)?;
+    let oracle_account = &accounts[1];
+    let args = UpdateIndexPriceArgs::unpack(data)?;
+
+    let now = current_ts_ms()?;
+    slab.header.update_timestamp(now);
+    let oracle_data = oracle_account.try_borrow_data()?;
+    let result = instructions::process_update_index_price(slab, &args, oracle_account.key(), &oracle_data, now)?;
+
+    set_return_data(&result.pack());
+    Ok(())
+}
+
+/// Process reap expired instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+///
+/// Data: [`ReapExpiredArgs`]. Return data: [`ReapExpiredResult`].
+fn process_reap_expired(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.is_empty() {
+        msg!("Error: ReapExpired instruction requires at least 1 account");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab = load_slab(program_id, accounts)?;
+    let args = ReapExpiredArgs::unpack(data)?;
+
+    let now = current_ts_ms()?;
+    slab.header.update_timestamp(now);
+    let result = instructions::process_reap_expired(slab, &args, now)?;
+
+    set_return_data(&result.pack());
+    Ok(())
+}
+
+/// Process liquidation call instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+///
+/// Data: [`LiquidationCallArgs`]. Return data: [`LiquidationResult`].
+fn process_liquidation_call(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.is_empty() {
+        msg!("Error: LiquidationCall instruction requires at least 1 account");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab = load_slab(program_id, accounts)?;
+    let args = LiquidationCallArgs::unpack(data)?;
+
+    let now = current_ts_ms()?;
+    slab.header.update_timestamp(now);
+    let result = instructions::process_liquidation_call(slab, &args, now)?;
+
+    set_return_data(&result.pack());
+    Ok(())
+}
+
+/// Process query insurance instruction
+///
+/// Expected accounts:
+/// 0. `[]` Slab state account
+///
+/// Data: none. Return data: [`InsuranceResult`].
+fn process_query_insurance(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    if accounts.is_empty() {
+        msg!("Error: QueryInsurance instruction requires at least 1 account");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab = load_slab_readonly(program_id, accounts)?;
+    let result = instructions::process_query_insurance(slab);
+
+    set_return_data(&result.pack());
+    Ok(())
+}
+
+/// Process top up insurance instruction
+///
+/// Expected accounts:
+/// 0. `[writable]` Slab state account
+/// 1. `[signer]` LP owner, paying from its slab account
+///
+/// Data: [`TopUpInsuranceArgs`]. Return data: [`InsuranceResult`].
+fn process_top_up_insurance(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if accounts.len() < 2 {
+        msg!("Error: TopUpInsurance instruction requires at least 2 accounts");
+        return Err(PercolatorError::InvalidInstruction.into());
+    }
+
+    let slab = load_slab(program_id, accounts)?;
+
+    let authority = &accounts[1];
+    validate_signer(authority)?;
+
+    let args = TopUpInsuranceArgs::unpack(data)?;
+
+    // Margin of the paying account is priced against fresh oracle readings
+    slab.header.update_timestamp(current_ts_ms()?);
 
-    // TODO: Parse instrument data and add to slab
-    let _ = (slab, data);
+    let result = instructions::process_top_up_insurance(slab, authority.key(), &args)?;
 
-    msg!("AddInstrument instruction validated - implementation pending");
+    set_return_data(&result.pack());
     Ok(())
 }
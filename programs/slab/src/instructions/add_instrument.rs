@@ -0,0 +1,161 @@
+//! Add instrument instruction - lists a new perp contract on the slab
+
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::pubkey::Pubkey;
+
+/// AddInstrument instruction arguments
+///
+/// Wire layout (v2, little-endian):
+/// `version u8 | symbol [u8; 8] | contract_size u64 | tick u64 | lot u64 | index_price u64 |
+///  oracle [u8; 32]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddInstrumentArgs {
+    pub symbol: [u8; 8],
+    pub contract_size: u64,
+    pub tick: u64,
+    pub lot: u64,
+    pub index_price: u64,
+    /// Oracle price account feeding UpdateIndexPrice (zeros for none)
+    pub oracle: Pubkey,
+}
+
+impl AddInstrumentArgs {
+    pub const VERSION: u8 = 2;
+    pub const LEN: usize = 1 + 8 + 8 + 8 + 8 + 8 + 32;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            symbol: r.read_bytes()?,
+            contract_size: r.read_u64()?,
+            tick: r.read_u64()?,
+            lot: r.read_u64()?,
+            index_price: r.read_u64()?,
+            oracle: r.read_pubkey()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_bytes(&self.symbol);
+        w.write_u64(self.contract_size);
+        w.write_u64(self.tick);
+        w.write_u64(self.lot);
+        w.write_u64(self.index_price);
+        w.write_bytes(&self.oracle);
+        buf
+    }
+}
+
+/// AddInstrument result
+///
+/// Written back as program return data. Wire layout (v1, little-endian):
+/// `version u8 | instrument_idx u16`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddInstrumentResult {
+    pub instrument_idx: u16,
+}
+
+impl AddInstrumentResult {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 2;
+
+    /// Encode as return data
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u16(self.instrument_idx);
+        buf
+    }
+
+    /// Decode from return data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let result = Self {
+            instrument_idx: r.read_u16()?,
+        };
+        r.finish()?;
+        Ok(result)
+    }
+}
+
+/// Process add instrument instruction
+///
+/// Only the slab's LP owner may list instruments; anyone else gets
+/// `Unauthorized`. The new instrument starts with empty live and pending
+/// books, zero funding and epoch 0. Mark starts at the listing index price.
+pub fn process_add_instrument(
+    slab: &mut SlabState,
+    authority: &Pubkey,
+    args: &AddInstrumentArgs,
+) -> Result<AddInstrumentResult, PercolatorError> {
+    if authority != &slab.header.lp_owner {
+        return Err(PercolatorError::Unauthorized);
+    }
+
+    // Validate contract spec
+    if args.contract_size == 0 || args.tick == 0 || args.lot == 0 {
+        return Err(PercolatorError::InvalidInstrument);
+    }
+
+    if args.index_price == 0 {
+        return Err(PercolatorError::InvalidPrice);
+    }
+
+    // Symbols must be unique within the slab
+    for i in 0..slab.instrument_count {
+        if slab.instruments[i as usize].symbol == args.symbol {
+            return Err(PercolatorError::InvalidInstrument);
+        }
+    }
+
+    let instrument = Instrument {
+        symbol: args.symbol,
+        contract_size: args.contract_size,
+        tick: args.tick,
+        lot: args.lot,
+        index_price: args.index_price,
+        oracle: args.oracle,
+        index_publish_ts: 0,
+        mark_price: args.index_price,
+        premium_ema_bps: 0,
+        premium_sample_bps: 0,
+        premium_ts: 0,
+        funding_rate: 0,
+        cum_funding: 0,
+        last_funding_ts: 0,
+        bids_head: u32::MAX,
+        asks_head: u32::MAX,
+        bids_pending_head: u32::MAX,
+        asks_pending_head: u32::MAX,
+        bids_levels: u32::MAX,
+        asks_levels: u32::MAX,
+        bids_pending_levels: u32::MAX,
+        asks_pending_levels: u32::MAX,
+        longs_head: u32::MAX,
+        shorts_head: u32::MAX,
+        aggressor_head: u32::MAX,
+        epoch: 0,
+        index: slab.instrument_count,
+        batch_open_ms: 0,
+        freeze_until_ms: 0,
+        bids_freeze_px: 0,
+        asks_freeze_px: 0,
+    };
+
+    let instrument_idx = slab
+        .add_instrument(instrument)
+        .map_err(|_| PercolatorError::PoolFull)?;
+
+    Ok(AddInstrumentResult { instrument_idx })
+}
@@ -1,16 +1,57 @@
 //! Batch open instruction - opens new batch epoch and promotes pending orders
 
-use crate::matching::book::promote_pending;
+use crate::matching::arg::recycle_ledger;
+use crate::matching::book::{clear_freeze, promote_pending};
 use crate::state::SlabState;
 use percolator_common::*;
+use pinocchio::pubkey::Pubkey;
+
+/// BatchOpen instruction arguments
+///
+/// Wire layout (v1, little-endian): `version u8 | instrument_idx u16`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchOpenArgs {
+    pub instrument_idx: u16,
+}
+
+impl BatchOpenArgs {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 2;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            instrument_idx: r.read_u16()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u16(self.instrument_idx);
+        buf
+    }
+}
 
 /// Process batch open instruction
 ///
 /// Opens a new batch epoch for the instrument, promoting all pending orders
 /// to live status. This implements the anti-toxicity mechanism where non-DLP
-/// orders wait one batch before becoming matchable.
+/// orders wait one batch before becoming matchable. Any top-K freeze from the
+/// previous batch is lifted and its aggressor ledger entries are recycled.
+///
+/// The LP owner may open a batch at any time. Anyone else is held to the
+/// batch window and gets `BatchNotDue` until `batch_ms` has passed since the
+/// current batch opened, so freezes cannot be cut short by spamming opens.
 pub fn process_batch_open(
     slab: &mut SlabState,
+    caller: &Pubkey,
     instrument_idx: u16,
     current_ts: u64,
 ) -> Result<(), PercolatorError> {
@@ -19,6 +60,15 @@ pub fn process_batch_open(
         return Err(PercolatorError::InvalidInstruction);
     }
 
+    let batch_open_ms = slab
+        .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?
+        .batch_open_ms;
+    let batch_end = batch_open_ms.saturating_add(slab.header.batch_ms);
+    if *caller != slab.header.lp_owner && current_ts < batch_end {
+        return Err(PercolatorError::BatchNotDue);
+    }
+
     // Get instrument, increment epoch, and update timestamp
     let new_epoch = {
         let instrument = slab
@@ -31,6 +81,10 @@ pub fn process_batch_open(
         instrument.epoch
     };
 
+    // The previous batch's top-K freeze and roundtrip ledger end with it
+    clear_freeze(slab, instrument_idx)?;
+    recycle_ledger(slab, instrument_idx);
+
     // Promote pending orders eligible for this epoch
     promote_pending(slab, instrument_idx, new_epoch)?;
 
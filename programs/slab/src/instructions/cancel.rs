@@ -1,21 +1,58 @@
 //! Cancel instruction - releases a reservation
 
+use crate::instructions::commit::check_hold_owner;
 use crate::matching::commit::cancel;
 use crate::state::SlabState;
 use percolator_common::*;
+use pinocchio::pubkey::Pubkey;
+
+/// Cancel instruction arguments
+///
+/// Wire layout (v1, little-endian): `version u8 | hold_id u64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CancelArgs {
+    pub hold_id: u64,
+}
+
+impl CancelArgs {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 8;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            hold_id: r.read_u64()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u64(self.hold_id);
+        buf
+    }
+}
 
 /// Process cancel instruction
 ///
 /// Releases all slices locked by a reservation, restoring available liquidity
-/// to the order book. Idempotent - safe to call multiple times.
+/// to the order book. Only the owner of the reserving account may cancel.
 pub fn process_cancel(
     slab: &mut SlabState,
+    owner: &Pubkey,
     hold_id: u64,
 ) -> Result<(), PercolatorError> {
     // Validate hold_id
     if hold_id == 0 {
         return Err(PercolatorError::InvalidReservation);
     }
+    check_hold_owner(slab, owner, hold_id)?;
 
     // Delegate to matching engine
     cancel(slab, hold_id)
@@ -0,0 +1,115 @@
+//! Cancel order instructions - pull resting maker orders off the book
+
+use crate::matching::orders::{cancel_all, cancel_order, CancelAllResult, CancelOrderResult};
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::pubkey::Pubkey;
+
+/// CancelOrder instruction arguments
+///
+/// Wire layout (v3, little-endian): `version u8 | order_id u64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CancelOrderArgs {
+    pub order_id: u64,
+}
+
+impl CancelOrderArgs {
+    pub const VERSION: u8 = 3;
+    pub const LEN: usize = 1 + 8;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            order_id: r.read_u64()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u64(self.order_id);
+        buf
+    }
+}
+
+/// CancelAll instruction arguments
+///
+/// Wire layout (v1, little-endian): `version u8 | instrument_idx u16`
+///
+/// `instrument_idx == u16::MAX` cancels across every instrument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CancelAllArgs {
+    pub instrument_idx: u16,
+}
+
+impl CancelAllArgs {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 2;
+
+    /// Sentinel instrument index meaning "all instruments"
+    pub const ALL_INSTRUMENTS: u16 = u16::MAX;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            instrument_idx: r.read_u16()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u16(self.instrument_idx);
+        buf
+    }
+}
+
+/// Process cancel order instruction
+///
+/// Only the account that placed the order may cancel it. Orders partially
+/// locked by an outstanding reservation are rejected.
+pub fn process_cancel_order(
+    slab: &mut SlabState,
+    owner: &Pubkey,
+    args: &CancelOrderArgs,
+) -> Result<CancelOrderResult, PercolatorError> {
+    let account_idx = slab
+        .find_account(owner)
+        .ok_or(PercolatorError::InvalidAccount)?;
+
+    cancel_order(slab, account_idx, args.order_id)
+}
+
+/// Process cancel all instruction
+///
+/// Pulls every live and pending order of the signer, optionally scoped to one
+/// instrument. Locked orders are skipped and reported in the result.
+pub fn process_cancel_all(
+    slab: &mut SlabState,
+    owner: &Pubkey,
+    args: &CancelAllArgs,
+) -> Result<CancelAllResult, PercolatorError> {
+    let account_idx = slab
+        .find_account(owner)
+        .ok_or(PercolatorError::InvalidAccount)?;
+
+    let instrument_idx = if args.instrument_idx == CancelAllArgs::ALL_INSTRUMENTS {
+        None
+    } else {
+        Some(args.instrument_idx)
+    };
+
+    cancel_all(slab, account_idx, instrument_idx)
+}
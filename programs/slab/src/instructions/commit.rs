@@ -3,14 +3,56 @@
 use crate::matching::commit::{commit, CommitResult};
 use crate::state::SlabState;
 use percolator_common::*;
+use pinocchio::pubkey::Pubkey;
+
+/// Commit instruction arguments
+///
+/// Wire layout (v2, little-endian): `version u8 | hold_id u64 | salt [u8; 16]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitArgs {
+    pub hold_id: u64,
+    /// Salt opening the reservation's commitment hash (ignored if none)
+    pub salt: [u8; 16],
+}
+
+impl CommitArgs {
+    pub const VERSION: u8 = 2;
+    pub const LEN: usize = 1 + 8 + 16;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            hold_id: r.read_u64()?,
+            salt: r.read_bytes()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u64(self.hold_id);
+        w.write_bytes(&self.salt);
+        buf
+    }
+}
 
 /// Process commit instruction
 ///
 /// Executes all trades locked by a reservation at the maker prices captured
 /// during the reserve operation. Updates positions, applies fees, and records trades.
+/// The salt is checked against the reservation's commitment hash first.
+/// Only the owner of the reserving account may commit.
 pub fn process_commit(
     slab: &mut SlabState,
+    owner: &Pubkey,
     hold_id: u64,
+    salt: &[u8; 16],
     current_ts: u64,
 ) -> Result<CommitResult, PercolatorError> {
     // Validate timestamp
@@ -18,9 +60,33 @@ pub fn process_commit(
         return Err(PercolatorError::InvalidInstruction);
     }
 
+    check_hold_owner(slab, owner, hold_id)?;
+
     // Update slab current timestamp for consistency
     slab.header.current_ts = current_ts;
 
     // Delegate to matching engine
-    commit(slab, hold_id, current_ts)
+    commit(slab, hold_id, salt, current_ts)
+}
+
+/// Check that `owner` holds the account a reservation was made for
+pub(crate) fn check_hold_owner(
+    slab: &SlabState,
+    owner: &Pubkey,
+    hold_id: u64,
+) -> Result<(), PercolatorError> {
+    let resv_idx = slab
+        .find_reservation(hold_id)
+        .ok_or(PercolatorError::ReservationNotFound)?;
+    let account_idx = slab
+        .reservations
+        .get(resv_idx)
+        .ok_or(PercolatorError::ReservationNotFound)?
+        .account_idx;
+
+    if slab.find_account(owner) != Some(account_idx) {
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    Ok(())
 }
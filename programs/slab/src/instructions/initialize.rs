@@ -0,0 +1,92 @@
+//! Initialize instruction - lays out a fresh slab over account data
+
+use crate::state::{SlabHeader, SlabState};
+use percolator_common::*;
+use pinocchio::pubkey::Pubkey;
+
+/// Initialize instruction arguments
+///
+/// Wire layout (v1, little-endian):
+/// `version u8 | router_id [u8; 32] | imr u64 | mmr u64 | maker_fee i64 |
+///  taker_fee u64 | batch_ms u64 | bump u8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InitializeArgs {
+    pub router_id: Pubkey,
+    pub imr: u64,
+    pub mmr: u64,
+    pub maker_fee: i64,
+    pub taker_fee: u64,
+    pub batch_ms: u64,
+    pub bump: u8,
+}
+
+impl InitializeArgs {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 32 + 8 + 8 + 8 + 8 + 8 + 1;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            router_id: r.read_pubkey()?,
+            imr: r.read_u64()?,
+            mmr: r.read_u64()?,
+            maker_fee: r.read_i64()?,
+            taker_fee: r.read_u64()?,
+            batch_ms: r.read_u64()?,
+            bump: r.read_u8()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_bytes(&self.router_id);
+        w.write_u64(self.imr);
+        w.write_u64(self.mmr);
+        w.write_i64(self.maker_fee);
+        w.write_u64(self.taker_fee);
+        w.write_u64(self.batch_ms);
+        w.write_u8(self.bump);
+        buf
+    }
+}
+
+/// Process initialize instruction
+///
+/// Validates the risk parameters and builds the slab in place over the raw
+/// account bytes. The signer becomes the slab's LP owner.
+pub fn process_initialize<'a>(
+    data: &'a mut [u8],
+    program_id: &Pubkey,
+    lp_owner: &Pubkey,
+    args: &InitializeArgs,
+) -> Result<&'a mut SlabState, PercolatorError> {
+    // Maintenance margin must be positive and no stricter than initial margin
+    if args.mmr == 0 || args.imr < args.mmr || args.imr > 10_000 {
+        return Err(PercolatorError::InvalidRiskParams);
+    }
+
+    if args.batch_ms == 0 {
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    let header = SlabHeader::new(
+        *program_id,
+        *lp_owner,
+        args.router_id,
+        args.imr,
+        args.mmr,
+        args.maker_fee,
+        args.taker_fee,
+        args.batch_ms,
+        args.bump,
+    );
+
+    SlabState::init_in_place(data, header)
+}
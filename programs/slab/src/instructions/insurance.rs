@@ -0,0 +1,91 @@
+//! Insurance instructions - fund balance query and LP owner top-up
+
+use crate::matching::insurance::{insurance_state, InsuranceResult};
+use crate::matching::risk::{calculate_equity, calculate_margin_requirements};
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::pubkey::Pubkey;
+
+/// TopUpInsurance instruction arguments
+///
+/// Wire layout (v1, little-endian): `version u8 | amount u128`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TopUpInsuranceArgs {
+    pub amount: u128,
+}
+
+impl TopUpInsuranceArgs {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 16;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            amount: r.read_u128()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u128(self.amount);
+        buf
+    }
+}
+
+/// Process query insurance instruction
+///
+/// Read-only: reports the fund balance and total paid out.
+pub fn process_query_insurance(slab: &SlabState) -> InsuranceResult {
+    insurance_state(slab)
+}
+
+/// Process top up insurance instruction
+///
+/// Only the slab's LP owner may add to the fund (`Unauthorized`), and only
+/// from its own slab account: `amount` is moved out of that account's cash,
+/// which must hold it and still cover the account's IM afterwards
+/// (`InsufficientFunds`).
+pub fn process_top_up_insurance(
+    slab: &mut SlabState,
+    authority: &Pubkey,
+    args: &TopUpInsuranceArgs,
+) -> Result<InsuranceResult, PercolatorError> {
+    if authority != &slab.header.lp_owner {
+        return Err(PercolatorError::Unauthorized);
+    }
+    if args.amount == 0 {
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    let account_idx = slab.find_account(authority).ok_or(PercolatorError::InvalidAccount)?;
+    let amount = i128::try_from(args.amount).map_err(|_| PercolatorError::Overflow)?;
+    let balance = slab
+        .header
+        .insurance_balance
+        .checked_add(args.amount)
+        .ok_or(PercolatorError::Overflow)?;
+
+    let cash = slab
+        .get_account(account_idx)
+        .ok_or(PercolatorError::InvalidAccount)?
+        .cash;
+    let equity = calculate_equity(slab, account_idx)?;
+    let (im, _) = calculate_margin_requirements(slab, account_idx)?;
+    if cash < amount || equity.saturating_sub(amount) < im as i128 {
+        return Err(PercolatorError::InsufficientFunds);
+    }
+
+    if let Some(account) = slab.get_account_mut(account_idx) {
+        account.cash -= amount;
+    }
+    slab.header.insurance_balance = balance;
+
+    Ok(insurance_state(slab))
+}
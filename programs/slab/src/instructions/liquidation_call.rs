@@ -0,0 +1,58 @@
+//! Liquidation call instruction - permissionless slab-level liquidation
+
+use crate::matching::liquidation::{liquidate, LiquidationResult};
+use crate::state::SlabState;
+use percolator_common::*;
+
+/// LiquidationCall instruction arguments
+///
+/// Wire layout (v1, little-endian): `version u8 | account_idx u32`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiquidationCallArgs {
+    /// Account to liquidate
+    pub account_idx: u32,
+}
+
+impl LiquidationCallArgs {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 4;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            account_idx: r.read_u32()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u32(self.account_idx);
+        buf
+    }
+}
+
+/// Process liquidation call instruction
+///
+/// Anyone may call: accounts at or above maintenance margin are refused with
+/// `NotLiquidatable`, and the sweep only closes what restoring it needs.
+pub fn process_liquidation_call(
+    slab: &mut SlabState,
+    args: &LiquidationCallArgs,
+    current_ts: u64,
+) -> Result<LiquidationResult, PercolatorError> {
+    if current_ts == 0 {
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    // The sweeps reserve and commit against the slab clock
+    slab.header.current_ts = current_ts;
+
+    liquidate(slab, args.account_idx, current_ts)
+}
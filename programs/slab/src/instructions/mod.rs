@@ -2,11 +2,35 @@ pub mod reserve;
 pub mod commit;
 pub mod cancel;
 pub mod batch_open;
+pub mod initialize;
+pub mod add_instrument;
+pub mod place_order;
+pub mod cancel_order;
+pub mod modify_order;
+pub mod set_params;
+pub mod update_funding;
+pub mod settle_funding;
+pub mod update_index_price;
+pub mod reap_expired;
+pub mod liquidation_call;
+pub mod insurance;
 
 pub use reserve::*;
 pub use commit::*;
 pub use cancel::*;
 pub use batch_open::*;
+pub use initialize::*;
+pub use add_instrument::*;
+pub use place_order::*;
+pub use cancel_order::*;
+pub use modify_order::*;
+pub use set_params::*;
+pub use update_funding::*;
+pub use settle_funding::*;
+pub use update_index_price::*;
+pub use reap_expired::*;
+pub use liquidation_call::*;
+pub use insurance::*;
 
 /// Instruction discriminator
 #[repr(u8)]
@@ -24,4 +48,28 @@ pub enum SlabInstruction {
     Initialize = 4,
     /// Add instrument
     AddInstrument = 5,
+    /// Place resting limit order
+    PlaceOrder = 6,
+    /// Cancel resting order
+    CancelOrder = 7,
+    /// Cancel all resting orders of an account
+    CancelAll = 8,
+    /// Amend resting order price/qty
+    ModifyOrder = 9,
+    /// Update a per-slab parameter (LP owner only)
+    SetParams = 10,
+    /// Accrue funding (permissionless crank)
+    UpdateFunding = 11,
+    /// Realize accrued funding into account cash
+    SettleFunding = 12,
+    /// Refresh an instrument's index price from its oracle
+    UpdateIndexPrice = 13,
+    /// Release expired reservations (permissionless crank)
+    ReapExpired = 14,
+    /// Liquidate an account below maintenance margin (permissionless)
+    LiquidationCall = 15,
+    /// Report the insurance fund balance
+    QueryInsurance = 16,
+    /// Add to the insurance fund (LP owner only)
+    TopUpInsurance = 17,
 }
@@ -0,0 +1,70 @@
+//! Modify order instruction - amends a resting maker order
+
+use crate::matching::orders::{modify_order, ModifyOrderResult};
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::pubkey::Pubkey;
+
+/// ModifyOrder instruction arguments
+///
+/// Wire layout (v3, little-endian):
+/// `version u8 | order_id u64 | new_price u64 | new_qty u64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModifyOrderArgs {
+    pub order_id: u64,
+    pub new_price: u64,
+    pub new_qty: u64,
+}
+
+impl ModifyOrderArgs {
+    pub const VERSION: u8 = 3;
+    pub const LEN: usize = 1 + 8 + 8 + 8;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            order_id: r.read_u64()?,
+            new_price: r.read_u64()?,
+            new_qty: r.read_u64()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u64(self.order_id);
+        w.write_u64(self.new_price);
+        w.write_u64(self.new_qty);
+        buf
+    }
+}
+
+/// Process modify order instruction
+///
+/// Only the account that placed the order may amend it. Pass the current
+/// price to resize only; see [`modify_order`] for the priority rules.
+pub fn process_modify_order(
+    slab: &mut SlabState,
+    owner: &Pubkey,
+    args: &ModifyOrderArgs,
+    current_ts: u64,
+) -> Result<ModifyOrderResult, PercolatorError> {
+    let account_idx = slab
+        .find_account(owner)
+        .ok_or(PercolatorError::InvalidAccount)?;
+
+    modify_order(
+        slab,
+        account_idx,
+        args.order_id,
+        args.new_price,
+        args.new_qty,
+        current_ts,
+    )
+}
@@ -0,0 +1,74 @@
+//! Place order instruction - rests a maker limit order on the book
+
+use crate::matching::orders::{place_order, PlaceOrderResult};
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::pubkey::Pubkey;
+
+/// PlaceOrder instruction arguments
+///
+/// Wire layout (v1, little-endian):
+/// `version u8 | instrument_idx u16 | side u8 | price u64 | qty u64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlaceOrderArgs {
+    pub instrument_idx: u16,
+    pub side: Side,
+    pub price: u64,
+    pub qty: u64,
+}
+
+impl PlaceOrderArgs {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 2 + 1 + 8 + 8;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            instrument_idx: r.read_u16()?,
+            side: Side::try_from(r.read_u8()?)?,
+            price: r.read_u64()?,
+            qty: r.read_u64()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u16(self.instrument_idx);
+        w.write_u8(self.side as u8);
+        w.write_u64(self.price);
+        w.write_u64(self.qty);
+        buf
+    }
+}
+
+/// Process place order instruction
+///
+/// Resolves (or opens) the owner's slab account and rests a limit order for it.
+/// The order's maker class follows the account's DLP status.
+pub fn process_place_order(
+    slab: &mut SlabState,
+    owner: &Pubkey,
+    args: &PlaceOrderArgs,
+    current_ts: u64,
+) -> Result<PlaceOrderResult, PercolatorError> {
+    let account_idx = slab
+        .find_or_create_account(owner)
+        .map_err(|_| PercolatorError::PoolFull)?;
+
+    place_order(
+        slab,
+        account_idx,
+        args.instrument_idx,
+        args.side,
+        args.price,
+        args.qty,
+        current_ts,
+    )
+}
@@ -0,0 +1,55 @@
+//! Reap expired instruction - permissionless reservation cleanup
+
+use crate::matching::commit::{reap_expired, ReapExpiredResult};
+use crate::state::SlabState;
+use percolator_common::*;
+
+/// ReapExpired instruction arguments
+///
+/// Wire layout (v1, little-endian): `version u8 | max_scan u32`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReapExpiredArgs {
+    /// Number of reservation slots to inspect
+    pub max_scan: u32,
+}
+
+impl ReapExpiredArgs {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 4;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            max_scan: r.read_u32()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u32(self.max_scan);
+        buf
+    }
+}
+
+/// Process reap expired instruction
+///
+/// Anyone may reap: only holds past their expiry are released, and those can
+/// no longer be committed.
+pub fn process_reap_expired(
+    slab: &mut SlabState,
+    args: &ReapExpiredArgs,
+    current_ts: u64,
+) -> Result<ReapExpiredResult, PercolatorError> {
+    if current_ts == 0 || args.max_scan == 0 {
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    reap_expired(slab, args.max_scan, current_ts)
+}
@@ -3,13 +3,80 @@
 use crate::matching::reserve::{reserve, ReserveResult};
 use crate::state::SlabState;
 use percolator_common::*;
+use pinocchio::pubkey::Pubkey;
+
+/// Reserve instruction arguments
+///
+/// Wire layout (v2, little-endian):
+/// `version u8 | account_idx u32 | instrument_idx u16 | side u8 | qty u64 |
+///  limit_px u64 | ttl_ms u64 | commitment_hash [u8; 32] | route_id u64 |
+///  tif u8 | flags u8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReserveArgs {
+    pub account_idx: u32,
+    pub instrument_idx: u16,
+    pub side: Side,
+    pub qty: u64,
+    pub limit_px: u64,
+    pub ttl_ms: u64,
+    pub commitment_hash: [u8; 32],
+    pub route_id: u64,
+    pub tif: TimeInForce,
+    /// `ORDER_FLAG_*` bits
+    pub flags: u8,
+}
+
+impl ReserveArgs {
+    pub const VERSION: u8 = 2;
+    pub const LEN: usize = 1 + 4 + 2 + 1 + 8 + 8 + 8 + 32 + 8 + 1 + 1;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            account_idx: r.read_u32()?,
+            instrument_idx: r.read_u16()?,
+            side: Side::try_from(r.read_u8()?)?,
+            qty: r.read_u64()?,
+            limit_px: r.read_u64()?,
+            ttl_ms: r.read_u64()?,
+            commitment_hash: r.read_bytes()?,
+            route_id: r.read_u64()?,
+            tif: TimeInForce::try_from(r.read_u8()?)?,
+            flags: r.read_u8()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u32(self.account_idx);
+        w.write_u16(self.instrument_idx);
+        w.write_u8(self.side as u8);
+        w.write_u64(self.qty);
+        w.write_u64(self.limit_px);
+        w.write_u64(self.ttl_ms);
+        w.write_bytes(&self.commitment_hash);
+        w.write_u64(self.route_id);
+        w.write_u8(self.tif as u8);
+        w.write_u8(self.flags);
+        buf
+    }
+}
 
 /// Process reserve instruction
 ///
 /// Walks the contra side of the order book, locks slices up to the quantity limit,
 /// and returns reservation details including VWAP, worst price, and max charge.
+/// `owner` must hold `account_idx`.
 pub fn process_reserve(
     slab: &mut SlabState,
+    owner: &Pubkey,
     account_idx: u32,
     instrument_idx: u16,
     side: Side,
@@ -18,7 +85,13 @@ pub fn process_reserve(
     ttl_ms: u64,
     commitment_hash: [u8; 32],
     route_id: u64,
+    tif: TimeInForce,
+    flags: u8,
 ) -> Result<ReserveResult, PercolatorError> {
+    if slab.find_account(owner) != Some(account_idx) {
+        return Err(PercolatorError::InvalidAccount);
+    }
+
     // Validate basic parameters
     if ttl_ms == 0 {
         return Err(PercolatorError::InvalidInstruction);
@@ -43,5 +116,7 @@ pub fn process_reserve(
         capped_ttl,
         commitment_hash,
         route_id,
+        tif,
+        flags,
     )
 }
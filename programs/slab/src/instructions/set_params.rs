@@ -0,0 +1,235 @@
+//! Set params instruction - LP owner tunes per-slab parameters
+
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::pubkey::Pubkey;
+
+/// Tunable slab parameter
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlabParam {
+    /// Self-trade prevention mode (`StpMode` discriminant)
+    StpMode = 0,
+    /// Funding interval (milliseconds, non-zero)
+    FundingIntervalMs = 1,
+    /// Cap on |funding| accrued per interval (basis points of index)
+    MaxFundingRateBps = 2,
+    /// Maximum index price age (milliseconds, non-zero)
+    MaxOracleStalenessMs = 3,
+    /// Maximum oracle confidence (basis points of price)
+    MaxOracleConfBps = 4,
+    /// Cap on |mark premium| over index (basis points)
+    MarkPremiumClampBps = 5,
+    /// Mark premium averaging window (milliseconds, non-zero)
+    MarkEmaWindowMs = 6,
+    /// Kill band (basis points)
+    KillBandBps = 7,
+    /// Top-of-book levels frozen against the contra queue
+    FreezeLevels = 8,
+    /// JIT penalty on/off (0 or 1)
+    JitPenaltyOn = 9,
+    /// Minimum resting time for maker rebate (milliseconds)
+    MakerRebateMinMs = 10,
+    /// Anti-sandwich fee factor (basis points)
+    AsFeeK = 11,
+    /// Aggressor Roundtrip Guard mode (`ArgMode` discriminant)
+    ArgMode = 12,
+    /// Commit policy for shrunk maker orders (`ShrinkPolicy` discriminant)
+    ShrinkPolicy = 13,
+    /// Liquidation price band around mark (basis points)
+    LiqBandBps = 14,
+    /// Liquidation fee (basis points of closed notional)
+    LiqFeeBps = 15,
+    /// Insurance share of liquidation fees (basis points)
+    InsuranceLiqShareBps = 16,
+    /// Insurance share of taker fees (basis points)
+    InsuranceTakerShareBps = 17,
+}
+
+impl TryFrom<u8> for SlabParam {
+    type Error = PercolatorError;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            0 => Ok(SlabParam::StpMode),
+            1 => Ok(SlabParam::FundingIntervalMs),
+            2 => Ok(SlabParam::MaxFundingRateBps),
+            3 => Ok(SlabParam::MaxOracleStalenessMs),
+            4 => Ok(SlabParam::MaxOracleConfBps),
+            5 => Ok(SlabParam::MarkPremiumClampBps),
+            6 => Ok(SlabParam::MarkEmaWindowMs),
+            7 => Ok(SlabParam::KillBandBps),
+            8 => Ok(SlabParam::FreezeLevels),
+            9 => Ok(SlabParam::JitPenaltyOn),
+            10 => Ok(SlabParam::MakerRebateMinMs),
+            11 => Ok(SlabParam::AsFeeK),
+            12 => Ok(SlabParam::ArgMode),
+            13 => Ok(SlabParam::ShrinkPolicy),
+            14 => Ok(SlabParam::LiqBandBps),
+            15 => Ok(SlabParam::LiqFeeBps),
+            16 => Ok(SlabParam::InsuranceLiqShareBps),
+            17 => Ok(SlabParam::InsuranceTakerShareBps),
+            _ => Err(PercolatorError::InvalidInstruction),
+        }
+    }
+}
+
+/// SetParams instruction arguments
+///
+/// Wire layout (v1, little-endian): `version u8 | param u8 | value u64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetParamsArgs {
+    pub param: SlabParam,
+    pub value: u64,
+}
+
+impl SetParamsArgs {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 1 + 8;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            param: SlabParam::try_from(r.read_u8()?)?,
+            value: r.read_u64()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u8(self.param as u8);
+        w.write_u64(self.value);
+        buf
+    }
+}
+
+/// Process set params instruction
+///
+/// Only the slab's LP owner may change parameters; anyone else gets
+/// `Unauthorized`. Out-of-range values are rejected with `InvalidRiskParams`
+/// and leave the header untouched.
+pub fn process_set_params(
+    slab: &mut SlabState,
+    authority: &Pubkey,
+    args: &SetParamsArgs,
+) -> Result<(), PercolatorError> {
+    if authority != &slab.header.lp_owner {
+        return Err(PercolatorError::Unauthorized);
+    }
+
+    let header = &mut slab.header;
+    let value = args.value;
+
+    match args.param {
+        SlabParam::StpMode => {
+            let mode = u8::try_from(value).map_err(|_| PercolatorError::InvalidRiskParams)?;
+            header.stp_mode =
+                StpMode::try_from(mode).map_err(|_| PercolatorError::InvalidRiskParams)?;
+        }
+        SlabParam::FundingIntervalMs => {
+            if value == 0 {
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            header.funding_interval_ms = value;
+        }
+        SlabParam::MaxFundingRateBps => {
+            if value > 10_000 {
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            header.max_funding_rate_bps = value;
+        }
+        SlabParam::MaxOracleStalenessMs => {
+            if value == 0 {
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            header.max_oracle_staleness_ms = value;
+        }
+        SlabParam::MaxOracleConfBps => {
+            if value > 10_000 {
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            header.max_oracle_conf_bps = value;
+        }
+        SlabParam::MarkPremiumClampBps => {
+            if value > 10_000 {
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            header.mark_premium_clamp_bps = value;
+        }
+        SlabParam::MarkEmaWindowMs => {
+            if value == 0 {
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            header.mark_ema_window_ms = value;
+        }
+        SlabParam::KillBandBps => {
+            if value > 10_000 {
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            header.kill_band_bps = value;
+        }
+        SlabParam::FreezeLevels => {
+            header.freeze_levels =
+                u16::try_from(value).map_err(|_| PercolatorError::InvalidRiskParams)?;
+        }
+        SlabParam::JitPenaltyOn => {
+            header.jit_penalty_on = match value {
+                0 => false,
+                1 => true,
+                _ => return Err(PercolatorError::InvalidRiskParams),
+            };
+        }
+        SlabParam::MakerRebateMinMs => {
+            header.maker_rebate_min_ms = value;
+        }
+        SlabParam::AsFeeK => {
+            if value > 10_000 {
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            header.as_fee_k = value;
+        }
+        SlabParam::ArgMode => {
+            let mode = u8::try_from(value).map_err(|_| PercolatorError::InvalidRiskParams)?;
+            header.arg_mode =
+                ArgMode::try_from(mode).map_err(|_| PercolatorError::InvalidRiskParams)?;
+        }
+        SlabParam::ShrinkPolicy => {
+            let policy = u8::try_from(value).map_err(|_| PercolatorError::InvalidRiskParams)?;
+            header.shrink_policy =
+                ShrinkPolicy::try_from(policy).map_err(|_| PercolatorError::InvalidRiskParams)?;
+        }
+        SlabParam::LiqBandBps => {
+            if value > 10_000 {
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            header.liq_band_bps = value;
+        }
+        SlabParam::LiqFeeBps => {
+            if value > 10_000 {
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            header.liq_fee_bps = value;
+        }
+        SlabParam::InsuranceLiqShareBps => {
+            if value > 10_000 {
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            header.insurance_liq_share_bps = value;
+        }
+        SlabParam::InsuranceTakerShareBps => {
+            if value > 10_000 {
+                return Err(PercolatorError::InvalidRiskParams);
+            }
+            header.insurance_taker_share_bps = value;
+        }
+    }
+
+    Ok(())
+}
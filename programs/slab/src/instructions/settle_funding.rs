@@ -0,0 +1,86 @@
+//! Settle funding instruction - realizes accrued funding into account cash
+
+use crate::matching::funding::settle_funding;
+use crate::state::SlabState;
+use percolator_common::*;
+
+/// SettleFunding instruction arguments
+///
+/// Wire layout (v1, little-endian): `version u8 | account_idx u32`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SettleFundingArgs {
+    pub account_idx: u32,
+}
+
+impl SettleFundingArgs {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 4;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            account_idx: r.read_u32()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u32(self.account_idx);
+        buf
+    }
+}
+
+/// SettleFunding result
+///
+/// Written back as program return data. Wire layout (v1, little-endian):
+/// `version u8 | funding_paid i128`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SettleFundingResult {
+    /// Funding debited from cash (negative when the account received funding)
+    pub funding_paid: i128,
+}
+
+impl SettleFundingResult {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 16;
+
+    /// Encode as return data
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_i128(self.funding_paid);
+        buf
+    }
+
+    /// Decode from return data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let result = Self {
+            funding_paid: r.read_i128()?,
+        };
+        r.finish()?;
+        Ok(result)
+    }
+}
+
+/// Process settle funding instruction
+///
+/// Permissionless: settlement moves accrued funding from unrealized into
+/// cash without changing the account's equity.
+pub fn process_settle_funding(
+    slab: &mut SlabState,
+    args: &SettleFundingArgs,
+) -> Result<SettleFundingResult, PercolatorError> {
+    let funding_paid = settle_funding(slab, args.account_idx)?;
+
+    Ok(SettleFundingResult { funding_paid })
+}
@@ -0,0 +1,54 @@
+//! Update funding instruction - permissionless funding crank
+
+use crate::matching::funding::{update_funding, FundingResult};
+use crate::state::SlabState;
+use percolator_common::*;
+
+/// UpdateFunding instruction arguments
+///
+/// Wire layout (v1, little-endian): `version u8 | instrument_idx u16`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateFundingArgs {
+    pub instrument_idx: u16,
+}
+
+impl UpdateFundingArgs {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 2;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            instrument_idx: r.read_u16()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u16(self.instrument_idx);
+        buf
+    }
+}
+
+/// Process update funding instruction
+///
+/// Anyone may crank funding; the interval gate in the matching engine makes
+/// repeated calls within one interval fail rather than double-accrue.
+pub fn process_update_funding(
+    slab: &mut SlabState,
+    args: &UpdateFundingArgs,
+    current_ts: u64,
+) -> Result<FundingResult, PercolatorError> {
+    if current_ts == 0 {
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    update_funding(slab, args.instrument_idx, current_ts)
+}
@@ -0,0 +1,57 @@
+//! Update index price instruction - permissionless oracle crank
+
+use crate::matching::oracle::{update_index_price, IndexPriceResult};
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::pubkey::Pubkey;
+
+/// UpdateIndexPrice instruction arguments
+///
+/// Wire layout (v1, little-endian): `version u8 | instrument_idx u16`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateIndexPriceArgs {
+    pub instrument_idx: u16,
+}
+
+impl UpdateIndexPriceArgs {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 2;
+
+    /// Decode arguments from instruction data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let args = Self {
+            instrument_idx: r.read_u16()?,
+        };
+        r.finish()?;
+        Ok(args)
+    }
+
+    /// Encode arguments as instruction data (without discriminator)
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u16(self.instrument_idx);
+        buf
+    }
+}
+
+/// Process update index price instruction
+///
+/// Anyone may push a reading; only the oracle account bound to the
+/// instrument at listing is accepted.
+pub fn process_update_index_price(
+    slab: &mut SlabState,
+    args: &UpdateIndexPriceArgs,
+    oracle_key: &Pubkey,
+    oracle_data: &[u8],
+    current_ts: u64,
+) -> Result<IndexPriceResult, PercolatorError> {
+    if current_ts == 0 {
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
+    update_index_price(slab, args.instrument_idx, oracle_key, oracle_data, current_ts)
+}
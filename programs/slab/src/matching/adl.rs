@@ -0,0 +1,175 @@
+//! Auto-deleveraging - close a bankrupt account's leftover positions against
+//! the most profitable, most leveraged opposing positions
+//!
+//! Last resort after liquidation: when the book within the band cannot absorb
+//! a position and the insurance fund cannot cover the loss, opposing traders
+//! are closed out at the bankruptcy price, so the loss is taken out of their
+//! unrealized profit instead of leaving the slab short.
+
+use crate::matching::commit::update_position;
+use crate::matching::risk::{calculate_equity, get_position_qty, update_account_margin};
+use crate::state::SlabState;
+use percolator_common::*;
+
+/// Force-close all of a bankrupt account's positions by ADL
+///
+/// Each position is closed at its bankruptcy price, the price at which the
+/// account's equity reaches zero, against opposing positions in rank order
+/// (see `adl_score`). Every close is recorded in the trade ring as a
+/// `TradeKind::Adl` record naming both accounts. Returns the qty closed.
+pub fn auto_deleverage(
+    slab: &mut SlabState,
+    account_idx: u32,
+    current_ts: u64,
+) -> Result<u64, PercolatorError> {
+    let mut closed = 0u64;
+
+    let mut pos_idx = slab
+        .get_account(account_idx)
+        .ok_or(PercolatorError::InvalidAccount)?
+        .position_head;
+
+    while pos_idx != u32::MAX {
+        // Closing the position frees its slot, so step past it first
+        let (instrument_idx, next) = {
+            let pos = slab
+                .positions
+                .get(pos_idx)
+                .ok_or(PercolatorError::PositionNotFound)?;
+            (pos.instrument_idx, pos.next_in_account)
+        };
+
+        let cum_funding = slab
+            .get_instrument(instrument_idx)
+            .ok_or(PercolatorError::InvalidInstrument)?
+            .cum_funding;
+
+        loop {
+            let position_qty = get_position_qty(slab, account_idx, instrument_idx);
+            if position_qty == 0 {
+                break;
+            }
+
+            let Some(counter_idx) = top_ranked(slab, account_idx, instrument_idx, position_qty < 0)? else {
+                break;
+            };
+            let (counter_account, counter_qty) = {
+                let pos = slab
+                    .positions
+                    .get(counter_idx)
+                    .ok_or(PercolatorError::PositionNotFound)?;
+                (pos.account_idx, pos.qty)
+            };
+
+            let price = bankruptcy_price(slab, account_idx, instrument_idx, position_qty)?;
+            let qty = core::cmp::min(position_qty.unsigned_abs(), counter_qty.unsigned_abs());
+            let (side, qty_delta) = if position_qty > 0 {
+                (Side::Sell, -(qty as i64))
+            } else {
+                (Side::Buy, qty as i64)
+            };
+
+            update_position(slab, account_idx, instrument_idx, qty_delta, price, cum_funding)?;
+            update_position(slab, counter_account, instrument_idx, -qty_delta, price, cum_funding)?;
+            update_account_margin(slab, account_idx)?;
+            update_account_margin(slab, counter_account)?;
+
+            slab.record_trade(Trade {
+                ts: current_ts,
+                order_id_maker: counter_account as u64,
+                order_id_taker: account_idx as u64,
+                instrument_idx,
+                side,
+                kind: TradeKind::Adl,
+                _padding: [0; 4],
+                price,
+                qty,
+                hash: [0; 32],
+                reveal_ms: current_ts,
+            });
+
+            closed = closed.saturating_add(qty);
+        }
+
+        pos_idx = next;
+    }
+
+    Ok(closed)
+}
+
+/// Price at which closing `position_qty` leaves the account's equity at zero
+///
+/// The whole negative equity is charged to this position. Rounding favours
+/// the counterparty; any residue stays with the bankrupt account.
+fn bankruptcy_price(
+    slab: &SlabState,
+    account_idx: u32,
+    instrument_idx: u16,
+    position_qty: i64,
+) -> Result<u64, PercolatorError> {
+    let mark_px = slab
+        .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?
+        .mark_price;
+    let equity = calculate_equity(slab, account_idx)?;
+
+    let price = (mark_px as i128).saturating_sub(equity / position_qty as i128);
+    Ok(price.clamp(1, u64::MAX as i128) as u64)
+}
+
+/// Highest ranked position on the requested side of an instrument
+///
+/// Walks only that side's position list, so the cost grows with the number
+/// of opposing positions rather than the size of the pool. The bankrupt
+/// account's own positions are never picked.
+fn top_ranked(
+    slab: &SlabState,
+    bankrupt_idx: u32,
+    instrument_idx: u16,
+    long: bool,
+) -> Result<Option<u32>, PercolatorError> {
+    let instrument = slab
+        .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?;
+    let mut pos_idx = if long {
+        instrument.longs_head
+    } else {
+        instrument.shorts_head
+    };
+    let mut best: Option<(u32, i128)> = None;
+
+    while pos_idx != u32::MAX {
+        let pos = slab
+            .positions
+            .get(pos_idx)
+            .ok_or(PercolatorError::PositionNotFound)?;
+
+        if pos.account_idx != bankrupt_idx {
+            let score = adl_score(slab, pos)?;
+            if best.map_or(true, |(_, best_score)| score > best_score) {
+                best = Some((pos_idx, score));
+            }
+        }
+
+        pos_idx = pos.next_in_instrument;
+    }
+
+    Ok(best.map(|(pos_idx, _)| pos_idx))
+}
+
+/// ADL rank of a position: unrealized profit scaled by its leverage
+///
+/// Leverage is the position's IM over its account's equity; accounts at or
+/// below zero equity count as maximally leveraged. Losing positions rank
+/// below every profitable one.
+fn adl_score(slab: &SlabState, pos: &Position) -> Result<i128, PercolatorError> {
+    let instrument = slab
+        .get_instrument(pos.instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?;
+
+    let pnl = calculate_pnl(pos.qty, pos.entry_px, instrument.mark_price);
+    let im = calculate_im(pos.qty, instrument.contract_size, instrument.mark_price, slab.header.imr);
+    let equity = calculate_equity(slab, pos.account_idx)?.max(1);
+
+    Ok(pnl.saturating_mul(im.min(i128::MAX as u128) as i128) / equity)
+}
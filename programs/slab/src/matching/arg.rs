@@ -0,0 +1,132 @@
+//! Aggressor Roundtrip Guard (ARG) - per-batch ledger of taker fills
+//!
+//! Each taker's aggressive buys and sells are tallied per instrument and
+//! epoch. A leg that offsets the taker's own earlier opposite leg in the same
+//! batch at non-negative PnL is a roundtrip: the overlapping qty is taxed at
+//! `as_fee_k` or clipped, depending on `ArgMode`. Maker fills never enter the
+//! ledger.
+//!
+//! Entries are looked up through the slab's (account, instrument) index and
+//! chained per instrument, so neither a commit nor a batch open scans the
+//! shared pool.
+
+use crate::state::SlabState;
+use percolator_common::*;
+
+/// Find the taker's ledger entry for the instrument's current epoch
+///
+/// Entries left over from an earlier epoch are reset in place.
+fn find_entry(slab: &mut SlabState, account_idx: u32, instrument_idx: u16, epoch: u16) -> Option<u32> {
+    let idx = slab.find_aggressor_entry(account_idx, instrument_idx)?;
+
+    let entry = slab.aggressor_ledger.get_mut(idx)?;
+    if entry.epoch != epoch {
+        reset_entry(entry, account_idx, instrument_idx, epoch);
+    }
+    Some(idx)
+}
+
+/// Clear an entry's tallies, keeping its place in the instrument's list
+fn reset_entry(entry: &mut AggressorEntry, account_idx: u32, instrument_idx: u16, epoch: u16) {
+    *entry = AggressorEntry {
+        account_idx,
+        instrument_idx,
+        epoch,
+        next_in_instrument: entry.next_in_instrument,
+        used: true,
+        ..Default::default()
+    };
+}
+
+/// Quantity of a new taker leg that roundtrips the taker's own earlier leg
+///
+/// Returns the overlap with the still-open opposite qty of this batch if
+/// trading it at `vwap` would realize non-negative PnL against that leg's
+/// average price, and 0 otherwise.
+pub fn roundtrip_overlap(
+    slab: &SlabState,
+    account_idx: u32,
+    instrument_idx: u16,
+    side: Side,
+    qty: u64,
+    vwap: u64,
+) -> Result<u64, PercolatorError> {
+    let epoch = slab
+        .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?
+        .epoch;
+
+    let Some(entry) = slab
+        .find_aggressor_entry(account_idx, instrument_idx)
+        .and_then(|idx| slab.aggressor_ledger.get(idx))
+        .filter(|e| e.epoch == epoch)
+    else {
+        return Ok(0);
+    };
+
+    let (open_qty, leg_qty, leg_notional) = match side {
+        Side::Sell => (entry.buy_qty.saturating_sub(entry.sell_qty), entry.buy_qty, entry.buy_notional),
+        Side::Buy => (entry.sell_qty.saturating_sub(entry.buy_qty), entry.sell_qty, entry.sell_notional),
+    };
+    if open_qty == 0 || qty == 0 {
+        return Ok(0);
+    }
+
+    // PnL of closing the earlier leg at this leg's price
+    let leg_px = calculate_vwap(leg_notional, leg_qty);
+    let non_negative = match side {
+        Side::Sell => vwap >= leg_px,
+        Side::Buy => vwap <= leg_px,
+    };
+
+    Ok(if non_negative { core::cmp::min(qty, open_qty) } else { 0 })
+}
+
+/// Add a taker fill to the ledger for the instrument's current epoch
+pub fn record_aggressor_fill(
+    slab: &mut SlabState,
+    account_idx: u32,
+    instrument_idx: u16,
+    side: Side,
+    qty: u64,
+    notional: u128,
+) -> Result<(), PercolatorError> {
+    let epoch = slab
+        .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?
+        .epoch;
+
+    let idx = match find_entry(slab, account_idx, instrument_idx, epoch) {
+        Some(idx) => idx,
+        None => {
+            let idx = slab.aggressor_ledger.alloc().ok_or(PercolatorError::PoolFull)?;
+            if let Some(entry) = slab.aggressor_ledger.get_mut(idx) {
+                reset_entry(entry, account_idx, instrument_idx, epoch);
+            }
+            slab.index_aggressor_entry(idx)?;
+            idx
+        }
+    };
+
+    let entry = slab
+        .aggressor_ledger
+        .get_mut(idx)
+        .ok_or(PercolatorError::PoolFull)?;
+    match side {
+        Side::Buy => {
+            entry.buy_qty = entry.buy_qty.saturating_add(qty);
+            entry.buy_notional = entry.buy_notional.saturating_add(notional);
+        }
+        Side::Sell => {
+            entry.sell_qty = entry.sell_qty.saturating_add(qty);
+            entry.sell_notional = entry.sell_notional.saturating_add(notional);
+        }
+    }
+
+    Ok(())
+}
+
+/// Release every ledger entry of an instrument (its epoch has rolled over)
+pub fn recycle_ledger(slab: &mut SlabState, instrument_idx: u16) {
+    slab.free_aggressor_entries(instrument_idx);
+}
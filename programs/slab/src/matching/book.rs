@@ -1,112 +1,135 @@
 //! Order book management with price-time priority
 
+use crate::matching::levels::{add_level, best_level, drop_level, find_level, next_level, prev_level};
 use crate::state::SlabState;
 use percolator_common::*;
 
-/// Insert order into book maintaining price-time priority
-pub fn insert_order(
+/// Head of one book list
+fn book_head_mut(instrument: &mut Instrument, side: Side, state: OrderState) -> &mut u32 {
+    match (side, state) {
+        (Side::Buy, OrderState::LIVE) => &mut instrument.bids_head,
+        (Side::Buy, OrderState::PENDING) => &mut instrument.bids_pending_head,
+        (Side::Sell, OrderState::LIVE) => &mut instrument.asks_head,
+        (Side::Sell, OrderState::PENDING) => &mut instrument.asks_pending_head,
+    }
+}
+
+/// Link an unlinked order into a book list between `prev_idx` and `next_idx`
+fn link_order(
     slab: &mut SlabState,
     instrument_idx: u16,
-    order_idx: u32,
     side: Side,
-    price: u64,
     state: OrderState,
+    order_idx: u32,
+    prev_idx: u32,
+    next_idx: u32,
 ) -> Result<(), PercolatorError> {
-    // Get the head pointer value (not a reference)
-    let head_ptr = {
+    if let Some(order) = slab.orders.get_mut(order_idx) {
+        order.prev = prev_idx;
+        order.next = next_idx;
+    }
+
+    if prev_idx == u32::MAX {
         let instrument = slab
-            .get_instrument(instrument_idx)
+            .get_instrument_mut(instrument_idx)
             .ok_or(PercolatorError::InvalidInstrument)?;
-
-        match (side, state) {
-            (Side::Buy, OrderState::LIVE) => instrument.bids_head,
-            (Side::Buy, OrderState::PENDING) => instrument.bids_pending_head,
-            (Side::Sell, OrderState::LIVE) => instrument.asks_head,
-            (Side::Sell, OrderState::PENDING) => instrument.asks_pending_head,
-        }
-    };
-
-    // If empty list, set as head
-    if head_ptr == u32::MAX {
-        if let Some(order) = slab.orders.get_mut(order_idx) {
-            order.next = u32::MAX;
-            order.prev = u32::MAX;
-        }
-
-        // Update instrument head
-        let instrument = slab.get_instrument_mut(instrument_idx).unwrap();
-        match (side, state) {
-            (Side::Buy, OrderState::LIVE) => instrument.bids_head = order_idx,
-            (Side::Buy, OrderState::PENDING) => instrument.bids_pending_head = order_idx,
-            (Side::Sell, OrderState::LIVE) => instrument.asks_head = order_idx,
-            (Side::Sell, OrderState::PENDING) => instrument.asks_pending_head = order_idx,
-        }
-
-        slab.header.increment_book_seqno();
-        return Ok(());
+        *book_head_mut(instrument, side, state) = order_idx;
+    } else if let Some(prev_order) = slab.orders.get_mut(prev_idx) {
+        prev_order.next = order_idx;
     }
 
-    // Get order_id for comparison
-    let new_order_id = slab.orders.get(order_idx).unwrap().order_id;
-
-    // Find insertion point maintaining price-time priority
-    let mut curr_idx = head_ptr;
-    let mut prev_idx = u32::MAX;
-
-    while curr_idx != u32::MAX {
-        let curr_order = slab
-            .orders
-            .get(curr_idx)
-            .ok_or(PercolatorError::OrderNotFound)?;
-
-        // Price-time priority:
-        // Buy: higher price first, then earlier order_id
-        // Sell: lower price first, then earlier order_id
-        let should_insert_before = match side {
-            Side::Buy => {
-                price > curr_order.price
-                    || (price == curr_order.price && new_order_id < curr_order.order_id)
-            }
-            Side::Sell => {
-                price < curr_order.price
-                    || (price == curr_order.price && new_order_id < curr_order.order_id)
-            }
-        };
-
-        if should_insert_before {
-            break;
+    if next_idx != u32::MAX {
+        if let Some(next_order) = slab.orders.get_mut(next_idx) {
+            next_order.prev = order_idx;
         }
-
-        prev_idx = curr_idx;
-        curr_idx = curr_order.next;
     }
 
-    // Insert order
-    if let Some(order) = slab.orders.get_mut(order_idx) {
-        order.next = curr_idx;
-        order.prev = prev_idx;
-    }
+    Ok(())
+}
 
-    // Update prev's next
-    if prev_idx == u32::MAX {
-        // Inserting at head - update instrument head pointer
-        let instrument = slab.get_instrument_mut(instrument_idx).unwrap();
-        match (side, state) {
-            (Side::Buy, OrderState::LIVE) => instrument.bids_head = order_idx,
-            (Side::Buy, OrderState::PENDING) => instrument.bids_pending_head = order_idx,
-            (Side::Sell, OrderState::LIVE) => instrument.asks_head = order_idx,
-            (Side::Sell, OrderState::PENDING) => instrument.asks_pending_head = order_idx,
+/// Insert order into book maintaining price-time priority
+///
+/// The price level index places the order without walking the list: a new
+/// price goes between its neighbouring levels, an existing one takes the
+/// order at its tail (or further forward if an older order id is promoted
+/// into it).
+pub fn insert_order(
+    slab: &mut SlabState,
+    instrument_idx: u16,
+    order_idx: u32,
+    side: Side,
+    price: u64,
+    state: OrderState,
+) -> Result<(), PercolatorError> {
+    let new_order_id = slab
+        .orders
+        .get(order_idx)
+        .ok_or(PercolatorError::OrderNotFound)?
+        .order_id;
+
+    let level_idx = match find_level(slab, instrument_idx, side, state, price)? {
+        Some(level_idx) => {
+            let level = *slab
+                .price_levels
+                .get(level_idx)
+                .ok_or(PercolatorError::BookCorrupted)?;
+
+            // Behind the last order with an earlier id, else at the level head
+            let mut prev_idx = level.tail;
+            loop {
+                let prev = slab.orders.get(prev_idx).ok_or(PercolatorError::BookCorrupted)?;
+                if prev.order_id < new_order_id {
+                    let next_idx = prev.next;
+                    link_order(slab, instrument_idx, side, state, order_idx, prev_idx, next_idx)?;
+                    break;
+                }
+                if prev_idx == level.head {
+                    let before = prev.prev;
+                    link_order(slab, instrument_idx, side, state, order_idx, before, prev_idx)?;
+                    break;
+                }
+                prev_idx = prev.prev;
+            }
+            level_idx
         }
-    } else if let Some(prev_order) = slab.orders.get_mut(prev_idx) {
-        prev_order.next = order_idx;
-    }
+        None => {
+            // Ahead of the next worse level, else behind the last better one
+            let worse = next_level(slab, instrument_idx, side, state, price)?;
+            let better = prev_level(slab, instrument_idx, side, state, price)?;
+            let (prev_idx, next_idx) = match (worse, better) {
+                (Some(worse), _) => {
+                    let head = slab.price_levels.get(worse).ok_or(PercolatorError::BookCorrupted)?.head;
+                    let prev = slab.orders.get(head).ok_or(PercolatorError::BookCorrupted)?.prev;
+                    (prev, head)
+                }
+                (None, Some(better)) => {
+                    let tail = slab.price_levels.get(better).ok_or(PercolatorError::BookCorrupted)?.tail;
+                    (tail, u32::MAX)
+                }
+                (None, None) => (u32::MAX, u32::MAX),
+            };
 
-    // Update next's prev
-    if curr_idx != u32::MAX {
-        if let Some(curr_order) = slab.orders.get_mut(curr_idx) {
-            curr_order.prev = order_idx;
+            let level_idx = add_level(slab, instrument_idx, side, state, price)?;
+            link_order(slab, instrument_idx, side, state, order_idx, prev_idx, next_idx)?;
+            level_idx
         }
+    };
+
+    let (prev_idx, next_idx) = {
+        let order = slab.orders.get(order_idx).ok_or(PercolatorError::OrderNotFound)?;
+        (order.prev, order.next)
+    };
+    let level = slab
+        .price_levels
+        .get_mut(level_idx)
+        .ok_or(PercolatorError::BookCorrupted)?;
+    if level.count == 0 || next_idx == level.head {
+        level.head = order_idx;
+    }
+    if level.count == 0 || prev_idx == level.tail {
+        level.tail = order_idx;
     }
+    level.count += 1;
 
     slab.header.increment_book_seqno();
     Ok(())
@@ -125,19 +148,17 @@ pub fn remove_order(
 
     let side = order.side;
     let state = order.state;
+    let price = order.price;
     let prev = order.prev;
     let next = order.next;
 
+    let level_idx = find_level(slab, instrument_idx, side, state, price)?
+        .ok_or(PercolatorError::BookCorrupted)?;
+
     let instrument = slab
         .get_instrument_mut(instrument_idx)
         .ok_or(PercolatorError::InvalidInstrument)?;
-
-    let target_head = match (side, state) {
-        (Side::Buy, OrderState::LIVE) => &mut instrument.bids_head,
-        (Side::Buy, OrderState::PENDING) => &mut instrument.bids_pending_head,
-        (Side::Sell, OrderState::LIVE) => &mut instrument.asks_head,
-        (Side::Sell, OrderState::PENDING) => &mut instrument.asks_pending_head,
-    };
+    let target_head = book_head_mut(instrument, side, state);
 
     // Update links
     if prev == u32::MAX {
@@ -153,6 +174,23 @@ pub fn remove_order(
         }
     }
 
+    // Shrink or drop the order's price level
+    let level = slab
+        .price_levels
+        .get_mut(level_idx)
+        .ok_or(PercolatorError::BookCorrupted)?;
+    if level.count <= 1 {
+        drop_level(slab, instrument_idx, side, state, level_idx)?;
+    } else {
+        if level.head == order_idx {
+            level.head = next;
+        }
+        if level.tail == order_idx {
+            level.tail = prev;
+        }
+        level.count -= 1;
+    }
+
     slab.header.increment_book_seqno();
     Ok(())
 }
@@ -229,22 +267,157 @@ fn promote_side(
 
 /// Get best bid/ask for instrument
 pub fn get_best_prices(slab: &SlabState, instrument_idx: u16) -> Result<(Option<u64>, Option<u64>), PercolatorError> {
-    let instrument = slab
+    let best_price = |side| -> Result<Option<u64>, PercolatorError> {
+        Ok(best_level(slab, instrument_idx, side, OrderState::LIVE)?
+            .and_then(|level_idx| slab.price_levels.get(level_idx))
+            .map(|level| level.price))
+    };
+
+    Ok((best_price(Side::Buy)?, best_price(Side::Sell)?))
+}
+
+/// Whether a limit order at `price` would take liquidity from the live book
+pub fn would_cross(
+    slab: &SlabState,
+    instrument_idx: u16,
+    side: Side,
+    price: u64,
+) -> Result<bool, PercolatorError> {
+    let (best_bid, best_ask) = get_best_prices(slab, instrument_idx)?;
+
+    Ok(match side {
+        Side::Buy => best_ask.is_some_and(|ask| ask <= price),
+        Side::Sell => best_bid.is_some_and(|bid| bid >= price),
+    })
+}
+
+/// Freeze the top `freeze_levels` price levels of one side of the live book
+///
+/// Called once a reservation has locked slices on `side`. Until the batch
+/// ends (`batch_open_ms + batch_ms`, or one `batch_ms` from now if that has
+/// already passed) new orders may not queue ahead of the frozen levels and
+/// resting orders in them may not be cancelled or amended, so late pop-ins
+/// cannot jump the reserved slices. A second freeze in the same batch can
+/// only widen the frozen range.
+pub fn freeze_top_levels(
+    slab: &mut SlabState,
+    instrument_idx: u16,
+    side: Side,
+) -> Result<(), PercolatorError> {
+    let levels = slab.header.freeze_levels;
+    if levels == 0 {
+        return Ok(());
+    }
+
+    let now = slab.header.current_ts;
+    let batch_ms = slab.header.batch_ms;
+    let batch_open_ms = slab
         .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?
+        .batch_open_ms;
+
+    // Price of the K-th level (or the last level if fewer)
+    let Some(level_idx) = best_level(slab, instrument_idx, side, OrderState::LIVE)? else {
+        return Ok(());
+    };
+    let mut boundary = slab.price_levels.get(level_idx).ok_or(PercolatorError::BookCorrupted)?.price;
+    for _ in 1..levels {
+        let Some(level_idx) = next_level(slab, instrument_idx, side, OrderState::LIVE, boundary)? else {
+            break;
+        };
+        boundary = slab.price_levels.get(level_idx).ok_or(PercolatorError::BookCorrupted)?.price;
+    }
+
+    let batch_end = batch_open_ms.saturating_add(batch_ms);
+    let until = if batch_end > now { batch_end } else { now.saturating_add(batch_ms) };
+
+    let instrument = slab
+        .get_instrument_mut(instrument_idx)
         .ok_or(PercolatorError::InvalidInstrument)?;
+    let active = now < instrument.freeze_until_ms;
+    let freeze_px = match side {
+        Side::Buy => &mut instrument.bids_freeze_px,
+        Side::Sell => &mut instrument.asks_freeze_px,
+    };
 
-    let best_bid = if instrument.bids_head != u32::MAX {
-        slab.orders.get(instrument.bids_head).map(|o| o.price)
-    } else {
-        None
+    *freeze_px = match side {
+        Side::Buy if active && *freeze_px != 0 => boundary.min(*freeze_px),
+        Side::Sell if active && *freeze_px != 0 => boundary.max(*freeze_px),
+        _ => boundary,
     };
+    if !active {
+        // A lapsed freeze on the other side must not come back to life
+        match side {
+            Side::Buy => instrument.asks_freeze_px = 0,
+            Side::Sell => instrument.bids_freeze_px = 0,
+        }
+    }
+    instrument.freeze_until_ms = instrument.freeze_until_ms.max(until);
 
-    let best_ask = if instrument.asks_head != u32::MAX {
-        slab.orders.get(instrument.asks_head).map(|o| o.price)
-    } else {
-        None
+    Ok(())
+}
+
+/// Worst frozen price on `side`, if that side is under an active freeze
+fn freeze_boundary(
+    slab: &SlabState,
+    instrument_idx: u16,
+    side: Side,
+) -> Result<Option<u64>, PercolatorError> {
+    let instrument = slab
+        .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?;
+
+    if slab.header.current_ts >= instrument.freeze_until_ms {
+        return Ok(None);
+    }
+
+    let boundary = match side {
+        Side::Buy => instrument.bids_freeze_px,
+        Side::Sell => instrument.asks_freeze_px,
     };
+    Ok((boundary != 0).then_some(boundary))
+}
 
-    Ok((best_bid, best_ask))
+/// Whether a resting live order at `price` on `side` sits in a frozen level
+pub fn is_frozen(
+    slab: &SlabState,
+    instrument_idx: u16,
+    side: Side,
+    price: u64,
+) -> Result<bool, PercolatorError> {
+    Ok(match (side, freeze_boundary(slab, instrument_idx, side)?) {
+        (Side::Buy, Some(boundary)) => price >= boundary,
+        (Side::Sell, Some(boundary)) => price <= boundary,
+        (_, None) => false,
+    })
 }
 
+/// Whether a new live order at `price` on `side` would queue ahead of a
+/// frozen level
+///
+/// Joining the back of the worst frozen level is allowed: time priority
+/// already puts it behind every reserved slice.
+pub fn would_jump_freeze(
+    slab: &SlabState,
+    instrument_idx: u16,
+    side: Side,
+    price: u64,
+) -> Result<bool, PercolatorError> {
+    Ok(match (side, freeze_boundary(slab, instrument_idx, side)?) {
+        (Side::Buy, Some(boundary)) => price > boundary,
+        (Side::Sell, Some(boundary)) => price < boundary,
+        (_, None) => false,
+    })
+}
+
+/// Lift any top-K freeze on the instrument (a new batch has opened)
+pub fn clear_freeze(slab: &mut SlabState, instrument_idx: u16) -> Result<(), PercolatorError> {
+    let instrument = slab
+        .get_instrument_mut(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?;
+
+    instrument.freeze_until_ms = 0;
+    instrument.bids_freeze_px = 0;
+    instrument.asks_freeze_px = 0;
+    Ok(())
+}
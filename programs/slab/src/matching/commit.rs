@@ -1,20 +1,133 @@
 //! Commit operation - execute trades at reserved prices
 
+use crate::matching::arg::{record_aggressor_fill, roundtrip_overlap};
+use crate::matching::book::would_cross;
+use crate::matching::insurance::credit_insurance;
+use crate::matching::funding::settle_position_funding;
+use crate::matching::mark::update_mark_price;
+use crate::matching::orders::place_order;
+use crate::matching::reserve::clip_reduce_only;
+use crate::matching::risk::{
+    calculate_equity, check_margin_for_fill, get_position_qty, increases_exposure, update_account_margin,
+};
 use crate::state::SlabState;
 use percolator_common::*;
 
 /// Commit result
+///
+/// Written back as program return data. Wire layout (v5, little-endian):
+/// `version u8 | filled_qty u64 | avg_price u64 | total_fee u128 | total_debit u128 |
+///  rested_order_id u64 | rested_qty u64 | rebate_withheld u128 | withheld_fills u32 |
+///  arg_tax u128 | arg_clipped_qty u64 | shortfall_qty u64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CommitResult {
     pub filled_qty: u64,
     pub avg_price: u64,
     pub total_fee: u128,
     pub total_debit: u128,
+    /// Order id of the GTC remainder left on the book (0 if none)
+    pub rested_order_id: u64,
+    pub rested_qty: u64,
+    /// Maker rebate withheld from JIT or briefly rested orders
+    pub rebate_withheld: u128,
+    /// Number of fills whose rebate was withheld
+    pub withheld_fills: u32,
+    /// Sandwich tax charged to the taker by the roundtrip guard
+    pub arg_tax: u128,
+    /// Quantity dropped from the fill by the roundtrip guard
+    pub arg_clipped_qty: u64,
+    /// Reserved qty lost to maker orders that shrank or left since reserve,
+    /// or that were pulled at commit for lack of margin
+    pub shortfall_qty: u64,
+}
+
+impl CommitResult {
+    pub const VERSION: u8 = 5;
+    pub const LEN: usize = 1 + 8 + 8 + 16 + 16 + 8 + 8 + 16 + 4 + 16 + 8 + 8;
+
+    /// Encode as return data
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u64(self.filled_qty);
+        w.write_u64(self.avg_price);
+        w.write_u128(self.total_fee);
+        w.write_u128(self.total_debit);
+        w.write_u64(self.rested_order_id);
+        w.write_u64(self.rested_qty);
+        w.write_u128(self.rebate_withheld);
+        w.write_u32(self.withheld_fills);
+        w.write_u128(self.arg_tax);
+        w.write_u64(self.arg_clipped_qty);
+        w.write_u64(self.shortfall_qty);
+        buf
+    }
+
+    /// Decode from return data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let result = Self {
+            filled_qty: r.read_u64()?,
+            avg_price: r.read_u64()?,
+            total_fee: r.read_u128()?,
+            total_debit: r.read_u128()?,
+            rested_order_id: r.read_u64()?,
+            rested_qty: r.read_u64()?,
+            rebate_withheld: r.read_u128()?,
+            withheld_fills: r.read_u32()?,
+            arg_tax: r.read_u128()?,
+            arg_clipped_qty: r.read_u64()?,
+            shortfall_qty: r.read_u64()?,
+        };
+        r.finish()?;
+        Ok(result)
+    }
 }
 
 /// Commit a reservation and execute trades
+///
+/// The reservation is returned to the pool once its fills are executed.
+///
+/// If the book changed since reserve, each slice is checked against its
+/// maker order: an order that was freed (or whose slot now holds another
+/// order) or that shrank below the slice leaves a shortfall. Under
+/// `ShrinkPolicy::Fail`, or for FOK orders, that fails the commit with
+/// `ReservationStale`; under `Reduce` the slices are cut to what is left, so a
+/// reservation never fills more than its makers still offer.
+///
+/// If the reservation carries a commitment hash, `salt` must reveal it:
+/// the hash is recomputed from the reserved order (see
+/// `percolator_common::commitment`) and a mismatch fails with
+/// `InvalidCommitment`.
+///
+/// Fails with `KillBandExceeded`, leaving the reservation open, if the mark
+/// has moved more than `kill_band_bps` from the reservation's snapshot.
+///
+/// Reduce-only holds are clipped again against the live position, since it
+/// may have shrunk since reserve; with nothing left to reduce the commit
+/// fails with `ReduceOnlyViolation`, leaving the reservation open.
+///
+/// Margin is re-checked for every fill that grows a position. A maker that
+/// can no longer margin its slice has its order pulled and the slice skipped
+/// (counted in `shortfall_qty`; FOK orders then fail with `ReservationStale`).
+/// The taker must still have equity covering its IM once positions are
+/// updated, or the commit fails with `InsufficientMargin` (and the
+/// transaction rolls back). Every fill refreshes both sides' cached IM/MM.
+///
+/// The taker fee is not taken from the taker's cash here: it is part of
+/// `total_debit`, which the router collects. `insurance_taker_share_bps` of
+/// it is credited to the insurance fund.
+///
+/// The taker's fill goes through the Aggressor Roundtrip Guard (see
+/// `matching::arg`): in `Clip` mode the roundtripping qty is released before
+/// execution (`RoundtripDetected` if nothing else is left), in `Tax` mode it
+/// is charged `as_fee_k` on its notional.
 pub fn commit(
     slab: &mut SlabState,
     hold_id: u64,
+    salt: &[u8; 16],
     current_ts: u64,
 ) -> Result<CommitResult, PercolatorError> {
     // Find reservation
@@ -34,14 +147,83 @@ pub fn commit(
         return Err(PercolatorError::InvalidReservation);
     }
 
+    // Commit-reveal: the salt must open the hash given at reserve
+    if resv.commitment_hash != NO_COMMITMENT
+        && compute_commitment(resv.route_id, resv.instrument_idx, resv.side, resv.qty_order, resv.limit_px, salt)
+            != resv.commitment_hash
+    {
+        return Err(PercolatorError::InvalidCommitment);
+    }
+
+    // Kill band: refuse to fill if the mark moved too far since reserve
+    let mark_now = slab
+        .get_instrument(resv.instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?
+        .mark_price;
+    if exceeds_kill_band(resv.mark_px, mark_now, slab.header.kill_band_bps) {
+        return Err(PercolatorError::KillBandExceeded);
+    }
+
     let account_idx = resv.account_idx;
     let instrument_idx = resv.instrument_idx;
     let side = resv.side;
     let slice_head = resv.slice_head;
+    let tif = resv.tif;
+    let flags = resv.flags;
+    let limit_px = resv.limit_px;
+    let qty_requested = resv.qty_requested;
+    let book_seqno = resv.book_seqno;
+    let arg_mode = slab.header.arg_mode;
+
+    // Shrink policy: makers may have shrunk or left since reserve
+    let mut shortfall_qty = 0;
+    if slab.header.book_seqno != book_seqno {
+        shortfall_qty = slice_shortfall(slab, slice_head)?;
+        if shortfall_qty > 0 {
+            if slab.header.shrink_policy == ShrinkPolicy::Fail || tif == TimeInForce::FOK {
+                return Err(PercolatorError::ReservationStale);
+            }
+            shrink_slices(slab, slice_head)?;
+        }
+    }
+
+    // Reduce-only: the position may have shrunk since reserve
+    if flags & ORDER_FLAG_REDUCE_ONLY != 0 {
+        let (qty, _) = slice_totals(slab, slice_head)?;
+        let reducible = clip_reduce_only(slab, account_idx, instrument_idx, side, qty)?;
+        if reducible < qty {
+            trim_slices(slab, slice_head, reducible)?;
+        }
+    }
+
+    // ARG clip: release the roundtripping part of the fill before executing
+    let mut arg_clipped_qty = 0;
+    if arg_mode == ArgMode::Clip {
+        let (qty, notional) = slice_totals(slab, slice_head)?;
+        let overlap = if qty > 0 {
+            roundtrip_overlap(slab, account_idx, instrument_idx, side, qty, calculate_vwap(notional, qty))?
+        } else {
+            0
+        };
+        if overlap > 0 {
+            if overlap >= qty {
+                return Err(PercolatorError::RoundtripDetected);
+            }
+            trim_slices(slab, slice_head, qty - overlap)?;
+            arg_clipped_qty = overlap;
+        }
+    }
 
     // Execute all slices
-    let (filled_qty, total_notional, total_fee) =
-        execute_slices(slab, slice_head, account_idx, instrument_idx, side, current_ts)?;
+    let position_before = get_position_qty(slab, account_idx, instrument_idx);
+    let fills = execute_slices(slab, slice_head, account_idx, instrument_idx, side, current_ts)?;
+    if fills.skipped_qty > 0 && tif == TimeInForce::FOK {
+        return Err(PercolatorError::ReservationStale);
+    }
+    shortfall_qty = shortfall_qty.saturating_add(fills.skipped_qty);
+    let filled_qty = fills.qty;
+    let total_notional = fills.notional;
+    let total_fee = fills.taker_fee;
 
     // Calculate average price
     let avg_price = if filled_qty > 0 {
@@ -50,25 +232,117 @@ pub fn commit(
         0
     };
 
-    let total_debit = total_notional.saturating_add(total_fee);
+    // ARG tax on the roundtripping part, then log this leg for the batch
+    let mut arg_tax = 0;
+    if arg_mode == ArgMode::Tax && filled_qty > 0 {
+        let overlap = roundtrip_overlap(slab, account_idx, instrument_idx, side, filled_qty, avg_price)?;
+        arg_tax = calculate_fee(mul_u64(overlap, avg_price), slab.header.as_fee_k as i64);
+        if arg_tax > 0 {
+            let taker = slab
+                .get_account_mut(account_idx)
+                .ok_or(PercolatorError::InvalidAccount)?;
+            taker.cash = taker.cash.saturating_sub(arg_tax as i128);
+            slab.header.arg_tax_collected = slab.header.arg_tax_collected.saturating_add(arg_tax);
+        }
+    }
+    if arg_mode != ArgMode::Off && filled_qty > 0 {
+        record_aggressor_fill(slab, account_idx, instrument_idx, side, filled_qty, total_notional)?;
+    }
 
-    // Mark reservation as committed
-    if let Some(resv) = slab.reservations.get_mut(resv_idx) {
-        resv.committed = true;
+    // Post-trade margin: the taker must still carry what it now holds
+    if increases_exposure(position_before, get_position_qty(slab, account_idx, instrument_idx))
+        && !covers_initial_margin(slab, account_idx)?
+    {
+        return Err(PercolatorError::InsufficientMargin);
     }
 
-    // Free slices and update reserved_qty
+    let total_debit = total_notional.saturating_add(total_fee);
+    let insurance_share = slab.header.insurance_taker_share_bps;
+    credit_insurance(slab, total_fee, insurance_share);
+
+    // Free slices and update reserved_qty, then return the hold to the pool
     free_slices(slab, slice_head)?;
+    slab.free_reservation(resv_idx);
+
+    // GTC remainder rests as a maker order; reduce-only orders never rest
+    let (rested_order_id, rested_qty) = if tif == TimeInForce::GTC
+        && flags & ORDER_FLAG_REDUCE_ONLY == 0
+    {
+        rest_remainder(
+            slab,
+            account_idx,
+            instrument_idx,
+            side,
+            limit_px,
+            qty_requested.saturating_sub(filled_qty).saturating_sub(arg_clipped_qty),
+            current_ts,
+        )
+    } else {
+        (0, 0)
+    };
+
+    // Sample the post-trade book into the mark premium
+    update_mark_price(slab, instrument_idx, current_ts)?;
 
     Ok(CommitResult {
         filled_qty,
         avg_price,
         total_fee,
         total_debit,
+        rested_order_id,
+        rested_qty,
+        rebate_withheld: fills.rebate_withheld,
+        withheld_fills: fills.withheld_fills,
+        arg_tax,
+        arg_clipped_qty,
+        shortfall_qty,
     })
 }
 
+/// Rest the unfilled remainder of a GTC taker order at its limit price
+///
+/// Best effort: a remainder that would now cross the book, or that the
+/// account can no longer margin, is dropped rather than failing the fill.
+/// Returns `(order_id, qty)`, or zeros when nothing rested.
+fn rest_remainder(
+    slab: &mut SlabState,
+    account_idx: u32,
+    instrument_idx: u16,
+    side: Side,
+    limit_px: u64,
+    remainder: u64,
+    current_ts: u64,
+) -> (u64, u64) {
+    if remainder == 0 || !matches!(would_cross(slab, instrument_idx, side, limit_px), Ok(false)) {
+        return (0, 0);
+    }
+
+    match place_order(slab, account_idx, instrument_idx, side, limit_px, remainder, current_ts) {
+        Ok(placed) => (placed.order_id, remainder),
+        Err(_) => (0, 0),
+    }
+}
+
+/// Totals from executing a reservation's slices
+struct SliceFills {
+    qty: u64,
+    notional: u128,
+    taker_fee: u128,
+    rebate_withheld: u128,
+    withheld_fills: u32,
+    /// Qty dropped with makers that could not margin their fill
+    skipped_qty: u64,
+}
+
 /// Execute all slices in a reservation
+///
+/// Maker rebates are only paid to orders that were resting before the
+/// current batch opened (when the JIT penalty is on) and for at least
+/// `maker_rebate_min_ms`; otherwise the rebate is withheld and counted.
+///
+/// A maker whose position would grow beyond what its equity can margin,
+/// fees and the fill's PnL included, has its order pulled from the book and
+/// the slice skipped; the rest of the reservation still fills.
 fn execute_slices(
     slab: &mut SlabState,
     slice_head: u32,
@@ -76,11 +350,21 @@ fn execute_slices(
     instrument_idx: u16,
     side: Side,
     current_ts: u64,
-) -> Result<(u64, u128, u128), PercolatorError> {
+) -> Result<SliceFills, PercolatorError> {
+    let batch_open_ms = slab
+        .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?
+        .batch_open_ms;
+
     let mut curr_slice_idx = slice_head;
-    let mut total_qty = 0u64;
-    let mut total_notional = 0u128;
-    let mut total_fee = 0u128;
+    let mut fills = SliceFills {
+        qty: 0,
+        notional: 0,
+        taker_fee: 0,
+        rebate_withheld: 0,
+        withheld_fills: 0,
+        skipped_qty: 0,
+    };
 
     while curr_slice_idx != u32::MAX {
         let slice = slab
@@ -89,9 +373,15 @@ fn execute_slices(
             .ok_or(PercolatorError::InvalidReservation)?;
 
         let order_idx = slice.order_idx;
-        let qty = slice.qty;
+        let qty = fillable_qty(slab, slice);
         let next_slice = slice.next;
 
+        // Slices emptied by the roundtrip guard, or whose maker order left
+        if qty == 0 {
+            curr_slice_idx = next_slice;
+            continue;
+        }
+
         // Get order
         let order = slab
             .orders
@@ -100,6 +390,39 @@ fn execute_slices(
 
         let maker_account_idx = order.account_idx;
         let price = order.price;
+        let created_ms = order.created_ms;
+        let maker_order_id = order.order_id;
+
+        // Calculate fees
+        let notional = mul_u64(qty, price);
+        let maker_fee_bps = slab.header.maker_fee;
+        let taker_fee = calculate_fee(notional, slab.header.taker_fee as i64);
+        let maker_fee = calculate_fee(notional, maker_fee_bps);
+
+        // JIT or briefly rested makers forfeit the rebate
+        let rebate_withheld = maker_fee_bps < 0
+            && (slab.header.is_jit_order(created_ms, batch_open_ms)
+                || current_ts.saturating_sub(created_ms) < slab.header.maker_rebate_min_ms);
+        let maker_cash_delta = if maker_fee_bps >= 0 {
+            -(maker_fee as i128)
+        } else if !rebate_withheld {
+            maker_fee as i128
+        } else {
+            0
+        };
+
+        // A maker that can no longer margin its quote is dropped, not filled
+        let maker_qty_delta = match side {
+            Side::Buy => -(qty as i64),
+            Side::Sell => qty as i64,
+        };
+        if !check_margin_for_fill(slab, maker_account_idx, instrument_idx, maker_qty_delta, price, maker_cash_delta)? {
+            remove_order_from_book(slab, instrument_idx, order_idx)?;
+            slab.free_order(order_idx);
+            fills.skipped_qty = fills.skipped_qty.saturating_add(qty);
+            curr_slice_idx = next_slice;
+            continue;
+        }
 
         // Execute trade
         execute_trade(
@@ -110,28 +433,24 @@ fn execute_slices(
             side,
             qty,
             price,
-            order.order_id,
+            maker_order_id,
             current_ts,
         )?;
 
-        // Calculate fees
-        let notional = mul_u64(qty, price);
-        let maker_fee_bps = slab.header.maker_fee;
-        let taker_fee = calculate_fee(notional, slab.header.taker_fee as i64);
-        let maker_fee = calculate_fee(notional, maker_fee_bps);
+        fills.qty = fills.qty.saturating_add(qty);
+        fills.notional = fills.notional.saturating_add(notional);
+        fills.taker_fee = fills.taker_fee.saturating_add(taker_fee);
 
-        total_qty = total_qty.saturating_add(qty);
-        total_notional = total_notional.saturating_add(notional);
-        total_fee = total_fee.saturating_add(taker_fee);
+        if rebate_withheld {
+            fills.rebate_withheld = fills.rebate_withheld.saturating_add(maker_fee);
+            fills.withheld_fills = fills.withheld_fills.saturating_add(1);
+            slab.header.rebates_withheld = slab.header.rebates_withheld.saturating_add(maker_fee);
+            slab.header.rebates_withheld_count = slab.header.rebates_withheld_count.saturating_add(1);
+        }
 
-        // Update maker's cash (subtract maker fee, can be negative for rebate)
+        // Update maker's cash (subtract maker fee, or add the rebate)
         if let Some(maker) = slab.get_account_mut(maker_account_idx) {
-            if maker_fee_bps >= 0 {
-                maker.cash = maker.cash.saturating_sub(maker_fee as i128);
-            } else {
-                // Negative fee = rebate
-                maker.cash = maker.cash.saturating_add(maker_fee as i128);
-            }
+            maker.cash = maker.cash.saturating_add(maker_cash_delta);
         }
 
         // Update order quantity
@@ -141,14 +460,14 @@ fn execute_slices(
             // If fully filled, remove from book
             if order.qty == 0 {
                 remove_order_from_book(slab, instrument_idx, order_idx)?;
-                slab.orders.free(order_idx);
+                slab.free_order(order_idx);
             }
         }
 
         curr_slice_idx = next_slice;
     }
 
-    Ok((total_qty, total_notional, total_fee))
+    Ok(fills)
 }
 
 /// Execute a single trade and update positions
@@ -194,6 +513,10 @@ fn execute_trade(
         cum_funding,
     )?;
 
+    // Refresh both sides' margin caches
+    update_account_margin(slab, taker_account_idx)?;
+    update_account_margin(slab, maker_account_idx)?;
+
     // Record trade
     let trade = Trade {
         ts: current_ts,
@@ -201,7 +524,8 @@ fn execute_trade(
         order_id_taker: 0, // Route ID from taker
         instrument_idx,
         side,
-        _padding: [0; 5],
+        kind: TradeKind::Fill,
+        _padding: [0; 4],
         price,
         qty,
         hash: [0; 32],
@@ -212,8 +536,21 @@ fn execute_trade(
     Ok(())
 }
 
+/// Whether an account's equity covers its cached initial margin
+///
+/// The cache is current after any fill, see `execute_trade`.
+fn covers_initial_margin(slab: &SlabState, account_idx: u32) -> Result<bool, PercolatorError> {
+    let equity = calculate_equity(slab, account_idx)?;
+    let im = slab
+        .get_account(account_idx)
+        .ok_or(PercolatorError::InvalidAccount)?
+        .im;
+
+    Ok(equity >= im as i128)
+}
+
 /// Update or create position with VWAP logic
-fn update_position(
+pub(crate) fn update_position(
     slab: &mut SlabState,
     account_idx: u32,
     instrument_idx: u16,
@@ -245,6 +582,9 @@ fn update_position(
     }
 
     if let Some(pos_idx) = found {
+        // Realize accrued funding before the size changes
+        settle_position_funding(slab, pos_idx)?;
+
         // Get position data before any mutable borrows
         let (old_qty, old_entry_px) = {
             let pos = slab.positions.get(pos_idx).unwrap();
@@ -262,10 +602,10 @@ fn update_position(
 
             // Remove position
             remove_position(slab, account_idx, pos_idx)?;
-        } else if (old_qty > 0 && new_qty > 0) || (old_qty < 0 && new_qty < 0) {
-            // Same direction - update VWAP
-            let abs_old = old_qty.abs() as u64;
-            let abs_delta = qty_delta.abs() as u64;
+        } else if (old_qty > 0) == (new_qty > 0) && new_qty.unsigned_abs() > old_qty.unsigned_abs() {
+            // Increased in the same direction - update VWAP
+            let abs_old = old_qty.unsigned_abs();
+            let abs_delta = qty_delta.unsigned_abs();
             let old_notional = mul_u64(abs_old, old_entry_px);
             let delta_notional = mul_u64(abs_delta, price);
             let new_notional = old_notional.saturating_add(delta_notional);
@@ -276,6 +616,16 @@ fn update_position(
                 pos.entry_px = new_entry_px;
                 pos.qty = new_qty;
             }
+        } else if (old_qty > 0) == (new_qty > 0) {
+            // Reduced - realize PnL on the closed part, entry unchanged
+            let pnl = calculate_pnl(old_qty - new_qty, old_entry_px, price);
+            if let Some(account) = slab.get_account_mut(account_idx) {
+                account.cash = account.cash.saturating_add(pnl);
+            }
+
+            if let Some(pos) = slab.positions.get_mut(pos_idx) {
+                pos.qty = new_qty;
+            }
         } else {
             // Flipped - realize partial PnL
             let pnl = calculate_pnl(old_qty, old_entry_px, price);
@@ -283,12 +633,14 @@ fn update_position(
                 account.cash = account.cash.saturating_add(pnl);
             }
 
-            // Set new position
+            // Set new position, on the other side of the instrument
+            unlink_instrument_side(slab, pos_idx)?;
             if let Some(pos) = slab.positions.get_mut(pos_idx) {
                 pos.qty = new_qty;
                 pos.entry_px = price;
                 pos.last_funding = cum_funding;
             }
+            link_instrument_side(slab, pos_idx)?;
         }
     } else if qty_delta != 0 {
         // Create new position
@@ -309,6 +661,8 @@ fn update_position(
                 entry_px: price,
                 last_funding: cum_funding,
                 next_in_account: pos_head,
+                next_in_instrument: u32::MAX,
+                prev_in_instrument: u32::MAX,
                 index: pos_idx,
                 used: true,
                 _padding2: [0; 7],
@@ -319,6 +673,70 @@ fn update_position(
         if let Some(account) = slab.get_account_mut(account_idx) {
             account.position_head = pos_idx;
         }
+        link_instrument_side(slab, pos_idx)?;
+    }
+
+    Ok(())
+}
+
+/// Push a position onto its instrument's long or short list
+fn link_instrument_side(slab: &mut SlabState, position_idx: u32) -> Result<(), PercolatorError> {
+    let (instrument_idx, long) = {
+        let pos = slab
+            .positions
+            .get(position_idx)
+            .ok_or(PercolatorError::PositionNotFound)?;
+        (pos.instrument_idx, pos.qty > 0)
+    };
+
+    let instrument = slab
+        .get_instrument_mut(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?;
+    let head = if long {
+        &mut instrument.longs_head
+    } else {
+        &mut instrument.shorts_head
+    };
+    let next = core::mem::replace(head, position_idx);
+
+    if let Some(next_pos) = slab.positions.get_mut(next) {
+        next_pos.prev_in_instrument = position_idx;
+    }
+    if let Some(pos) = slab.positions.get_mut(position_idx) {
+        pos.next_in_instrument = next;
+        pos.prev_in_instrument = u32::MAX;
+    }
+
+    Ok(())
+}
+
+/// Take a position off its instrument's long or short list
+///
+/// The side is read from the position's current qty, so unlink before
+/// changing its sign.
+fn unlink_instrument_side(slab: &mut SlabState, position_idx: u32) -> Result<(), PercolatorError> {
+    let (instrument_idx, long, prev, next) = {
+        let pos = slab
+            .positions
+            .get(position_idx)
+            .ok_or(PercolatorError::PositionNotFound)?;
+        (pos.instrument_idx, pos.qty > 0, pos.prev_in_instrument, pos.next_in_instrument)
+    };
+
+    if let Some(prev_pos) = slab.positions.get_mut(prev) {
+        prev_pos.next_in_instrument = next;
+    } else {
+        let instrument = slab
+            .get_instrument_mut(instrument_idx)
+            .ok_or(PercolatorError::InvalidInstrument)?;
+        if long {
+            instrument.longs_head = next;
+        } else {
+            instrument.shorts_head = next;
+        }
+    }
+    if let Some(next_pos) = slab.positions.get_mut(next) {
+        next_pos.prev_in_instrument = prev;
     }
 
     Ok(())
@@ -354,6 +772,7 @@ fn remove_position(
                 prev_pos.next_in_account = next;
             }
 
+            unlink_instrument_side(slab, position_idx)?;
             slab.positions.free(position_idx);
             return Ok(());
         }
@@ -388,13 +807,204 @@ pub fn cancel(slab: &mut SlabState, hold_id: u64) -> Result<(), PercolatorError>
     free_slices(slab, slice_head)?;
 
     // Free reservation
-    slab.reservations.free(resv_idx);
+    slab.free_reservation(resv_idx);
+
+    Ok(())
+}
+
+/// ReapExpired result
+///
+/// Written back as program return data. Wire layout (v1, little-endian):
+/// `version u8 | reaped u32 | next_cursor u32`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReapExpiredResult {
+    /// Number of expired reservations released
+    pub reaped: u32,
+    /// Reservation slot the next call starts from
+    pub next_cursor: u32,
+}
+
+impl ReapExpiredResult {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 4 + 4;
+
+    /// Encode as return data
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u32(self.reaped);
+        w.write_u32(self.next_cursor);
+        buf
+    }
+
+    /// Decode from return data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let result = Self {
+            reaped: r.read_u32()?,
+            next_cursor: r.read_u32()?,
+        };
+        r.finish()?;
+        Ok(result)
+    }
+}
+
+/// Release reservations that expired without being committed
+///
+/// Inspects at most `max_scan` reservation slots, starting at the header's
+/// reap cursor and wrapping around the pool, so repeated calls sweep the
+/// whole pool at bounded cost. Expired holds have their slices' reserved qty
+/// returned to the book and are freed. Committed holds never reach the
+/// crank: commit frees them itself.
+pub fn reap_expired(
+    slab: &mut SlabState,
+    max_scan: u32,
+    current_ts: u64,
+) -> Result<ReapExpiredResult, PercolatorError> {
+    let pool_len = MAX_RESERVATIONS as u32;
+    let scan = core::cmp::min(max_scan, pool_len);
+    let mut cursor = slab.header.reap_cursor % pool_len;
+    let mut reaped = 0u32;
+
+    for _ in 0..scan {
+        let expired = slab
+            .reservations
+            .get(cursor)
+            .map(|resv| (current_ts > resv.expiry_ms, resv.slice_head));
+
+        if let Some((true, slice_head)) = expired {
+            free_slices(slab, slice_head)?;
+            slab.free_reservation(cursor);
+            reaped += 1;
+        }
+
+        cursor = (cursor + 1) % pool_len;
+    }
+
+    slab.header.reap_cursor = cursor;
+
+    Ok(ReapExpiredResult { reaped, next_cursor: cursor })
+}
+
+/// Total qty and notional a reservation's slices can still fill
+fn slice_totals(slab: &SlabState, slice_head: u32) -> Result<(u64, u128), PercolatorError> {
+    let mut qty = 0u64;
+    let mut notional = 0u128;
+    let mut curr_idx = slice_head;
+
+    while curr_idx != u32::MAX {
+        let slice = slab
+            .slices
+            .get(curr_idx)
+            .ok_or(PercolatorError::InvalidReservation)?;
+        curr_idx = slice.next;
+
+        // Emptied slices and slices whose maker order left carry nothing
+        let fillable = fillable_qty(slab, slice);
+        if fillable == 0 {
+            continue;
+        }
+        let price = slab
+            .orders
+            .get(slice.order_idx)
+            .ok_or(PercolatorError::OrderNotFound)?
+            .price;
+
+        qty = qty.saturating_add(fillable);
+        notional = notional.saturating_add(mul_u64(fillable, price));
+    }
+
+    Ok((qty, notional))
+}
+
+/// Shrink a reservation's slices to `keep` qty, releasing the worst-priced tail
+fn trim_slices(slab: &mut SlabState, slice_head: u32, keep: u64) -> Result<(), PercolatorError> {
+    let mut remaining = keep;
+    let mut curr_idx = slice_head;
+
+    while curr_idx != u32::MAX {
+        let slice = slab
+            .slices
+            .get_mut(curr_idx)
+            .ok_or(PercolatorError::InvalidReservation)?;
+
+        let kept = core::cmp::min(slice.qty, remaining);
+        let released = slice.qty - kept;
+        slice.qty = kept;
+        remaining -= kept;
+        let (order_idx, order_id, next) = (slice.order_idx, slice.order_id, slice.next);
+
+        unreserve(slab, order_idx, order_id, released);
+
+        curr_idx = next;
+    }
+
+    Ok(())
+}
+
+/// Quantity a slice can still fill from its maker order
+///
+/// Zero if the order was freed or its slot now holds a different order.
+fn fillable_qty(slab: &SlabState, slice: &Slice) -> u64 {
+    match slab.orders.get(slice.order_idx) {
+        Some(order) if order.order_id == slice.order_id => core::cmp::min(slice.qty, order.qty),
+        _ => 0,
+    }
+}
+
+/// Reserved qty whose maker orders have since shrunk or left the book
+fn slice_shortfall(slab: &SlabState, slice_head: u32) -> Result<u64, PercolatorError> {
+    let mut shortfall = 0u64;
+    let mut curr_idx = slice_head;
+
+    while curr_idx != u32::MAX {
+        let slice = slab
+            .slices
+            .get(curr_idx)
+            .ok_or(PercolatorError::InvalidReservation)?;
+
+        shortfall = shortfall.saturating_add(slice.qty - fillable_qty(slab, slice));
+        curr_idx = slice.next;
+    }
+
+    Ok(shortfall)
+}
+
+/// Cut every slice down to what its maker order can still fill
+fn shrink_slices(slab: &mut SlabState, slice_head: u32) -> Result<(), PercolatorError> {
+    let mut curr_idx = slice_head;
+
+    while curr_idx != u32::MAX {
+        let slice = *slab
+            .slices
+            .get(curr_idx)
+            .ok_or(PercolatorError::InvalidReservation)?;
+
+        let fillable = fillable_qty(slab, &slice);
+        unreserve(slab, slice.order_idx, slice.order_id, slice.qty - fillable);
+        if let Some(slice) = slab.slices.get_mut(curr_idx) {
+            slice.qty = fillable;
+        }
+
+        curr_idx = slice.next;
+    }
 
     Ok(())
 }
 
+/// Release reserved qty on a slice's order, unless its slot was reused
+fn unreserve(slab: &mut SlabState, order_idx: u32, order_id: u64, qty: u64) {
+    if let Some(order) = slab.orders.get_mut(order_idx) {
+        if order.order_id == order_id {
+            order.reserved_qty = order.reserved_qty.saturating_sub(qty);
+        }
+    }
+}
+
 /// Free slices and update order reserved quantities
-fn free_slices(slab: &mut SlabState, slice_head: u32) -> Result<(), PercolatorError> {
+pub(crate) fn free_slices(slab: &mut SlabState, slice_head: u32) -> Result<(), PercolatorError> {
     let mut curr_idx = slice_head;
 
     while curr_idx != u32::MAX {
@@ -404,13 +1014,12 @@ fn free_slices(slab: &mut SlabState, slice_head: u32) -> Result<(), PercolatorEr
             .ok_or(PercolatorError::InvalidReservation)?;
 
         let order_idx = slice.order_idx;
+        let order_id = slice.order_id;
         let qty = slice.qty;
         let next = slice.next;
 
         // Unreserve quantity in order
-        if let Some(order) = slab.orders.get_mut(order_idx) {
-            order.reserved_qty = order.reserved_qty.saturating_sub(qty);
-        }
+        unreserve(slab, order_idx, order_id, qty);
 
         // Free slice
         slab.slices.free(curr_idx);
@@ -423,17 +1032,7 @@ fn free_slices(slab: &mut SlabState, slice_head: u32) -> Result<(), PercolatorEr
 
 /// Find reservation by hold_id
 fn find_reservation(slab: &SlabState, hold_id: u64) -> Result<u32, PercolatorError> {
-    // Linear search through reservations
-    // Could be optimized with a hashmap, but keeping simple for now
-    for i in 0..slab.reservations.items.len() {
-        if let Some(resv) = slab.reservations.get(i as u32) {
-            if resv.hold_id == hold_id {
-                return Ok(i as u32);
-            }
-        }
-    }
-
-    Err(PercolatorError::ReservationNotFound)
+    slab.find_reservation(hold_id).ok_or(PercolatorError::ReservationNotFound)
 }
 
 /// Remove order from book (internal helper)
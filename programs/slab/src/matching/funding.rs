@@ -0,0 +1,169 @@
+//! Funding - per-interval accrual into instrument cumulative funding and
+//! settlement of accrued funding into account cash
+
+use crate::matching::mark::{book_premium_bps, update_mark_price};
+use crate::state::SlabState;
+use percolator_common::*;
+
+/// UpdateFunding result
+///
+/// Written back as program return data. Wire layout (v1, little-endian):
+/// `version u8 | funding_rate i64 | cum_funding i128 | intervals u32 | last_funding_ts u64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FundingResult {
+    /// Rate that will accrue over the next interval (bps per hour)
+    pub funding_rate: i64,
+    pub cum_funding: i128,
+    /// Whole intervals accrued by this crank
+    pub intervals: u32,
+    pub last_funding_ts: u64,
+}
+
+impl FundingResult {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 8 + 16 + 4 + 8;
+
+    /// Encode as return data
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_i64(self.funding_rate);
+        w.write_i128(self.cum_funding);
+        w.write_u32(self.intervals);
+        w.write_u64(self.last_funding_ts);
+        buf
+    }
+
+    /// Decode from return data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let result = Self {
+            funding_rate: r.read_i64()?,
+            cum_funding: r.read_i128()?,
+            intervals: r.read_u32()?,
+            last_funding_ts: r.read_u64()?,
+        };
+        r.finish()?;
+        Ok(result)
+    }
+}
+
+/// Accrue funding for an instrument and set the rate for the next interval
+///
+/// The first crank only anchors `last_funding_ts`. After that funding is
+/// applied in whole intervals: a crank before the next boundary fails with
+/// `FundingNotDue`, and missed intervals are caught up at the stored rate so
+/// each interval accrues exactly once.
+///
+/// Rates are quoted per hour but capped per interval: no interval accrues
+/// more than `max_funding_rate_bps` of index, so the hourly cap is scaled by
+/// `FUNDING_PERIOD_MS / funding_interval_ms`.
+pub fn update_funding(
+    slab: &mut SlabState,
+    instrument_idx: u16,
+    current_ts: u64,
+) -> Result<FundingResult, PercolatorError> {
+    let interval_ms = slab.header.funding_interval_ms;
+    let max_rate = (slab.header.max_funding_rate_bps as u128 * FUNDING_PERIOD_MS as u128 / interval_ms as u128)
+        .min(i64::MAX as u128) as i64;
+
+    let (funding_rate, index_price, last_funding_ts) = {
+        let instrument = slab
+            .get_instrument(instrument_idx)
+            .ok_or(PercolatorError::InvalidInstrument)?;
+
+        (instrument.funding_rate, instrument.index_price, instrument.last_funding_ts)
+    };
+
+    let (intervals, accrued_until) = if last_funding_ts == 0 {
+        (0, current_ts)
+    } else {
+        let elapsed = current_ts.saturating_sub(last_funding_ts);
+        let intervals = elapsed / interval_ms;
+        if intervals == 0 {
+            return Err(PercolatorError::FundingNotDue);
+        }
+        (intervals, last_funding_ts + intervals * interval_ms)
+    };
+
+    // Accrue the rate in force over the elapsed intervals
+    let capped_rate = funding_rate.clamp(-max_rate, max_rate);
+    let accrual = calculate_funding_accrual(capped_rate, index_price, intervals * interval_ms);
+
+    // Next interval's rate follows the book premium over index
+    let next_rate = book_premium_bps(slab, instrument_idx, index_price)?.clamp(-max_rate, max_rate);
+
+    let instrument = slab
+        .get_instrument_mut(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?;
+    instrument.cum_funding = instrument.cum_funding.saturating_add(accrual);
+    instrument.last_funding_ts = accrued_until;
+    instrument.funding_rate = next_rate;
+    let cum_funding = instrument.cum_funding;
+
+    // The crank also keeps the mark premium average sampled
+    update_mark_price(slab, instrument_idx, current_ts)?;
+
+    Ok(FundingResult {
+        funding_rate: next_rate,
+        cum_funding,
+        intervals: intervals.min(u32::MAX as u64) as u32,
+        last_funding_ts: accrued_until,
+    })
+}
+
+/// Realize a position's accrued funding into its account's cash
+///
+/// Moves `last_funding` up to the instrument's `cum_funding`, so equity is
+/// unchanged. Returns the amount paid by the account (negative if received).
+pub fn settle_position_funding(slab: &mut SlabState, pos_idx: u32) -> Result<i128, PercolatorError> {
+    let (account_idx, instrument_idx, qty, last_funding) = {
+        let pos = slab
+            .positions
+            .get(pos_idx)
+            .ok_or(PercolatorError::PositionNotFound)?;
+
+        (pos.account_idx, pos.instrument_idx, pos.qty, pos.last_funding)
+    };
+
+    let cum_funding = slab
+        .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?
+        .cum_funding;
+
+    let payment = calculate_funding_payment(qty, cum_funding, last_funding);
+
+    if let Some(pos) = slab.positions.get_mut(pos_idx) {
+        pos.last_funding = cum_funding;
+    }
+    let account = slab
+        .get_account_mut(account_idx)
+        .ok_or(PercolatorError::InvalidAccount)?;
+    account.cash = account.cash.saturating_sub(payment);
+
+    Ok(payment)
+}
+
+/// Realize accrued funding on every position of an account
+///
+/// Returns the net amount paid by the account (negative if received).
+pub fn settle_funding(slab: &mut SlabState, account_idx: u32) -> Result<i128, PercolatorError> {
+    let mut pos_idx = slab
+        .get_account(account_idx)
+        .ok_or(PercolatorError::InvalidAccount)?
+        .position_head;
+
+    let mut total = 0i128;
+    while pos_idx != u32::MAX {
+        total = total.saturating_add(settle_position_funding(slab, pos_idx)?);
+        pos_idx = slab
+            .positions
+            .get(pos_idx)
+            .ok_or(PercolatorError::PositionNotFound)?
+            .next_in_account;
+    }
+
+    Ok(total)
+}
@@ -0,0 +1,83 @@
+//! Insurance fund - per-slab balance fed by fees that absorbs the negative
+//! cash of bankrupt accounts
+
+use crate::state::SlabState;
+use percolator_common::*;
+
+/// Insurance fund state
+///
+/// Written back as program return data. Wire layout (v1, little-endian):
+/// `version u8 | balance u128 | paid_out u128`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InsuranceResult {
+    pub balance: u128,
+    /// Total spent covering bankrupt accounts
+    pub paid_out: u128,
+}
+
+impl InsuranceResult {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 16 + 16;
+
+    /// Encode as return data
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u128(self.balance);
+        w.write_u128(self.paid_out);
+        buf
+    }
+
+    /// Decode from return data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let result = Self {
+            balance: r.read_u128()?,
+            paid_out: r.read_u128()?,
+        };
+        r.finish()?;
+        Ok(result)
+    }
+}
+
+/// Current insurance fund state
+pub fn insurance_state(slab: &SlabState) -> InsuranceResult {
+    InsuranceResult {
+        balance: slab.header.insurance_balance,
+        paid_out: slab.header.insurance_paid_out,
+    }
+}
+
+/// Credit `share_bps` of a collected fee to the insurance fund
+///
+/// Returns the amount credited.
+pub fn credit_insurance(slab: &mut SlabState, fee: u128, share_bps: u64) -> u128 {
+    let share = fee.saturating_mul(share_bps as u128) / 10_000;
+    slab.header.insurance_balance = slab.header.insurance_balance.saturating_add(share);
+    share
+}
+
+/// Bring a bankrupt account's negative cash back towards zero from the fund
+///
+/// Only accounts without open positions are covered; their negative cash is
+/// a realized loss nobody else will pay. Returns the amount paid, which is
+/// less than the shortfall when the fund runs dry.
+pub fn cover_bankruptcy(slab: &mut SlabState, account_idx: u32) -> Result<u128, PercolatorError> {
+    let account = slab
+        .get_account(account_idx)
+        .ok_or(PercolatorError::InvalidAccount)?;
+    if account.position_head != u32::MAX || account.cash >= 0 {
+        return Ok(0);
+    }
+
+    let paid = core::cmp::min(account.cash.unsigned_abs(), slab.header.insurance_balance);
+    if let Some(account) = slab.get_account_mut(account_idx) {
+        account.cash = account.cash.saturating_add(paid as i128);
+    }
+    slab.header.insurance_balance -= paid;
+    slab.header.insurance_paid_out = slab.header.insurance_paid_out.saturating_add(paid);
+
+    Ok(paid)
+}
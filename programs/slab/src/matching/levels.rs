@@ -0,0 +1,312 @@
+//! Price-level index over the order book lists
+//!
+//! Each book list (live or pending, bids or asks) keeps its orders in one
+//! doubly-linked list in price-time order. A `PriceLevel` marks the run of
+//! orders at one price (head, tail, count), and a crit-bit tree keyed by price
+//! finds a level, the best level and a level's neighbours in at most 64 steps
+//! however deep the book is. Tree roots live on the instrument; inner nodes
+//! and levels come from slab pools, so nothing is heap allocated.
+
+use crate::state::SlabState;
+use percolator_common::*;
+
+/// Empty tree
+const EMPTY: u32 = u32::MAX;
+
+fn is_leaf(node_ref: u32) -> bool {
+    node_ref & LEVEL_LEAF != 0
+}
+
+fn bit(price: u64, crit_bit: u8) -> usize {
+    ((price >> crit_bit) & 1) as usize
+}
+
+/// Tree root of one book list
+pub fn levels_root(instrument: &Instrument, side: Side, state: OrderState) -> u32 {
+    match (side, state) {
+        (Side::Buy, OrderState::LIVE) => instrument.bids_levels,
+        (Side::Buy, OrderState::PENDING) => instrument.bids_pending_levels,
+        (Side::Sell, OrderState::LIVE) => instrument.asks_levels,
+        (Side::Sell, OrderState::PENDING) => instrument.asks_pending_levels,
+    }
+}
+
+fn levels_root_mut(instrument: &mut Instrument, side: Side, state: OrderState) -> &mut u32 {
+    match (side, state) {
+        (Side::Buy, OrderState::LIVE) => &mut instrument.bids_levels,
+        (Side::Buy, OrderState::PENDING) => &mut instrument.bids_pending_levels,
+        (Side::Sell, OrderState::LIVE) => &mut instrument.asks_levels,
+        (Side::Sell, OrderState::PENDING) => &mut instrument.asks_pending_levels,
+    }
+}
+
+fn root(slab: &SlabState, instrument_idx: u16, side: Side, state: OrderState) -> Result<u32, PercolatorError> {
+    let instrument = slab
+        .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?;
+    Ok(levels_root(instrument, side, state))
+}
+
+fn set_root(
+    slab: &mut SlabState,
+    instrument_idx: u16,
+    side: Side,
+    state: OrderState,
+    node_ref: u32,
+) -> Result<(), PercolatorError> {
+    let instrument = slab
+        .get_instrument_mut(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?;
+    *levels_root_mut(instrument, side, state) = node_ref;
+    Ok(())
+}
+
+fn node(slab: &SlabState, node_ref: u32) -> Result<&LevelNode, PercolatorError> {
+    slab.level_nodes.get(node_ref).ok_or(PercolatorError::BookCorrupted)
+}
+
+fn level_price(slab: &SlabState, level_idx: u32) -> Result<u64, PercolatorError> {
+    Ok(slab
+        .price_levels
+        .get(level_idx)
+        .ok_or(PercolatorError::BookCorrupted)?
+        .price)
+}
+
+/// Leaf reached by following `price`'s bits from `node_ref`
+fn descend(slab: &SlabState, mut node_ref: u32, price: u64) -> Result<u32, PercolatorError> {
+    while !is_leaf(node_ref) {
+        let n = node(slab, node_ref)?;
+        node_ref = n.children[bit(price, n.crit_bit)];
+    }
+    Ok(node_ref & !LEVEL_LEAF)
+}
+
+/// Lowest (`dir` 0) or highest (`dir` 1) level under `node_ref`
+fn extreme(slab: &SlabState, mut node_ref: u32, dir: usize) -> Result<u32, PercolatorError> {
+    while !is_leaf(node_ref) {
+        node_ref = node(slab, node_ref)?.children[dir];
+    }
+    Ok(node_ref & !LEVEL_LEAF)
+}
+
+/// Level with the closest price above (`up`) or below `price`
+///
+/// `price` itself need not be in the tree.
+fn neighbor(slab: &SlabState, root: u32, price: u64, up: bool) -> Result<Option<u32>, PercolatorError> {
+    if root == EMPTY {
+        return Ok(None);
+    }
+
+    let closest = level_price(slab, descend(slab, root, price)?)?;
+    let crit = (closest != price).then(|| (63 - (closest ^ price).leading_zeros()) as u8);
+
+    // Walk down to the leaf for `price`, or to the subtree it would split off,
+    // remembering the deepest branch on the far side of the path
+    let mut node_ref = root;
+    let mut branch = None;
+    while !is_leaf(node_ref) {
+        let n = node(slab, node_ref)?;
+        if crit.is_some_and(|c| n.crit_bit < c) {
+            break;
+        }
+        let dir = bit(price, n.crit_bit);
+        if up && dir == 0 {
+            branch = Some(n.children[1]);
+        } else if !up && dir == 1 {
+            branch = Some(n.children[0]);
+        }
+        node_ref = n.children[dir];
+    }
+
+    let dir = if up { 0 } else { 1 };
+    let beyond = match crit {
+        // Every price in the subtree lies on the requested side of `price`
+        Some(c) if (bit(price, c) == 0) == up => Some(node_ref),
+        _ => branch,
+    };
+    beyond.map(|b| extreme(slab, b, dir)).transpose()
+}
+
+/// Level holding orders at `price` in one book list
+pub fn find_level(
+    slab: &SlabState,
+    instrument_idx: u16,
+    side: Side,
+    state: OrderState,
+    price: u64,
+) -> Result<Option<u32>, PercolatorError> {
+    let root = root(slab, instrument_idx, side, state)?;
+    if root == EMPTY {
+        return Ok(None);
+    }
+
+    let level_idx = descend(slab, root, price)?;
+    Ok((level_price(slab, level_idx)? == price).then_some(level_idx))
+}
+
+/// Highest priority level of one book list
+pub fn best_level(
+    slab: &SlabState,
+    instrument_idx: u16,
+    side: Side,
+    state: OrderState,
+) -> Result<Option<u32>, PercolatorError> {
+    let root = root(slab, instrument_idx, side, state)?;
+    if root == EMPTY {
+        return Ok(None);
+    }
+
+    let dir = match side {
+        Side::Buy => 1,
+        Side::Sell => 0,
+    };
+    extreme(slab, root, dir).map(Some)
+}
+
+/// Next level behind `price` in priority order (lower bid, higher ask)
+pub fn next_level(
+    slab: &SlabState,
+    instrument_idx: u16,
+    side: Side,
+    state: OrderState,
+    price: u64,
+) -> Result<Option<u32>, PercolatorError> {
+    let root = root(slab, instrument_idx, side, state)?;
+    neighbor(slab, root, price, side == Side::Sell)
+}
+
+/// Next level ahead of `price` in priority order (higher bid, lower ask)
+pub fn prev_level(
+    slab: &SlabState,
+    instrument_idx: u16,
+    side: Side,
+    state: OrderState,
+    price: u64,
+) -> Result<Option<u32>, PercolatorError> {
+    let root = root(slab, instrument_idx, side, state)?;
+    neighbor(slab, root, price, side == Side::Buy)
+}
+
+/// Create an empty level at `price`, which must not have one yet
+pub(crate) fn add_level(
+    slab: &mut SlabState,
+    instrument_idx: u16,
+    side: Side,
+    state: OrderState,
+    price: u64,
+) -> Result<u32, PercolatorError> {
+    let root = root(slab, instrument_idx, side, state)?;
+
+    let level_idx = slab.price_levels.alloc().ok_or(PercolatorError::PoolFull)?;
+    if let Some(level) = slab.price_levels.get_mut(level_idx) {
+        *level = PriceLevel {
+            price,
+            head: u32::MAX,
+            tail: u32::MAX,
+            count: 0,
+            next_free: u32::MAX,
+            used: true,
+            _padding: [0; 7],
+        };
+    }
+    let leaf = level_idx | LEVEL_LEAF;
+
+    if root == EMPTY {
+        return set_root(slab, instrument_idx, side, state, leaf).map(|_| level_idx);
+    }
+
+    let closest = level_price(slab, descend(slab, root, price)?)?;
+    if closest == price {
+        slab.price_levels.free(level_idx);
+        return Err(PercolatorError::BookCorrupted);
+    }
+    let crit = (63 - (closest ^ price).leading_zeros()) as u8;
+
+    // The new inner node goes above the first node splitting on a lower bit
+    let mut parent: Option<(u32, usize)> = None;
+    let mut node_ref = root;
+    while !is_leaf(node_ref) {
+        let n = node(slab, node_ref)?;
+        if n.crit_bit < crit {
+            break;
+        }
+        let dir = bit(price, n.crit_bit);
+        parent = Some((node_ref, dir));
+        node_ref = n.children[dir];
+    }
+
+    let Some(node_idx) = slab.level_nodes.alloc() else {
+        slab.price_levels.free(level_idx);
+        return Err(PercolatorError::PoolFull);
+    };
+    let dir = bit(price, crit);
+    let mut children = [node_ref; 2];
+    children[dir] = leaf;
+    if let Some(n) = slab.level_nodes.get_mut(node_idx) {
+        *n = LevelNode {
+            children,
+            crit_bit: crit,
+            used: true,
+            _padding: [0; 2],
+            next_free: u32::MAX,
+        };
+    }
+
+    match parent {
+        Some((parent_ref, parent_dir)) => {
+            if let Some(p) = slab.level_nodes.get_mut(parent_ref) {
+                p.children[parent_dir] = node_idx;
+            }
+        }
+        None => set_root(slab, instrument_idx, side, state, node_idx)?,
+    }
+
+    Ok(level_idx)
+}
+
+/// Remove an emptied level from its tree and free it
+pub(crate) fn drop_level(
+    slab: &mut SlabState,
+    instrument_idx: u16,
+    side: Side,
+    state: OrderState,
+    level_idx: u32,
+) -> Result<(), PercolatorError> {
+    let root = root(slab, instrument_idx, side, state)?;
+    let price = level_price(slab, level_idx)?;
+
+    let mut grandparent: Option<(u32, usize)> = None;
+    let mut parent: Option<(u32, usize)> = None;
+    let mut node_ref = root;
+    while !is_leaf(node_ref) {
+        let n = node(slab, node_ref)?;
+        let dir = bit(price, n.crit_bit);
+        grandparent = parent;
+        parent = Some((node_ref, dir));
+        node_ref = n.children[dir];
+    }
+    if node_ref != level_idx | LEVEL_LEAF {
+        return Err(PercolatorError::BookCorrupted);
+    }
+
+    // The sibling subtree takes the parent's place
+    match parent {
+        None => set_root(slab, instrument_idx, side, state, EMPTY)?,
+        Some((parent_ref, parent_dir)) => {
+            let sibling = node(slab, parent_ref)?.children[1 - parent_dir];
+            slab.level_nodes.free(parent_ref);
+            match grandparent {
+                Some((gp_ref, gp_dir)) => {
+                    if let Some(gp) = slab.level_nodes.get_mut(gp_ref) {
+                        gp.children[gp_dir] = sibling;
+                    }
+                }
+                None => set_root(slab, instrument_idx, side, state, sibling)?,
+            }
+        }
+    }
+
+    slab.price_levels.free(level_idx);
+    Ok(())
+}
@@ -0,0 +1,269 @@
+//! Liquidation - close an under-margined account's positions against the
+//! live book, within a price band around mark
+
+use crate::matching::adl::auto_deleverage;
+use crate::matching::commit::{cancel, commit};
+use crate::matching::insurance::{cover_bankruptcy, credit_insurance};
+use crate::matching::orders::cancel_all;
+use crate::matching::reserve::reserve;
+use crate::matching::risk::{calculate_equity, calculate_margin_requirements, get_position_qty};
+use crate::state::SlabState;
+use percolator_common::*;
+
+/// LiquidationCall result
+///
+/// Written back as program return data. Wire layout (v3, little-endian):
+/// `version u8 | closed_qty u64 | closed_notional u128 | liq_fee u128 |
+///  adl_qty u64 | insurance_used u128 | remaining_deficit u128`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiquidationResult {
+    /// Position qty closed across all instruments
+    pub closed_qty: u64,
+    pub closed_notional: u128,
+    /// Liquidation fee charged to the account
+    pub liq_fee: u128,
+    /// Position qty force-closed against opposing traders by ADL
+    pub adl_qty: u64,
+    /// Insurance paid to bring the account's negative cash towards zero
+    pub insurance_used: u128,
+    /// Maintenance margin still missing (0 once restored); with every
+    /// position closed this is the negative cash insurance could not cover
+    pub remaining_deficit: u128,
+}
+
+impl LiquidationResult {
+    pub const VERSION: u8 = 3;
+    pub const LEN: usize = 1 + 8 + 16 + 16 + 8 + 16 + 16;
+
+    /// Encode as return data
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u64(self.closed_qty);
+        w.write_u128(self.closed_notional);
+        w.write_u128(self.liq_fee);
+        w.write_u64(self.adl_qty);
+        w.write_u128(self.insurance_used);
+        w.write_u128(self.remaining_deficit);
+        buf
+    }
+
+    /// Decode from return data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let result = Self {
+            closed_qty: r.read_u64()?,
+            closed_notional: r.read_u128()?,
+            liq_fee: r.read_u128()?,
+            adl_qty: r.read_u64()?,
+            insurance_used: r.read_u128()?,
+            remaining_deficit: r.read_u128()?,
+        };
+        r.finish()?;
+        Ok(result)
+    }
+}
+
+/// Liquidate an account whose equity is below its maintenance margin
+///
+/// Fails with `NotLiquidatable` unless `equity < mm`. The account's resting
+/// orders are pulled first, then each position is closed with reduce-only
+/// IOC reserve/commit sweeps of the live book, priced no worse than
+/// `liq_band_bps` from the mark at the time of the call. Sweeps are sized to
+/// restore maintenance margin and stop as soon as it is (partial
+/// liquidation), or when the book within the band runs dry. `liq_fee_bps`
+/// of the closed notional is charged to the account, capped at its remaining
+/// positive equity, and
+/// `insurance_liq_share_bps` of that fee goes to the insurance fund. An
+/// account left flat with negative cash is covered from the fund as far as
+/// its balance allows.
+///
+/// If positions are left over because the band ran dry, and the account's
+/// negative equity is more than the fund holds, they are auto-deleveraged
+/// (see `matching::adl`) at the bankruptcy price. While the fund can cover
+/// the loss the positions stay open for a later call to close against the
+/// book.
+///
+/// The roundtrip guard is suspended for the sweeps: forced closes are not
+/// aggressor flow and must not be clipped or taxed.
+pub fn liquidate(
+    slab: &mut SlabState,
+    account_idx: u32,
+    current_ts: u64,
+) -> Result<LiquidationResult, PercolatorError> {
+    if margin_deficit(slab, account_idx)? == 0 {
+        return Err(PercolatorError::NotLiquidatable);
+    }
+
+    cancel_all(slab, account_idx, None)?;
+
+    let arg_mode = core::mem::replace(&mut slab.header.arg_mode, ArgMode::Off);
+    let swept = sweep_positions(slab, account_idx, current_ts);
+    slab.header.arg_mode = arg_mode;
+    let mut result = swept?;
+
+    // Last resort: neither the book nor the fund can absorb the loss
+    let has_positions = slab
+        .get_account(account_idx)
+        .ok_or(PercolatorError::InvalidAccount)?
+        .position_head
+        != u32::MAX;
+    let equity = calculate_equity(slab, account_idx)?;
+    if has_positions && equity < 0 && equity.unsigned_abs() > slab.header.insurance_balance {
+        result.adl_qty = auto_deleverage(slab, account_idx, current_ts)?;
+    }
+
+    result.insurance_used = cover_bankruptcy(slab, account_idx)?;
+    result.remaining_deficit = margin_deficit(slab, account_idx)?;
+    Ok(result)
+}
+
+/// Close positions one instrument at a time until margin is restored
+fn sweep_positions(
+    slab: &mut SlabState,
+    account_idx: u32,
+    current_ts: u64,
+) -> Result<LiquidationResult, PercolatorError> {
+    let mut result = LiquidationResult {
+        closed_qty: 0,
+        closed_notional: 0,
+        liq_fee: 0,
+        adl_qty: 0,
+        insurance_used: 0,
+        remaining_deficit: 0,
+    };
+
+    let mut pos_idx = slab
+        .get_account(account_idx)
+        .ok_or(PercolatorError::InvalidAccount)?
+        .position_head;
+
+    while pos_idx != u32::MAX {
+        // Closing the position frees its slot, so step past it first
+        let (instrument_idx, next) = {
+            let pos = slab
+                .positions
+                .get(pos_idx)
+                .ok_or(PercolatorError::PositionNotFound)?;
+            (pos.instrument_idx, pos.next_in_account)
+        };
+
+        let (tick, lot, mark_px) = {
+            let instrument = slab
+                .get_instrument(instrument_idx)
+                .ok_or(PercolatorError::InvalidInstrument)?;
+            (instrument.tick, instrument.lot, instrument.mark_price)
+        };
+
+        loop {
+            let deficit = margin_deficit(slab, account_idx)?;
+            if deficit == 0 {
+                return Ok(result);
+            }
+
+            let position_qty = get_position_qty(slab, account_idx, instrument_idx);
+            if position_qty == 0 {
+                break;
+            }
+
+            let side = if position_qty > 0 { Side::Sell } else { Side::Buy };
+            let qty = close_qty(slab, instrument_idx, position_qty, deficit, lot)?;
+            let limit_px = band_limit(mark_px, slab.header.liq_band_bps, tick, side);
+
+            let res = reserve(
+                slab,
+                account_idx,
+                instrument_idx,
+                side,
+                qty,
+                limit_px,
+                0,
+                NO_COMMITMENT,
+                0,
+                TimeInForce::IOC,
+                ORDER_FLAG_REDUCE_ONLY,
+            )?;
+            if res.filled_qty == 0 {
+                cancel(slab, res.hold_id)?;
+                break;
+            }
+
+            let fill = commit(slab, res.hold_id, &[0; 16], current_ts)?;
+            if fill.filled_qty == 0 {
+                break;
+            }
+
+            // The fee never pushes the account below zero equity: a loss
+            // beyond that is the insurance fund's, not extra fee income
+            let notional = fill.total_debit.saturating_sub(fill.total_fee);
+            let equity = calculate_equity(slab, account_idx)?.max(0) as u128;
+            let liq_fee = core::cmp::min((notional * slab.header.liq_fee_bps as u128) / 10_000, equity);
+            let account = slab
+                .get_account_mut(account_idx)
+                .ok_or(PercolatorError::InvalidAccount)?;
+            account.cash = account.cash.saturating_sub(liq_fee as i128);
+            slab.header.liq_fees_collected = slab.header.liq_fees_collected.saturating_add(liq_fee);
+            let insurance_share = slab.header.insurance_liq_share_bps;
+            credit_insurance(slab, liq_fee, insurance_share);
+
+            result.closed_qty = result.closed_qty.saturating_add(fill.filled_qty);
+            result.closed_notional = result.closed_notional.saturating_add(notional);
+            result.liq_fee = result.liq_fee.saturating_add(liq_fee);
+        }
+
+        pos_idx = next;
+    }
+
+    Ok(result)
+}
+
+/// Maintenance margin the account is short of (0 if none)
+fn margin_deficit(slab: &SlabState, account_idx: u32) -> Result<u128, PercolatorError> {
+    let equity = calculate_equity(slab, account_idx)?;
+    let (_, mm) = calculate_margin_requirements(slab, account_idx)?;
+
+    Ok((mm as i128).saturating_sub(equity).max(0) as u128)
+}
+
+/// Qty to close so the freed maintenance margin covers `deficit`
+///
+/// Each closed contract frees its MM at mark but pays the liquidation fee;
+/// if the fee eats the whole relief the position is closed outright. Slippage
+/// is not priced in, so the caller sweeps again while a deficit remains.
+fn close_qty(
+    slab: &SlabState,
+    instrument_idx: u16,
+    position_qty: i64,
+    deficit: u128,
+    lot: u64,
+) -> Result<u64, PercolatorError> {
+    let instrument = slab
+        .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?;
+    let position = position_qty.unsigned_abs();
+
+    let mm_per_lot = calculate_mm(lot as i64, instrument.contract_size, instrument.mark_price, slab.header.mmr);
+    let fee_per_lot = (mul_u64(lot, instrument.mark_price) * slab.header.liq_fee_bps as u128) / 10_000;
+    let relief = mm_per_lot.saturating_sub(fee_per_lot);
+    if relief == 0 {
+        return Ok(position);
+    }
+
+    let lots = deficit.div_ceil(relief);
+    let qty = u64::try_from(lots.saturating_mul(lot as u128)).unwrap_or(u64::MAX);
+    Ok(core::cmp::min(qty, position))
+}
+
+/// Worst price a liquidation sweep may fill at, rounded inside the band
+fn band_limit(mark_px: u64, band_bps: u64, tick: u64, side: Side) -> u64 {
+    let band = (mul_u64(mark_px, band_bps) / 10_000) as u64;
+    match side {
+        Side::Buy => round_to_tick(mark_px.saturating_add(band), tick),
+        Side::Sell => {
+            let floor = mark_px.saturating_sub(band);
+            round_to_tick(floor.saturating_add(tick - 1), tick)
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Mark price - index plus a clamped, time-averaged book premium
+//!
+//! Risk values positions at mark so a single thin quote cannot move margin
+//! or trigger liquidations; funding keeps accruing against the index.
+
+use crate::matching::book::get_best_prices;
+use crate::state::SlabState;
+use percolator_common::*;
+
+/// Premium of the live book mid over index, in basis points
+///
+/// Returns 0 when either side of the book is empty.
+pub fn book_premium_bps(
+    slab: &SlabState,
+    instrument_idx: u16,
+    index_price: u64,
+) -> Result<i64, PercolatorError> {
+    let (Some(bid), Some(ask)) = get_best_prices(slab, instrument_idx)? else {
+        return Ok(0);
+    };
+    if index_price == 0 {
+        return Ok(0);
+    }
+
+    let mid = (bid as i128 + ask as i128) / 2;
+    let premium = (mid - index_price as i128) * 10_000 / index_price as i128;
+    Ok(premium.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+}
+
+/// Sample the book premium into the instrument's average and recompute mark
+///
+/// The average is a time-weighted average of held premiums: each sample is
+/// taken to hold until the next one, so on every update the average first
+/// moves towards the previous sample in proportion to how long it held,
+/// reaching it after `mark_ema_window_ms`, and the current premium only
+/// counts once time passes with it on the book. A single sample after a long
+/// gap therefore cannot move mark. The first sample only anchors the clock.
+/// Mark is the index adjusted by the average premium, clamped to
+/// `mark_premium_clamp_bps`.
+pub fn update_mark_price(
+    slab: &mut SlabState,
+    instrument_idx: u16,
+    current_ts: u64,
+) -> Result<u64, PercolatorError> {
+    let window_ms = slab.header.mark_ema_window_ms.max(1);
+    let clamp = slab.header.mark_premium_clamp_bps.min(10_000) as i64;
+
+    let (index_price, ema, held, last_ts) = {
+        let instrument = slab
+            .get_instrument(instrument_idx)
+            .ok_or(PercolatorError::InvalidInstrument)?;
+
+        (
+            instrument.index_price,
+            instrument.premium_ema_bps,
+            instrument.premium_sample_bps,
+            instrument.premium_ts,
+        )
+    };
+
+    let sample = book_premium_bps(slab, instrument_idx, index_price)?;
+    let ema = if last_ts == 0 {
+        ema
+    } else {
+        let elapsed = current_ts.saturating_sub(last_ts).min(window_ms);
+        let step = (held as i128 - ema as i128) * elapsed as i128 / window_ms as i128;
+        (ema as i128 + step) as i64
+    };
+
+    let premium = ema.clamp(-clamp, clamp);
+    let mark = (index_price as i128 * (10_000 + premium as i128) / 10_000).max(1) as u64;
+
+    let instrument = slab
+        .get_instrument_mut(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?;
+    instrument.premium_ema_bps = ema;
+    instrument.premium_sample_bps = sample;
+    instrument.premium_ts = instrument.premium_ts.max(current_ts);
+    instrument.mark_price = mark;
+
+    Ok(mark)
+}
@@ -1,9 +1,27 @@
 pub mod book;
+pub mod levels;
 pub mod reserve;
 pub mod commit;
 pub mod risk;
+pub mod orders;
+pub mod funding;
+pub mod oracle;
+pub mod mark;
+pub mod arg;
+pub mod liquidation;
+pub mod insurance;
+pub mod adl;
 
 pub use book::*;
+pub use levels::*;
 pub use reserve::*;
 pub use commit::*;
 pub use risk::*;
+pub use orders::*;
+pub use funding::*;
+pub use oracle::*;
+pub use mark::*;
+pub use arg::*;
+pub use liquidation::*;
+pub use insurance::*;
+pub use adl::*;
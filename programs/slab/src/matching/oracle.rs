@@ -0,0 +1,108 @@
+//! Oracle - ingestion of external index prices
+
+use crate::matching::mark::update_mark_price;
+use crate::state::SlabState;
+use percolator_common::*;
+use pinocchio::pubkey::Pubkey;
+
+/// UpdateIndexPrice result
+///
+/// Written back as program return data. Wire layout (v1, little-endian):
+/// `version u8 | index_price u64 | mark_price u64 | publish_ts u64 | conf_bps u64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexPriceResult {
+    pub index_price: u64,
+    /// Mark price recomputed against the new index
+    pub mark_price: u64,
+    /// Oracle publish time in milliseconds
+    pub publish_ts: u64,
+    /// Confidence interval of the accepted reading (bps of price)
+    pub conf_bps: u64,
+}
+
+impl IndexPriceResult {
+    pub const VERSION: u8 = 2;
+    pub const LEN: usize = 1 + 8 + 8 + 8 + 8;
+
+    /// Encode as return data
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u64(self.index_price);
+        w.write_u64(self.mark_price);
+        w.write_u64(self.publish_ts);
+        w.write_u64(self.conf_bps);
+        buf
+    }
+
+    /// Decode from return data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let result = Self {
+            index_price: r.read_u64()?,
+            mark_price: r.read_u64()?,
+            publish_ts: r.read_u64()?,
+            conf_bps: r.read_u64()?,
+        };
+        r.finish()?;
+        Ok(result)
+    }
+}
+
+/// Update an instrument's index price from its bound oracle account
+///
+/// The reading is rejected if it comes from any account other than the one
+/// bound at listing, is older than `max_oracle_staleness_ms` relative to
+/// `current_ts`, is older than the reading already recorded, or has a
+/// confidence interval wider than `max_oracle_conf_bps`. A reading published
+/// after `current_ts` fails with `InvalidOracle`: recorded, it would hold
+/// every honest reading off until the clock caught up. Mark is recomputed
+/// against the accepted index.
+pub fn update_index_price(
+    slab: &mut SlabState,
+    instrument_idx: u16,
+    oracle_key: &Pubkey,
+    oracle_data: &[u8],
+    current_ts: u64,
+) -> Result<IndexPriceResult, PercolatorError> {
+    let max_staleness = slab.header.max_oracle_staleness_ms;
+    let max_conf_bps = slab.header.max_oracle_conf_bps;
+
+    let instrument = slab
+        .get_instrument_mut(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?;
+
+    if instrument.oracle == [0; 32] || oracle_key != &instrument.oracle {
+        return Err(PercolatorError::InvalidAccount);
+    }
+
+    let reading = OraclePrice::unpack(oracle_data)?;
+    let publish_ts = reading.publish_ms();
+
+    if publish_ts > current_ts {
+        return Err(PercolatorError::InvalidOracle);
+    }
+    if current_ts - publish_ts > max_staleness || publish_ts < instrument.index_publish_ts {
+        return Err(PercolatorError::OracleStale);
+    }
+
+    let (price, conf) = reading.to_price_units()?;
+    let conf_bps = confidence_bps(price, conf);
+    if conf_bps > max_conf_bps {
+        return Err(PercolatorError::OracleConfidenceTooWide);
+    }
+
+    instrument.index_price = price;
+    instrument.index_publish_ts = publish_ts;
+
+    let mark_price = update_mark_price(slab, instrument_idx, current_ts)?;
+
+    Ok(IndexPriceResult {
+        index_price: price,
+        mark_price,
+        publish_ts,
+        conf_bps,
+    })
+}
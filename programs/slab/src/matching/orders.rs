@@ -0,0 +1,507 @@
+//! Maker order lifecycle - placing, amending and cancelling resting limit orders
+
+use crate::matching::book::{insert_order, is_frozen, remove_order, would_jump_freeze};
+use crate::matching::risk::check_margin_pre_trade;
+use crate::state::SlabState;
+use percolator_common::*;
+
+/// PlaceOrder result
+///
+/// Written back as program return data. Wire layout (v1, little-endian):
+/// `version u8 | order_id u64 | order_idx u32 | state u8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlaceOrderResult {
+    pub order_id: u64,
+    pub order_idx: u32,
+    pub state: OrderState,
+}
+
+impl PlaceOrderResult {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 8 + 4 + 1;
+
+    /// Encode as return data
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u64(self.order_id);
+        w.write_u32(self.order_idx);
+        w.write_u8(self.state as u8);
+        buf
+    }
+
+    /// Decode from return data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let result = Self {
+            order_id: r.read_u64()?,
+            order_idx: r.read_u32()?,
+            state: match r.read_u8()? {
+                0 => OrderState::LIVE,
+                1 => OrderState::PENDING,
+                _ => return Err(PercolatorError::InvalidOrderState),
+            },
+        };
+        r.finish()?;
+        Ok(result)
+    }
+}
+
+/// CancelOrder result
+///
+/// Written back as program return data. Wire layout (v1, little-endian):
+/// `version u8 | order_id u64 | cancelled_qty u64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CancelOrderResult {
+    pub order_id: u64,
+    pub cancelled_qty: u64,
+}
+
+impl CancelOrderResult {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 8 + 8;
+
+    /// Encode as return data
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u64(self.order_id);
+        w.write_u64(self.cancelled_qty);
+        buf
+    }
+
+    /// Decode from return data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let result = Self {
+            order_id: r.read_u64()?,
+            cancelled_qty: r.read_u64()?,
+        };
+        r.finish()?;
+        Ok(result)
+    }
+}
+
+/// CancelAll result
+///
+/// Written back as program return data. Wire layout (v1, little-endian):
+/// `version u8 | cancelled u32 | skipped u32`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CancelAllResult {
+    /// Orders unlinked and freed
+    pub cancelled: u32,
+    /// Orders left on the book because part of them is locked by a reservation
+    pub skipped: u32,
+}
+
+impl CancelAllResult {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 4 + 4;
+
+    /// Encode as return data
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u32(self.cancelled);
+        w.write_u32(self.skipped);
+        buf
+    }
+
+    /// Decode from return data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let result = Self {
+            cancelled: r.read_u32()?,
+            skipped: r.read_u32()?,
+        };
+        r.finish()?;
+        Ok(result)
+    }
+}
+
+/// ModifyOrder result
+///
+/// Written back as program return data. Wire layout (v1, little-endian):
+/// `version u8 | order_id u64 | order_idx u32 | state u8 | requeued u8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModifyOrderResult {
+    /// Order id after the amend (new id when requeued)
+    pub order_id: u64,
+    pub order_idx: u32,
+    pub state: OrderState,
+    /// Whether the order lost its time priority
+    pub requeued: bool,
+}
+
+impl ModifyOrderResult {
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 1 + 8 + 4 + 1 + 1;
+
+    /// Encode as return data
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u64(self.order_id);
+        w.write_u32(self.order_idx);
+        w.write_u8(self.state as u8);
+        w.write_u8(self.requeued as u8);
+        buf
+    }
+
+    /// Decode from return data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let result = Self {
+            order_id: r.read_u64()?,
+            order_idx: r.read_u32()?,
+            state: match r.read_u8()? {
+                0 => OrderState::LIVE,
+                1 => OrderState::PENDING,
+                _ => return Err(PercolatorError::InvalidOrderState),
+            },
+            requeued: match r.read_u8()? {
+                0 => false,
+                1 => true,
+                _ => return Err(PercolatorError::InvalidInstruction),
+            },
+        };
+        r.finish()?;
+        Ok(result)
+    }
+}
+
+/// Place a resting limit order
+///
+/// DLP accounts post straight into the live book. Everyone else lands in the
+/// pending queue and becomes matchable at the next batch (`epoch + 1`). Live
+/// orders that would queue ahead of a frozen top level are rejected with
+/// `OrderFrozen`.
+pub fn place_order(
+    slab: &mut SlabState,
+    account_idx: u32,
+    instrument_idx: u16,
+    side: Side,
+    price: u64,
+    qty: u64,
+    current_ts: u64,
+) -> Result<PlaceOrderResult, PercolatorError> {
+    validate_quote(slab, account_idx, instrument_idx, side, price, qty)?;
+
+    let (maker_class, state, eligible_epoch) = queue_placement(slab, account_idx, instrument_idx)?;
+    if state == OrderState::LIVE && would_jump_freeze(slab, instrument_idx, side, price)? {
+        return Err(PercolatorError::OrderFrozen);
+    }
+
+    let order_idx = slab.orders.alloc().ok_or(PercolatorError::PoolFull)?;
+    let order_id = slab.header.next_order_id();
+
+    if let Some(order) = slab.orders.get_mut(order_idx) {
+        *order = Order {
+            order_id,
+            account_idx,
+            instrument_idx,
+            side,
+            tif: TimeInForce::GTC,
+            maker_class,
+            state,
+            eligible_epoch,
+            created_ms: current_ts,
+            price,
+            qty,
+            reserved_qty: 0,
+            qty_orig: qty,
+            next: u32::MAX,
+            prev: u32::MAX,
+            next_in_account: u32::MAX,
+            prev_in_account: u32::MAX,
+            next_free: u32::MAX,
+            used: true,
+            _padding: [0; 3],
+        };
+    }
+
+    if let Err(e) = insert_order(slab, instrument_idx, order_idx, side, price, state) {
+        slab.orders.free(order_idx);
+        return Err(e);
+    }
+    slab.link_order(order_idx)?;
+
+    Ok(PlaceOrderResult {
+        order_id,
+        order_idx,
+        state,
+    })
+}
+
+/// Check a maker quote's price and size against the instrument's tick and lot
+fn check_quote_spec(
+    slab: &SlabState,
+    instrument_idx: u16,
+    price: u64,
+    qty: u64,
+) -> Result<(), PercolatorError> {
+    let instrument = slab
+        .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?;
+
+    if price == 0 {
+        return Err(PercolatorError::InvalidPrice);
+    }
+    if qty == 0 {
+        return Err(PercolatorError::InvalidQuantity);
+    }
+    if !is_tick_aligned(price, instrument.tick) {
+        return Err(PercolatorError::PriceNotAligned);
+    }
+    if !is_lot_aligned(qty, instrument.lot) {
+        return Err(PercolatorError::QuantityNotAligned);
+    }
+
+    Ok(())
+}
+
+/// Check a maker quote against the instrument spec and the maker's margin
+///
+/// The maker must be able to carry the position if the order fills completely.
+fn validate_quote(
+    slab: &SlabState,
+    account_idx: u32,
+    instrument_idx: u16,
+    side: Side,
+    price: u64,
+    qty: u64,
+) -> Result<(), PercolatorError> {
+    check_quote_spec(slab, instrument_idx, price, qty)?;
+
+    let qty_delta = match side {
+        Side::Buy => qty as i64,
+        Side::Sell => -(qty as i64),
+    };
+    if !check_margin_pre_trade(slab, account_idx, instrument_idx, qty_delta)? {
+        return Err(PercolatorError::InsufficientMargin);
+    }
+
+    Ok(())
+}
+
+/// Maker class, book and eligible epoch for a newly queued order
+///
+/// DLP orders go straight to the live book; everyone else waits one batch.
+fn queue_placement(
+    slab: &SlabState,
+    account_idx: u32,
+    instrument_idx: u16,
+) -> Result<(MakerClass, OrderState, u16), PercolatorError> {
+    let epoch = slab
+        .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?
+        .epoch;
+
+    Ok(if slab.is_dlp(account_idx) {
+        (MakerClass::DLP, OrderState::LIVE, epoch)
+    } else {
+        (MakerClass::REG, OrderState::PENDING, epoch.wrapping_add(1))
+    })
+}
+
+/// Slot and contents of the resting (live or pending) order with `order_id`
+///
+/// Filled, cancelled and never issued ids fail with `OrderNotFound`.
+fn resting_order(slab: &SlabState, order_id: u64) -> Result<(u32, &Order), PercolatorError> {
+    let order_idx = slab.find_order(order_id).ok_or(PercolatorError::OrderNotFound)?;
+    let order = slab.orders.get(order_idx).ok_or(PercolatorError::OrderNotFound)?;
+    Ok((order_idx, order))
+}
+
+/// Cancel a resting order owned by `account_idx`
+///
+/// The order is looked up by id through the slab's order id index.
+/// Orders with quantity locked by an outstanding reservation are rejected
+/// with `ReservedQtyExceeded`: the slices still point at the order, so it
+/// must stay on the book until the reservation commits or is cancelled.
+/// Live orders in a frozen top level are rejected with `OrderFrozen`.
+pub fn cancel_order(
+    slab: &mut SlabState,
+    account_idx: u32,
+    order_id: u64,
+) -> Result<CancelOrderResult, PercolatorError> {
+    let (order_idx, order) = resting_order(slab, order_id)?;
+
+    if order.account_idx != account_idx {
+        return Err(PercolatorError::InvalidAccount);
+    }
+    if order.reserved_qty > 0 {
+        return Err(PercolatorError::ReservedQtyExceeded);
+    }
+
+    let instrument_idx = order.instrument_idx;
+    let cancelled_qty = order.qty;
+    if order.state == OrderState::LIVE && is_frozen(slab, instrument_idx, order.side, order.price)? {
+        return Err(PercolatorError::OrderFrozen);
+    }
+
+    remove_order(slab, instrument_idx, order_idx)?;
+    slab.free_order(order_idx);
+
+    Ok(CancelOrderResult {
+        order_id,
+        cancelled_qty,
+    })
+}
+
+/// Cancel every resting order of `account_idx`
+///
+/// When `instrument_idx` is `Some`, only that instrument's orders are pulled.
+/// Live and pending orders are both removed; orders with reserved quantity
+/// or in a frozen top level are left in place and counted as skipped. Only
+/// the account's own order list is walked, never the whole pool.
+pub fn cancel_all(
+    slab: &mut SlabState,
+    account_idx: u32,
+    instrument_idx: Option<u16>,
+) -> Result<CancelAllResult, PercolatorError> {
+    if let Some(idx) = instrument_idx {
+        slab.get_instrument(idx)
+            .ok_or(PercolatorError::InvalidInstrument)?;
+    }
+
+    let mut result = CancelAllResult {
+        cancelled: 0,
+        skipped: 0,
+    };
+
+    let mut order_idx = slab
+        .get_account(account_idx)
+        .ok_or(PercolatorError::InvalidAccount)?
+        .order_head;
+
+    while order_idx != u32::MAX {
+        let order = slab.orders.get(order_idx).ok_or(PercolatorError::OrderNotFound)?;
+        // Freeing the order unlinks it, so step past it first
+        let next = order.next_in_account;
+        let order_instrument = order.instrument_idx;
+
+        if instrument_idx.is_some_and(|idx| idx != order_instrument) {
+            order_idx = next;
+            continue;
+        }
+        let frozen = order.state == OrderState::LIVE
+            && is_frozen(slab, order_instrument, order.side, order.price)?;
+        if order.reserved_qty > 0 || frozen {
+            result.skipped += 1;
+            order_idx = next;
+            continue;
+        }
+
+        remove_order(slab, order_instrument, order_idx)?;
+        slab.free_order(order_idx);
+        result.cancelled += 1;
+        order_idx = next;
+    }
+
+    Ok(result)
+}
+
+/// Amend price and/or quantity of a resting order owned by `account_idx`
+///
+/// The order is looked up by id as in [`cancel_order`]. A requeue keeps the
+/// slot but changes the order id.
+///
+/// Queue priority rules:
+/// - same price and qty not increased: amended in place, keeps order id and
+///   time priority
+/// - price change or qty increase: unlinked and requeued under a new order id,
+///   exactly as if freshly placed (REG orders wait for the next batch again)
+///
+/// The new qty may never drop below `reserved_qty`, and a partially reserved
+/// order cannot be requeued (repriced or sized up) since its slices point at
+/// the order as queued and priced at reserve.
+/// Orders in a frozen top level cannot be amended at all, and a requeue may
+/// not jump ahead of one (`OrderFrozen`).
+pub fn modify_order(
+    slab: &mut SlabState,
+    account_idx: u32,
+    order_id: u64,
+    new_price: u64,
+    new_qty: u64,
+    current_ts: u64,
+) -> Result<ModifyOrderResult, PercolatorError> {
+    let (order_idx, instrument_idx, side, price, qty, reserved_qty, state) = {
+        let (order_idx, order) = resting_order(slab, order_id)?;
+        if order.account_idx != account_idx {
+            return Err(PercolatorError::InvalidAccount);
+        }
+
+        (
+            order_idx,
+            order.instrument_idx,
+            order.side,
+            order.price,
+            order.qty,
+            order.reserved_qty,
+            order.state,
+        )
+    };
+
+    check_quote_spec(slab, instrument_idx, new_price, new_qty)?;
+
+    if new_qty < reserved_qty || (reserved_qty > 0 && (new_price != price || new_qty > qty)) {
+        return Err(PercolatorError::ReservedQtyExceeded);
+    }
+    if state == OrderState::LIVE && is_frozen(slab, instrument_idx, side, price)? {
+        return Err(PercolatorError::OrderFrozen);
+    }
+
+    // Size-down at the same price keeps priority
+    if new_price == price && new_qty <= qty {
+        if let Some(order) = slab.orders.get_mut(order_idx) {
+            order.qty = new_qty;
+        }
+        slab.header.increment_book_seqno();
+
+        return Ok(ModifyOrderResult {
+            order_id,
+            order_idx,
+            state,
+            requeued: false,
+        });
+    }
+
+    validate_quote(slab, account_idx, instrument_idx, side, new_price, new_qty)?;
+    let (maker_class, new_state, eligible_epoch) =
+        queue_placement(slab, account_idx, instrument_idx)?;
+    if new_state == OrderState::LIVE && would_jump_freeze(slab, instrument_idx, side, new_price)? {
+        return Err(PercolatorError::OrderFrozen);
+    }
+
+    remove_order(slab, instrument_idx, order_idx)?;
+    let new_order_id = slab.header.next_order_id();
+    slab.rekey_order(order_idx, new_order_id)?;
+
+    if let Some(order) = slab.orders.get_mut(order_idx) {
+        order.price = new_price;
+        order.qty = new_qty;
+        order.qty_orig = new_qty;
+        order.maker_class = maker_class;
+        order.state = new_state;
+        order.eligible_epoch = eligible_epoch;
+        order.created_ms = current_ts;
+    }
+
+    insert_order(slab, instrument_idx, order_idx, side, new_price, new_state)?;
+
+    Ok(ModifyOrderResult {
+        order_id: new_order_id,
+        order_idx,
+        state: new_state,
+        requeued: true,
+    })
+}
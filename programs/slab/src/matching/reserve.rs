@@ -1,9 +1,19 @@
 //! Reserve operation - walk book and lock slices without executing
 
+use crate::matching::book::{freeze_top_levels, remove_order, would_cross};
+use crate::matching::levels::{best_level, next_level};
+use crate::matching::commit::free_slices;
+use crate::matching::risk::{check_margin_pre_trade, get_position_qty, increases_exposure};
 use crate::state::SlabState;
 use percolator_common::*;
 
 /// Reserve result
+///
+/// Written back as program return data. Wire layout (v3, little-endian):
+/// `version u8 | hold_id u64 | vwap_px u64 | worst_px u64 | max_charge u128 |
+///  expiry_ms u64 | book_seqno u64 | filled_qty u64 | stp_mode u8 |
+///  stp_hits u16 | stp_qty u64 | mark_px u64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ReserveResult {
     pub hold_id: u64,
     pub vwap_px: u64,
@@ -12,9 +22,71 @@ pub struct ReserveResult {
     pub expiry_ms: u64,
     pub book_seqno: u64,
     pub filled_qty: u64,
+    /// Self-trade prevention mode in effect for this walk
+    pub stp_mode: StpMode,
+    /// Own resting orders the walk ran into
+    pub stp_hits: u16,
+    /// Quantity affected by STP (cancelled, decremented or skipped)
+    pub stp_qty: u64,
+    /// Mark snapshot the commit's kill band is measured against
+    pub mark_px: u64,
+}
+
+impl ReserveResult {
+    pub const VERSION: u8 = 3;
+    pub const LEN: usize = 1 + 8 + 8 + 8 + 16 + 8 + 8 + 8 + 1 + 2 + 8 + 8;
+
+    /// Encode as return data
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut buf = [0u8; Self::LEN];
+        let mut w = WireWriter::new(&mut buf);
+        w.write_u8(Self::VERSION);
+        w.write_u64(self.hold_id);
+        w.write_u64(self.vwap_px);
+        w.write_u64(self.worst_px);
+        w.write_u128(self.max_charge);
+        w.write_u64(self.expiry_ms);
+        w.write_u64(self.book_seqno);
+        w.write_u64(self.filled_qty);
+        w.write_u8(self.stp_mode as u8);
+        w.write_u16(self.stp_hits);
+        w.write_u64(self.stp_qty);
+        w.write_u64(self.mark_px);
+        buf
+    }
+
+    /// Decode from return data
+    pub fn unpack(data: &[u8]) -> Result<Self, PercolatorError> {
+        let mut r = WireReader::new(data);
+        r.read_version(Self::VERSION)?;
+        let result = Self {
+            hold_id: r.read_u64()?,
+            vwap_px: r.read_u64()?,
+            worst_px: r.read_u64()?,
+            max_charge: r.read_u128()?,
+            expiry_ms: r.read_u64()?,
+            book_seqno: r.read_u64()?,
+            filled_qty: r.read_u64()?,
+            stp_mode: StpMode::try_from(r.read_u8()?)?,
+            stp_hits: r.read_u16()?,
+            stp_qty: r.read_u64()?,
+            mark_px: r.read_u64()?,
+        };
+        r.finish()?;
+        Ok(result)
+    }
 }
 
 /// Reserve liquidity from the book
+///
+/// Time in force decides what happens to the unfilled remainder:
+/// - `FOK` fails with `InsufficientLiquidity` unless the full qty is locked
+/// - `IOC` keeps only what was locked; the remainder is released
+/// - `GTC` rests the remainder as a maker order at `limit_px` on commit
+///
+/// Fails with `InsufficientMargin` if the filled qty would leave the taker's
+/// initial margin above its equity. Fills that only shrink the position are
+/// always allowed.
 pub fn reserve(
     slab: &mut SlabState,
     account_idx: u32,
@@ -25,14 +97,20 @@ pub fn reserve(
     ttl_ms: u64,
     commitment_hash: [u8; 32],
     route_id: u64,
+    tif: TimeInForce,
+    flags: u8,
 ) -> Result<ReserveResult, PercolatorError> {
+    if flags & !ORDER_FLAGS_ALL != 0 {
+        return Err(PercolatorError::InvalidInstruction);
+    }
+
     // Validate instrument and get needed values
-    let (tick, lot, contract_size) = {
+    let (tick, lot, contract_size, mark_px) = {
         let instrument = slab
             .get_instrument(instrument_idx)
             .ok_or(PercolatorError::InvalidInstrument)?;
 
-        (instrument.tick, instrument.lot, instrument.contract_size)
+        (instrument.tick, instrument.lot, instrument.contract_size, instrument.mark_price)
     };
 
     // Check alignment
@@ -43,6 +121,24 @@ pub fn reserve(
         return Err(PercolatorError::QuantityNotAligned);
     }
 
+    // Reduce-only: never trade through zero or grow the position
+    let qty_order = qty;
+    let qty = if flags & ORDER_FLAG_REDUCE_ONLY != 0 {
+        clip_reduce_only(slab, account_idx, instrument_idx, side, qty)?
+    } else {
+        qty
+    };
+
+    // Post-only: the order may only ever rest, so it must not cross
+    if flags & ORDER_FLAG_POST_ONLY != 0 {
+        if tif != TimeInForce::GTC {
+            return Err(PercolatorError::InvalidTimeInForce);
+        }
+        if would_cross(slab, instrument_idx, side, limit_px)? {
+            return Err(PercolatorError::PostOnlyWouldCross);
+        }
+    }
+
     // Allocate reservation
     let resv_idx = slab
         .reservations
@@ -57,8 +153,58 @@ pub fn reserve(
         Side::Sell => Side::Buy,
     };
 
-    let (filled_qty, total_notional, worst_px, slice_head) =
-        walk_and_reserve(slab, instrument_idx, contra_side, qty, limit_px, resv_idx)?;
+    let stp_mode = slab.header.stp_mode;
+    let walk = walk_and_reserve(
+        slab,
+        account_idx,
+        instrument_idx,
+        contra_side,
+        qty,
+        limit_px,
+        stp_mode,
+    )?;
+    let filled_qty = walk.filled_qty;
+    let total_notional = walk.total_notional;
+    let worst_px = walk.worst_px;
+    let slice_head = walk.slice_head;
+
+    // Quantity the taker still wants once STP has had its say
+    let qty_requested = if walk.taker_cancelled {
+        filled_qty
+    } else {
+        qty.saturating_sub(walk.decremented)
+    };
+
+    // Fill-or-kill: all or nothing
+    if tif == TimeInForce::FOK && filled_qty < qty_requested {
+        free_slices(slab, slice_head)?;
+        slab.reservations.free(resv_idx);
+        return Err(PercolatorError::InsufficientLiquidity);
+    }
+
+    // Pre-trade margin: the taker must be able to carry the filled position
+    if filled_qty > 0 {
+        let qty_delta = match side {
+            Side::Buy => filled_qty as i64,
+            Side::Sell => -(filled_qty as i64),
+        };
+        let position_qty = get_position_qty(slab, account_idx, instrument_idx);
+        let margin_ok = if increases_exposure(position_qty, position_qty + qty_delta) {
+            check_margin_pre_trade(slab, account_idx, instrument_idx, qty_delta)
+        } else {
+            Ok(true)
+        };
+        if margin_ok != Ok(true) {
+            free_slices(slab, slice_head)?;
+            slab.reservations.free(resv_idx);
+            return Err(margin_ok.err().unwrap_or(PercolatorError::InsufficientMargin));
+        }
+    }
+
+    // Lock the contra queue's top levels for the rest of the batch
+    if filled_qty > 0 {
+        freeze_top_levels(slab, instrument_idx, contra_side)?;
+    }
 
     // Calculate VWAP
     let vwap_px = if filled_qty > 0 {
@@ -83,8 +229,12 @@ pub fn reserve(
             account_idx,
             instrument_idx,
             side,
-            _padding: 0,
+            tif,
             qty: filled_qty,
+            qty_requested,
+            qty_order,
+            limit_px,
+            mark_px,
             vwap_px,
             worst_px,
             max_charge,
@@ -96,9 +246,11 @@ pub fn reserve(
             index: resv_idx,
             used: true,
             committed: false,
-            _padding2: [0; 6],
+            flags,
+            _padding2: [0; 5],
         };
     }
+    slab.index_reservation(resv_idx)?;
 
     Ok(ReserveResult {
         hold_id,
@@ -108,105 +260,209 @@ pub fn reserve(
         expiry_ms,
         book_seqno,
         filled_qty,
+        stp_mode,
+        stp_hits: walk.stp_hits,
+        stp_qty: walk.stp_qty,
+        mark_px,
     })
 }
 
+/// Clip a reduce-only order to the size of the opposite position
+pub(crate) fn clip_reduce_only(
+    slab: &SlabState,
+    account_idx: u32,
+    instrument_idx: u16,
+    side: Side,
+    qty: u64,
+) -> Result<u64, PercolatorError> {
+    let position_qty = get_position_qty(slab, account_idx, instrument_idx);
+
+    let reducible = match side {
+        Side::Buy if position_qty < 0 => position_qty.unsigned_abs(),
+        Side::Sell if position_qty > 0 => position_qty.unsigned_abs(),
+        _ => 0,
+    };
+
+    if reducible == 0 {
+        return Err(PercolatorError::ReduceOnlyViolation);
+    }
+
+    Ok(core::cmp::min(qty, reducible))
+}
+
+/// Outcome of walking the contra book
+struct WalkOutcome {
+    filled_qty: u64,
+    total_notional: u128,
+    worst_px: u64,
+    slice_head: u32,
+    /// Own orders encountered
+    stp_hits: u16,
+    /// Quantity affected by STP
+    stp_qty: u64,
+    /// Taker qty removed by `DecrementBoth`
+    decremented: u64,
+    /// Walk stopped by `CancelTaker`
+    taker_cancelled: bool,
+}
+
 /// Walk book and create reservation slices
+///
+/// Orders owned by the taker itself are handled according to `stp_mode`
+/// instead of being reserved.
 fn walk_and_reserve(
     slab: &mut SlabState,
+    taker_account_idx: u32,
     instrument_idx: u16,
     side: Side,
     qty: u64,
     limit_px: u64,
-    _resv_idx: u32,
-) -> Result<(u64, u128, u64, u32), PercolatorError> {
-    let head = {
-        let instrument = slab
-            .get_instrument(instrument_idx)
-            .ok_or(PercolatorError::InvalidInstrument)?;
-
-        match side {
-            Side::Buy => instrument.bids_head,
-            Side::Sell => instrument.asks_head,
-        }
-    };
-
-    let mut curr_idx = head;
+    stp_mode: StpMode,
+) -> Result<WalkOutcome, PercolatorError> {
+    let mut level = best_level(slab, instrument_idx, side, OrderState::LIVE)?;
     let mut qty_left = qty;
-    let mut total_notional: u128 = 0;
-    let mut worst_px = limit_px;
-    let mut slice_head = u32::MAX;
+    let mut out = WalkOutcome {
+        filled_qty: 0,
+        total_notional: 0,
+        worst_px: limit_px,
+        slice_head: u32::MAX,
+        stp_hits: 0,
+        stp_qty: 0,
+        decremented: 0,
+        taker_cancelled: false,
+    };
     let mut slice_tail = u32::MAX;
 
-    while curr_idx != u32::MAX && qty_left > 0 {
-        // Get order info (immutable borrow)
-        let (order_price, order_qty, order_reserved_qty, order_next) = {
-            let order = slab
-                .orders
-                .get(curr_idx)
-                .ok_or(PercolatorError::OrderNotFound)?;
-
-            (order.price, order.qty, order.reserved_qty, order.next)
+    // Level by level in priority order; each level is a run of the order list
+    'levels: while let Some(level_idx) = level {
+        let (level_px, mut curr_idx) = {
+            let level = slab
+                .price_levels
+                .get(level_idx)
+                .ok_or(PercolatorError::BookCorrupted)?;
+            (level.price, level.head)
         };
 
-        // Check price limit
+        // Check price limit (`side` is the contra book being walked)
         let crosses = match side {
-            Side::Buy => order_price <= limit_px,
-            Side::Sell => order_price >= limit_px,
+            Side::Sell => level_px <= limit_px,
+            Side::Buy => level_px >= limit_px,
         };
 
         if !crosses {
             break;
         }
 
-        // Calculate available quantity
-        let available = order_qty.saturating_sub(order_reserved_qty);
-        if available == 0 {
-            curr_idx = order_next;
-            continue;
-        }
+        while curr_idx != u32::MAX && qty_left > 0 {
+            // Get order info (immutable borrow)
+            let (order_id, order_price, order_qty, order_reserved_qty, order_next, order_account) = {
+                let order = slab
+                    .orders
+                    .get(curr_idx)
+                    .ok_or(PercolatorError::OrderNotFound)?;
 
-        let take_qty = core::cmp::min(qty_left, available);
+                (order.order_id, order.price, order.qty, order.reserved_qty, order.next, order.account_idx)
+            };
 
-        // Allocate slice
-        let slice_idx = slab.slices.alloc().ok_or(PercolatorError::PoolFull)?;
+            if order_price != level_px {
+                break;
+            }
 
-        // Create slice
-        if let Some(slice) = slab.slices.get_mut(slice_idx) {
-            *slice = Slice {
-                order_idx: curr_idx,
-                qty: take_qty,
-                next: u32::MAX,
-                index: slice_idx,
-                used: true,
-                _padding: [0; 7],
-            };
+            // Calculate available quantity
+            let available = order_qty.saturating_sub(order_reserved_qty);
+            if available == 0 {
+                curr_idx = order_next;
+                continue;
+            }
 
-            // Link slice
-            if slice_head == u32::MAX {
-                slice_head = slice_idx;
-            } else if let Some(tail) = slab.slices.get_mut(slice_tail) {
-                tail.next = slice_idx;
+            // Self-trade prevention
+            if order_account == taker_account_idx {
+                out.stp_hits = out.stp_hits.saturating_add(1);
+
+                match stp_mode {
+                    StpMode::CancelResting if order_reserved_qty == 0 => {
+                        remove_order(slab, instrument_idx, curr_idx)?;
+                        slab.free_order(curr_idx);
+                        out.stp_qty = out.stp_qty.saturating_add(order_qty);
+                    }
+                    // Partly locked by someone else's hold - cannot unlink, step over it
+                    StpMode::CancelResting | StpMode::Skip => {
+                        out.stp_qty = out.stp_qty.saturating_add(available);
+                    }
+                    StpMode::CancelTaker => {
+                        out.stp_qty = out.stp_qty.saturating_add(qty_left);
+                        out.taker_cancelled = true;
+                        break 'levels;
+                    }
+                    StpMode::DecrementBoth => {
+                        let overlap = core::cmp::min(qty_left, available);
+                        qty_left -= overlap;
+                        out.decremented = out.decremented.saturating_add(overlap);
+                        out.stp_qty = out.stp_qty.saturating_add(overlap);
+
+                        if order_qty == overlap {
+                            remove_order(slab, instrument_idx, curr_idx)?;
+                            slab.free_order(curr_idx);
+                        } else {
+                            if let Some(order) = slab.orders.get_mut(curr_idx) {
+                                order.qty -= overlap;
+                            }
+                            slab.header.increment_book_seqno();
+                        }
+                    }
+                }
+
+                curr_idx = order_next;
+                continue;
             }
-            slice_tail = slice_idx;
-        }
 
-        // Update order reserved quantity
-        if let Some(order) = slab.orders.get_mut(curr_idx) {
-            order.reserved_qty = order.reserved_qty.saturating_add(take_qty);
-        }
+            let take_qty = core::cmp::min(qty_left, available);
+
+            // Allocate slice
+            let slice_idx = slab.slices.alloc().ok_or(PercolatorError::PoolFull)?;
+
+            // Create slice
+            if let Some(slice) = slab.slices.get_mut(slice_idx) {
+                *slice = Slice {
+                    order_idx: curr_idx,
+                    order_id,
+                    qty: take_qty,
+                    next: u32::MAX,
+                    index: slice_idx,
+                    used: true,
+                    _padding: [0; 7],
+                };
+
+                // Link slice
+                if out.slice_head == u32::MAX {
+                    out.slice_head = slice_idx;
+                } else if let Some(tail) = slab.slices.get_mut(slice_tail) {
+                    tail.next = slice_idx;
+                }
+                slice_tail = slice_idx;
+            }
 
-        // Update totals
-        qty_left = qty_left.saturating_sub(take_qty);
-        total_notional = total_notional.saturating_add(mul_u64(take_qty, order_price));
-        worst_px = order_price;
+            // Update order reserved quantity
+            if let Some(order) = slab.orders.get_mut(curr_idx) {
+                order.reserved_qty = order.reserved_qty.saturating_add(take_qty);
+            }
 
-        curr_idx = order_next;
-    }
+            // Update totals
+            qty_left = qty_left.saturating_sub(take_qty);
+            out.filled_qty = out.filled_qty.saturating_add(take_qty);
+            out.total_notional = out.total_notional.saturating_add(mul_u64(take_qty, order_price));
+            out.worst_px = order_price;
+
+            curr_idx = order_next;
+        }
 
-    let filled_qty = qty.saturating_sub(qty_left);
+        if qty_left == 0 {
+            break;
+        }
+        level = next_level(slab, instrument_idx, side, OrderState::LIVE, level_px)?;
+    }
 
-    Ok((filled_qty, total_notional, worst_px, slice_head))
+    Ok(out)
 }
 
 /// Calculate maximum charge including fees
@@ -1,4 +1,6 @@
 //! Risk calculations and margin checks
+//!
+//! Positions are valued at the instrument's mark price; see `matching::mark`.
 
 use crate::state::SlabState;
 use percolator_common::*;
@@ -27,7 +29,7 @@ pub fn calculate_equity(
             .ok_or(PercolatorError::InvalidInstrument)?;
 
         // Calculate unrealized PnL
-        let pnl = calculate_pnl(pos.qty, pos.entry_px, instrument.index_price);
+        let pnl = calculate_pnl(pos.qty, pos.entry_px, instrument.mark_price);
 
         // Calculate funding payment
         let funding_payment = calculate_funding_payment(
@@ -67,18 +69,19 @@ pub fn calculate_margin_requirements(
         let instrument = slab
             .get_instrument(pos.instrument_idx)
             .ok_or(PercolatorError::InvalidInstrument)?;
+        ensure_fresh_index(slab, instrument)?;
 
         let im = calculate_im(
             pos.qty,
             instrument.contract_size,
-            instrument.index_price,
+            instrument.mark_price,
             slab.header.imr,
         );
 
         let mm = calculate_mm(
             pos.qty,
             instrument.contract_size,
-            instrument.index_price,
+            instrument.mark_price,
             slab.header.mmr,
         );
 
@@ -99,12 +102,56 @@ pub fn check_margin_pre_trade(
     qty_delta: i64,
 ) -> Result<bool, PercolatorError> {
     let equity = calculate_equity(slab, account_idx)?;
+    let total_im = im_after_trade(slab, account_idx, instrument_idx, qty_delta)?;
+
+    Ok(equity >= total_im as i128)
+}
+
+/// Check if account can margin a fill of `qty_delta` at `price`
+///
+/// Unlike `check_margin_pre_trade` the fill's price is known, so the equity
+/// includes its immediate PnL against mark plus `cash_delta` (fees paid as a
+/// negative, rebates as a positive amount). Fills that do not increase the
+/// position always pass.
+pub fn check_margin_for_fill(
+    slab: &SlabState,
+    account_idx: u32,
+    instrument_idx: u16,
+    qty_delta: i64,
+    price: u64,
+    cash_delta: i128,
+) -> Result<bool, PercolatorError> {
+    let current_qty = get_position_qty(slab, account_idx, instrument_idx);
+    if !increases_exposure(current_qty, current_qty + qty_delta) {
+        return Ok(true);
+    }
+
+    let mark_price = slab
+        .get_instrument(instrument_idx)
+        .ok_or(PercolatorError::InvalidInstrument)?
+        .mark_price;
+    let equity = calculate_equity(slab, account_idx)?
+        .saturating_add(calculate_pnl(qty_delta, price, mark_price))
+        .saturating_add(cash_delta);
+    let total_im = im_after_trade(slab, account_idx, instrument_idx, qty_delta)?;
+
+    Ok(equity >= total_im as i128)
+}
+
+/// Account IM once its position in `instrument_idx` moves by `qty_delta`
+fn im_after_trade(
+    slab: &SlabState,
+    account_idx: u32,
+    instrument_idx: u16,
+    qty_delta: i64,
+) -> Result<u128, PercolatorError> {
     let (current_im, _) = calculate_margin_requirements(slab, account_idx)?;
 
     // Calculate new IM with the additional position
     let instrument = slab
         .get_instrument(instrument_idx)
         .ok_or(PercolatorError::InvalidInstrument)?;
+    ensure_fresh_index(slab, instrument)?;
 
     // Find current position qty
     let current_qty = get_position_qty(slab, account_idx, instrument_idx);
@@ -114,21 +161,44 @@ pub fn check_margin_pre_trade(
     let old_im = calculate_im(
         current_qty,
         instrument.contract_size,
-        instrument.index_price,
+        instrument.mark_price,
         slab.header.imr,
     );
 
     let new_im = calculate_im(
         new_qty,
         instrument.contract_size,
-        instrument.index_price,
+        instrument.mark_price,
         slab.header.imr,
     );
 
     let im_delta = new_im.saturating_sub(old_im);
-    let total_im = current_im.saturating_add(im_delta);
+    Ok(current_im.saturating_add(im_delta))
+}
 
-    Ok(equity >= total_im as i128)
+/// Whether moving a position from `old_qty` to `new_qty` adds margin
+///
+/// IM scales with the absolute size, so reducing or flipping to a smaller
+/// opposite position never needs margin.
+pub fn increases_exposure(old_qty: i64, new_qty: i64) -> bool {
+    new_qty.unsigned_abs() > old_qty.unsigned_abs()
+}
+
+/// Refuse to price risk off a stale oracle reading
+///
+/// Instruments listed without an oracle keep their listing price and are
+/// never considered stale.
+pub fn ensure_fresh_index(slab: &SlabState, instrument: &Instrument) -> Result<(), PercolatorError> {
+    if instrument.oracle == [0; 32] {
+        return Ok(());
+    }
+
+    let age = slab.header.current_ts.saturating_sub(instrument.index_publish_ts);
+    if instrument.index_publish_ts == 0 || age > slab.header.max_oracle_staleness_ms {
+        return Err(PercolatorError::OracleStale);
+    }
+
+    Ok(())
 }
 
 /// Check if account is below maintenance margin (liquidatable)
@@ -140,7 +210,7 @@ pub fn is_liquidatable(slab: &SlabState, account_idx: u32) -> Result<bool, Perco
 }
 
 /// Get position quantity for instrument (0 if no position)
-fn get_position_qty(slab: &SlabState, account_idx: u32, instrument_idx: u16) -> i64 {
+pub fn get_position_qty(slab: &SlabState, account_idx: u32, instrument_idx: u16) -> i64 {
     if let Some(account) = slab.get_account(account_idx) {
         let mut pos_idx = account.position_head;
         while pos_idx != u32::MAX {
@@ -1,5 +1,6 @@
 //! Slab header with metadata and anti-toxicity params
 
+use percolator_common::{ArgMode, ShrinkPolicy, StpMode};
 use pinocchio::pubkey::Pubkey;
 
 /// Slab header (at start of 10 MB account)
@@ -42,6 +43,40 @@ pub struct SlabHeader {
     pub jit_penalty_on: bool,
     /// Minimum time for maker rebate (milliseconds)
     pub maker_rebate_min_ms: u64,
+    /// Self-trade prevention mode
+    pub stp_mode: StpMode,
+    /// Aggressor Roundtrip Guard mode
+    pub arg_mode: ArgMode,
+    /// Commit behaviour when reserved maker orders shrank since reserve
+    pub shrink_policy: ShrinkPolicy,
+
+    // Funding parameters
+    /// Funding interval (milliseconds)
+    pub funding_interval_ms: u64,
+    /// Cap on |funding| accrued per interval (basis points of index)
+    pub max_funding_rate_bps: u64,
+
+    // Oracle parameters
+    /// Maximum age of an index price (milliseconds)
+    pub max_oracle_staleness_ms: u64,
+    /// Maximum oracle confidence interval (basis points of price)
+    pub max_oracle_conf_bps: u64,
+    /// Cap on |mark premium| over index (basis points)
+    pub mark_premium_clamp_bps: u64,
+    /// Averaging window of the mark premium (milliseconds)
+    pub mark_ema_window_ms: u64,
+
+    // Liquidation parameters
+    /// Maximum distance of liquidation fills from mark (basis points)
+    pub liq_band_bps: u64,
+    /// Liquidation fee on closed notional (basis points)
+    pub liq_fee_bps: u64,
+
+    // Insurance fund parameters
+    /// Share of liquidation fees credited to the insurance fund (basis points)
+    pub insurance_liq_share_bps: u64,
+    /// Share of taker fees credited to the insurance fund (basis points)
+    pub insurance_taker_share_bps: u64,
 
     // DLP configuration
     /// Maximum number of DLP accounts
@@ -68,6 +103,20 @@ pub struct SlabHeader {
     pub book_seqno: u64,
     /// Current timestamp (updated at batch_open)
     pub current_ts: u64,
+    /// Next reservation slot inspected by ReapExpired
+    pub reap_cursor: u32,
+    /// Fills whose maker rebate was withheld (JIT or rested too briefly)
+    pub rebates_withheld_count: u64,
+    /// Total maker rebate withheld across those fills
+    pub rebates_withheld: u128,
+    /// Total sandwich tax charged by the roundtrip guard
+    pub arg_tax_collected: u128,
+    /// Total fees charged by liquidations
+    pub liq_fees_collected: u128,
+    /// Insurance fund balance
+    pub insurance_balance: u128,
+    /// Total insurance spent covering bankrupt accounts
+    pub insurance_paid_out: u128,
 
     /// Bump seed
     pub bump: u8,
@@ -109,6 +158,19 @@ impl SlabHeader {
             as_fee_k: 50,       // 0.5%
             jit_penalty_on: true,
             maker_rebate_min_ms: 100,
+            stp_mode: StpMode::CancelResting,
+            arg_mode: ArgMode::Tax,
+            shrink_policy: ShrinkPolicy::Fail,
+            funding_interval_ms: 3_600_000, // 1 hour
+            max_funding_rate_bps: 75,       // 0.75% per interval
+            max_oracle_staleness_ms: 60_000, // 1 minute
+            max_oracle_conf_bps: 100,        // 1%
+            mark_premium_clamp_bps: 50,      // 0.5%
+            mark_ema_window_ms: 300_000,     // 5 minutes
+            liq_band_bps: 500,               // 5%
+            liq_fee_bps: 50,                 // 0.5%
+            insurance_liq_share_bps: 5_000,  // 50%
+            insurance_taker_share_bps: 1_000, // 10%
             dlp_max: 100,
             dlp_count: 0,
             max_accounts: percolator_common::MAX_ACCOUNTS as u32,
@@ -123,6 +185,13 @@ impl SlabHeader {
             next_hold_id: 1,
             book_seqno: 0,
             current_ts: 0,
+            reap_cursor: 0,
+            rebates_withheld_count: 0,
+            rebates_withheld: 0,
+            arg_tax_collected: 0,
+            liq_fees_collected: 0,
+            insurance_balance: 0,
+            insurance_paid_out: 0,
             bump,
             _padding2: [0; 7],
         }
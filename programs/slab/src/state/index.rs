@@ -0,0 +1,139 @@
+//! Fixed-capacity hash indexes over slab pools
+
+use percolator_common::{MAX_ACCOUNTS, MAX_AGGRESSOR_ENTRIES, MAX_ORDERS, MAX_RESERVATIONS};
+
+/// Slots in the owner pubkey -> account index
+pub const ACCOUNT_INDEX_SLOTS: usize = 8_192;
+/// Slots in the order id -> order index
+pub const ORDER_INDEX_SLOTS: usize = 65_536;
+/// Slots in the hold id -> reservation index
+pub const RESERVATION_INDEX_SLOTS: usize = 8_192;
+/// Slots in the (account, instrument) -> aggressor ledger index
+pub const AGGRESSOR_INDEX_SLOTS: usize = 8_192;
+
+const _: () = {
+    assert!(ACCOUNT_INDEX_SLOTS.is_power_of_two() && ACCOUNT_INDEX_SLOTS > MAX_ACCOUNTS);
+    assert!(ORDER_INDEX_SLOTS.is_power_of_two() && ORDER_INDEX_SLOTS > MAX_ORDERS);
+    assert!(RESERVATION_INDEX_SLOTS.is_power_of_two() && RESERVATION_INDEX_SLOTS > MAX_RESERVATIONS);
+    assert!(AGGRESSOR_INDEX_SLOTS.is_power_of_two() && AGGRESSOR_INDEX_SLOTS > MAX_AGGRESSOR_ENTRIES);
+};
+
+/// Open-addressing hash index mapping keys to pool indexes
+///
+/// Keys are not stored: each slot holds `pool index + 1` (0 marks an empty
+/// slot) and callers compare keys against the pool item. Collisions probe
+/// linearly; removal shifts later entries back instead of leaving
+/// tombstones, so lookups stay short however much the pool churns.
+/// `N` must be a power of two larger than the indexed pool.
+#[repr(C)]
+pub struct HashIndex<const N: usize> {
+    pub slots: [u32; N],
+}
+
+impl<const N: usize> HashIndex<N> {
+    const EMPTY: u32 = 0;
+    const MASK: usize = N - 1;
+
+    /// Create an empty index
+    pub fn new() -> Self {
+        Self { slots: [Self::EMPTY; N] }
+    }
+
+    /// Home slot of a hash (Fibonacci hashing)
+    fn home(hash: u64) -> usize {
+        (hash.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32) as usize & Self::MASK
+    }
+
+    /// Slot holding the first entry matching `is_match` along the probe chain
+    fn position(&self, hash: u64, is_match: impl Fn(u32) -> bool) -> Option<usize> {
+        let mut slot = Self::home(hash);
+        for _ in 0..N {
+            let v = self.slots[slot];
+            if v == Self::EMPTY {
+                return None;
+            }
+            if is_match(v - 1) {
+                return Some(slot);
+            }
+            slot = (slot + 1) & Self::MASK;
+        }
+        None
+    }
+
+    /// Look up the pool index whose key hashes to `hash` and passes `is_match`
+    pub fn find(&self, hash: u64, is_match: impl Fn(u32) -> bool) -> Option<u32> {
+        self.position(hash, is_match).map(|slot| self.slots[slot] - 1)
+    }
+
+    /// Insert a pool index under `hash`
+    ///
+    /// The caller ensures the key is not already present.
+    pub fn insert(&mut self, hash: u64, idx: u32) -> Result<(), ()> {
+        let mut slot = Self::home(hash);
+        for _ in 0..N {
+            if self.slots[slot] == Self::EMPTY {
+                self.slots[slot] = idx + 1;
+                return Ok(());
+            }
+            slot = (slot + 1) & Self::MASK;
+        }
+        Err(())
+    }
+
+    /// Remove a pool index stored under `hash`
+    ///
+    /// `hash_of` recomputes the hash of other indexed entries so they can be
+    /// shifted into the freed slot. Missing entries are ignored.
+    pub fn remove(&mut self, hash: u64, idx: u32, hash_of: impl Fn(u32) -> u64) {
+        let Some(mut hole) = self.position(hash, |i| i == idx) else {
+            return;
+        };
+
+        let mut slot = (hole + 1) & Self::MASK;
+        while self.slots[slot] != Self::EMPTY {
+            let v = self.slots[slot];
+            let home = Self::home(hash_of(v - 1));
+            // Move the entry back unless its home lies between the hole and it
+            if (slot.wrapping_sub(home) & Self::MASK) >= (slot.wrapping_sub(hole) & Self::MASK) {
+                self.slots[hole] = v;
+                hole = slot;
+            }
+            slot = (slot + 1) & Self::MASK;
+        }
+        self.slots[hole] = Self::EMPTY;
+    }
+}
+
+impl<const N: usize> Default for HashIndex<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hash of an account owner pubkey
+///
+/// Every word goes through a full 64-bit avalanche, so unrelated keys spread
+/// evenly over the index. The hash is unkeyed: owners pick their own keys
+/// and can grind ones that share a probe chain, which lengthens lookups on
+/// that chain but never past the index size.
+pub fn pubkey_hash(key: &pinocchio::pubkey::Pubkey) -> u64 {
+    let mut hash = 0u64;
+    for chunk in key.chunks_exact(8) {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(chunk);
+        hash = mix64(hash ^ u64::from_le_bytes(word));
+    }
+    hash
+}
+
+/// SplitMix64 finalizer
+fn mix64(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Key of an aggressor ledger entry
+pub fn aggressor_key(account_idx: u32, instrument_idx: u16) -> u64 {
+    ((account_idx as u64) << 16) | instrument_idx as u64
+}
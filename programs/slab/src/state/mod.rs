@@ -1,7 +1,9 @@
 pub mod header;
 pub mod slab;
 pub mod pools;
+pub mod index;
 
 pub use header::*;
 pub use slab::*;
 pub use pools::*;
+pub use index::*;
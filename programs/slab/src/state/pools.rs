@@ -16,9 +16,9 @@ impl<T: Copy + Default + PoolItem, const N: usize> Pool<T, N> {
         let mut items = [T::default(); N];
 
         // Initialize freelist - each item points to next
-        for i in 0..N {
-            items[i].set_next_free((i + 1) as u32);
-            items[i].set_used(false);
+        for (i, item) in items.iter_mut().enumerate() {
+            item.set_next_free((i + 1) as u32);
+            item.set_used(false);
         }
 
         Self {
@@ -28,6 +28,19 @@ impl<T: Copy + Default + PoolItem, const N: usize> Pool<T, N> {
         }
     }
 
+    /// Thread the freelist over existing storage without building `[T; N]` by value
+    ///
+    /// Used when the pool lives inside account data that is far too large for
+    /// the BPF stack. Every item is marked free and linked to its successor.
+    pub fn init_in_place(&mut self) {
+        for (i, item) in self.items.iter_mut().enumerate() {
+            item.set_next_free((i + 1) as u32);
+            item.set_used(false);
+        }
+        self.free_head = 0;
+        self.used_count = 0;
+    }
+
     /// Allocate an item from the pool
     pub fn alloc(&mut self) -> Option<u32> {
         if self.used_count >= N as u32 {
@@ -97,6 +110,12 @@ impl<T: Copy + Default + PoolItem, const N: usize> Pool<T, N> {
     }
 }
 
+impl<T: Copy + Default + PoolItem, const N: usize> Default for Pool<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Trait for items that can be stored in a pool
 pub trait PoolItem: Copy {
     fn set_next_free(&mut self, next: u32);
@@ -121,6 +140,36 @@ impl PoolItem for percolator_common::Order {
     }
 }
 
+impl PoolItem for percolator_common::PriceLevel {
+    fn set_next_free(&mut self, next: u32) {
+        self.next_free = next;
+    }
+    fn get_next_free(&self) -> u32 {
+        self.next_free
+    }
+    fn set_used(&mut self, used: bool) {
+        self.used = used;
+    }
+    fn is_used(&self) -> bool {
+        self.used
+    }
+}
+
+impl PoolItem for percolator_common::LevelNode {
+    fn set_next_free(&mut self, next: u32) {
+        self.next_free = next;
+    }
+    fn get_next_free(&self) -> u32 {
+        self.next_free
+    }
+    fn set_used(&mut self, used: bool) {
+        self.used = used;
+    }
+    fn is_used(&self) -> bool {
+        self.used
+    }
+}
+
 impl PoolItem for percolator_common::Position {
     fn set_next_free(&mut self, next: u32) {
         self.index = next; // Reuse index field for freelist
@@ -1,6 +1,10 @@
 //! Main slab state structure
 
 use super::header::SlabHeader;
+use super::index::{
+    aggressor_key, pubkey_hash, HashIndex, ACCOUNT_INDEX_SLOTS, AGGRESSOR_INDEX_SLOTS, ORDER_INDEX_SLOTS,
+    RESERVATION_INDEX_SLOTS,
+};
 use super::pools::Pool;
 use percolator_common::*;
 
@@ -13,6 +17,10 @@ pub struct SlabState {
 
     /// Account pool
     pub accounts: [AccountState; MAX_ACCOUNTS],
+    /// Accounts handed out so far (slots are used in order and never released)
+    pub account_count: u32,
+    /// Owner pubkey -> account index
+    pub account_index: HashIndex<ACCOUNT_INDEX_SLOTS>,
 
     /// Instrument pool (small, fixed size)
     pub instruments: [Instrument; MAX_INSTRUMENTS],
@@ -23,12 +31,22 @@ pub struct SlabState {
 
     /// Order pool
     pub orders: Pool<Order, MAX_ORDERS>,
+    /// Order id -> order index (resting orders only)
+    pub order_index: HashIndex<ORDER_INDEX_SLOTS>,
+
+    /// Price level pool (one per distinct price in each book list)
+    pub price_levels: Pool<PriceLevel, MAX_PRICE_LEVELS>,
+
+    /// Inner nodes of the per-book price-level trees
+    pub level_nodes: Pool<LevelNode, MAX_PRICE_LEVELS>,
 
     /// Position pool
     pub positions: Pool<Position, MAX_POSITIONS>,
 
     /// Reservation pool
     pub reservations: Pool<Reservation, MAX_RESERVATIONS>,
+    /// Hold id -> reservation index (live reservations only)
+    pub reservation_index: HashIndex<RESERVATION_INDEX_SLOTS>,
 
     /// Slice pool
     pub slices: Pool<Slice, MAX_SLICES>,
@@ -40,9 +58,68 @@ pub struct SlabState {
 
     /// Aggressor ledger pool (shared, not per account)
     pub aggressor_ledger: Pool<AggressorEntry, MAX_AGGRESSOR_ENTRIES>,
+    /// (account, instrument) -> aggressor ledger entry
+    pub aggressor_index: HashIndex<AGGRESSOR_INDEX_SLOTS>,
 }
 
 impl SlabState {
+    /// Size of the slab state in account data
+    pub const LEN: usize = core::mem::size_of::<Self>();
+
+    /// Initialize a slab in place over raw account bytes
+    ///
+    /// The state is never materialized on the stack: the region is zeroed
+    /// (a valid bit pattern for every field), the header is written, book
+    /// heads are emptied and each pool's freelist is threaded in place.
+    /// Refuses to run over an account that already carries the slab magic.
+    pub fn init_in_place(data: &mut [u8], header: SlabHeader) -> Result<&mut SlabState, PercolatorError> {
+        if data.len() < Self::LEN {
+            return Err(PercolatorError::InvalidAccount);
+        }
+
+        let ptr = data.as_mut_ptr();
+        if (ptr as usize) % core::mem::align_of::<Self>() != 0 {
+            return Err(PercolatorError::InvalidAccount);
+        }
+
+        if &data[..SlabHeader::MAGIC.len()] == SlabHeader::MAGIC {
+            return Err(PercolatorError::AlreadyInitialized);
+        }
+
+        // SAFETY: size and alignment checked above; all-zero bytes are a valid
+        // SlabState (integers, bools and enums with a zero discriminant)
+        let slab = unsafe {
+            core::ptr::write_bytes(ptr, 0, Self::LEN);
+            &mut *(ptr as *mut SlabState)
+        };
+
+        slab.header = header;
+
+        for instrument in slab.instruments.iter_mut() {
+            instrument.bids_head = u32::MAX;
+            instrument.asks_head = u32::MAX;
+            instrument.bids_pending_head = u32::MAX;
+            instrument.asks_pending_head = u32::MAX;
+            instrument.bids_levels = u32::MAX;
+            instrument.asks_levels = u32::MAX;
+            instrument.bids_pending_levels = u32::MAX;
+            instrument.asks_pending_levels = u32::MAX;
+            instrument.longs_head = u32::MAX;
+            instrument.shorts_head = u32::MAX;
+            instrument.aggressor_head = u32::MAX;
+        }
+
+        slab.orders.init_in_place();
+        slab.price_levels.init_in_place();
+        slab.level_nodes.init_in_place();
+        slab.positions.init_in_place();
+        slab.reservations.init_in_place();
+        slab.slices.init_in_place();
+        slab.aggressor_ledger.init_in_place();
+
+        Ok(slab)
+    }
+
     /// Get instrument by index
     pub fn get_instrument(&self, idx: u16) -> Option<&Instrument> {
         if idx < self.instrument_count {
@@ -128,33 +205,205 @@ impl SlabState {
         }
     }
 
+    /// Find an existing account by owner pubkey
+    pub fn find_account(&self, pubkey: &pinocchio::pubkey::Pubkey) -> Option<u32> {
+        self.account_index
+            .find(pubkey_hash(pubkey), |i| &self.accounts[i as usize].key == pubkey)
+    }
+
     /// Find or create account
     pub fn find_or_create_account(&mut self, pubkey: &pinocchio::pubkey::Pubkey) -> Result<u32, ()> {
-        // First try to find existing
-        for i in 0..MAX_ACCOUNTS {
-            if self.accounts[i].active && &self.accounts[i].key == pubkey {
-                return Ok(i as u32);
-            }
+        if let Some(idx) = self.find_account(pubkey) {
+            return Ok(idx);
         }
 
-        // Find first inactive slot
-        for i in 0..MAX_ACCOUNTS {
-            if !self.accounts[i].active {
-                self.accounts[i] = AccountState {
-                    key: *pubkey,
-                    cash: 0,
-                    im: 0,
-                    mm: 0,
-                    position_head: u32::MAX,
-                    index: i as u32,
-                    active: true,
-                    _padding: [0; 7],
-                };
-                return Ok(i as u32);
-            }
+        let idx = self.account_count;
+        if idx as usize >= MAX_ACCOUNTS {
+            return Err(());
         }
 
-        Err(())
+        self.accounts[idx as usize] = AccountState {
+            key: *pubkey,
+            cash: 0,
+            im: 0,
+            mm: 0,
+            position_head: u32::MAX,
+            order_head: u32::MAX,
+            index: idx,
+            active: true,
+            _padding: [0; 3],
+        };
+        self.account_index.insert(pubkey_hash(pubkey), idx)?;
+        self.account_count += 1;
+
+        Ok(idx)
+    }
+
+    /// Find a live reservation by hold id
+    pub fn find_reservation(&self, hold_id: u64) -> Option<u32> {
+        self.reservation_index
+            .find(hold_id, |i| self.reservations.items[i as usize].hold_id == hold_id)
+    }
+
+    /// Index the reservation written at `idx` by its hold id
+    pub fn index_reservation(&mut self, idx: u32) -> Result<(), PercolatorError> {
+        let hold_id = self
+            .reservations
+            .get(idx)
+            .ok_or(PercolatorError::ReservationNotFound)?
+            .hold_id;
+
+        self.reservation_index
+            .insert(hold_id, idx)
+            .map_err(|_| PercolatorError::PoolFull)
+    }
+
+    /// Drop a reservation from the hold id index and return it to the pool
+    pub fn free_reservation(&mut self, idx: u32) {
+        let Some(hold_id) = self.reservations.get(idx).map(|r| r.hold_id) else {
+            return;
+        };
+
+        let reservations = &self.reservations;
+        self.reservation_index
+            .remove(hold_id, idx, |i| reservations.items[i as usize].hold_id);
+        self.reservations.free(idx);
+    }
+
+    /// Find an account's aggressor ledger entry on an instrument
+    pub fn find_aggressor_entry(&self, account_idx: u32, instrument_idx: u16) -> Option<u32> {
+        self.aggressor_index.find(aggressor_key(account_idx, instrument_idx), |i| {
+            let entry = &self.aggressor_ledger.items[i as usize];
+            entry.account_idx == account_idx && entry.instrument_idx == instrument_idx
+        })
+    }
+
+    /// Index the ledger entry written at `idx` and link it into its
+    /// instrument's entry list
+    pub fn index_aggressor_entry(&mut self, idx: u32) -> Result<(), PercolatorError> {
+        let (account_idx, instrument_idx) = self
+            .aggressor_ledger
+            .get(idx)
+            .map(|e| (e.account_idx, e.instrument_idx))
+            .ok_or(PercolatorError::PoolFull)?;
+        let instrument = self
+            .get_instrument_mut(instrument_idx)
+            .ok_or(PercolatorError::InvalidInstrument)?;
+        let head = core::mem::replace(&mut instrument.aggressor_head, idx);
+
+        if let Some(entry) = self.aggressor_ledger.get_mut(idx) {
+            entry.next_in_instrument = head;
+        }
+        self.aggressor_index
+            .insert(aggressor_key(account_idx, instrument_idx), idx)
+            .map_err(|_| PercolatorError::PoolFull)
+    }
+
+    /// Release every aggressor ledger entry of an instrument
+    ///
+    /// Walks the instrument's entry list, so the cost follows the number of
+    /// takers in the batch rather than the size of the pool.
+    pub fn free_aggressor_entries(&mut self, instrument_idx: u16) {
+        let Some(instrument) = self.get_instrument_mut(instrument_idx) else {
+            return;
+        };
+        let mut idx = core::mem::replace(&mut instrument.aggressor_head, u32::MAX);
+
+        while let Some((account_idx, next)) = self
+            .aggressor_ledger
+            .get(idx)
+            .map(|e| (e.account_idx, e.next_in_instrument))
+        {
+            let ledger = &self.aggressor_ledger;
+            self.aggressor_index.remove(aggressor_key(account_idx, instrument_idx), idx, |i| {
+                let entry = &ledger.items[i as usize];
+                aggressor_key(entry.account_idx, entry.instrument_idx)
+            });
+            self.aggressor_ledger.free(idx);
+            idx = next;
+        }
+    }
+
+    /// Find a resting order by order id
+    pub fn find_order(&self, order_id: u64) -> Option<u32> {
+        self.order_index
+            .find(order_id, |i| self.orders.items[i as usize].order_id == order_id)
+    }
+
+    /// Index the order written at `idx` by its order id and link it into its
+    /// account's order list
+    pub fn link_order(&mut self, idx: u32) -> Result<(), PercolatorError> {
+        let (account_idx, order_id) = self
+            .orders
+            .get(idx)
+            .map(|o| (o.account_idx, o.order_id))
+            .ok_or(PercolatorError::OrderNotFound)?;
+        let head = self
+            .get_account(account_idx)
+            .ok_or(PercolatorError::InvalidAccount)?
+            .order_head;
+
+        if let Some(order) = self.orders.get_mut(idx) {
+            order.prev_in_account = u32::MAX;
+            order.next_in_account = head;
+        }
+        if let Some(next) = self.orders.get_mut(head) {
+            next.prev_in_account = idx;
+        }
+        if let Some(account) = self.get_account_mut(account_idx) {
+            account.order_head = idx;
+        }
+
+        self.order_index
+            .insert(order_id, idx)
+            .map_err(|_| PercolatorError::PoolFull)
+    }
+
+    /// Give the order at `idx` a new order id, keeping the index in step
+    pub fn rekey_order(&mut self, idx: u32, order_id: u64) -> Result<(), PercolatorError> {
+        let old_id = self
+            .orders
+            .get(idx)
+            .ok_or(PercolatorError::OrderNotFound)?
+            .order_id;
+
+        self.unindex_order(idx, old_id);
+        if let Some(order) = self.orders.get_mut(idx) {
+            order.order_id = order_id;
+        }
+        self.order_index
+            .insert(order_id, idx)
+            .map_err(|_| PercolatorError::PoolFull)
+    }
+
+    fn unindex_order(&mut self, idx: u32, order_id: u64) {
+        let orders = &self.orders;
+        self.order_index
+            .remove(order_id, idx, |i| orders.items[i as usize].order_id);
+    }
+
+    /// Drop an order from the order id index and its account's order list,
+    /// and return it to the pool
+    pub fn free_order(&mut self, idx: u32) {
+        let Some((account_idx, order_id, prev, next)) = self
+            .orders
+            .get(idx)
+            .map(|o| (o.account_idx, o.order_id, o.prev_in_account, o.next_in_account))
+        else {
+            return;
+        };
+
+        self.unindex_order(idx, order_id);
+
+        if let Some(prev_order) = self.orders.get_mut(prev) {
+            prev_order.next_in_account = next;
+        } else if let Some(account) = self.get_account_mut(account_idx) {
+            account.order_head = next;
+        }
+        if let Some(next_order) = self.orders.get_mut(next) {
+            next_order.prev_in_account = prev;
+        }
+        self.orders.free(idx);
     }
 }
 
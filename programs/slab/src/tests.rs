@@ -90,14 +90,14 @@ mod pool_tests {
         let mut indices = [0u32; 5];
 
         // Allocate all
-        for i in 0..5 {
-            indices[i] = pool.alloc().unwrap();
+        for idx in indices.iter_mut() {
+            *idx = pool.alloc().unwrap();
         }
         assert!(pool.is_full());
 
         // Free all
-        for i in 0..5 {
-            pool.free(indices[i]);
+        for idx in indices {
+            pool.free(idx);
         }
         assert_eq!(pool.used(), 0);
 
@@ -109,6 +109,107 @@ mod pool_tests {
     }
 }
 
+#[cfg(test)]
+mod index_tests {
+    use super::harness::TestSlab;
+    use crate::matching::{cancel, commit, reserve};
+    use crate::state::index::*;
+    use percolator_common::*;
+
+    /// Keys are pool indexes; `hash` forces collisions onto a few home slots
+    fn hash(key: u32) -> u64 {
+        (key % 3) as u64
+    }
+
+    #[test]
+    fn test_index_insert_find_remove() {
+        let mut index: HashIndex<16> = HashIndex::new();
+        for key in 0..10 {
+            index.insert(hash(key), key).unwrap();
+        }
+        for key in 0..10 {
+            assert_eq!(index.find(hash(key), |i| i == key), Some(key));
+        }
+        assert_eq!(index.find(hash(10), |i| i == 10), None);
+
+        // Removing from the middle of collision chains keeps the rest reachable
+        for key in [0, 4, 8, 3] {
+            index.remove(hash(key), key, hash);
+            assert_eq!(index.find(hash(key), |i| i == key), None);
+        }
+        for key in [1, 2, 5, 6, 7, 9] {
+            assert_eq!(index.find(hash(key), |i| i == key), Some(key));
+        }
+    }
+
+    #[test]
+    fn test_index_churn_leaves_no_residue() {
+        let mut index: HashIndex<16> = HashIndex::new();
+        for round in 0..100u32 {
+            let keys = [round % 12, (round + 5) % 12];
+            for key in keys {
+                index.insert(hash(key), key).unwrap();
+            }
+            for key in keys {
+                index.remove(hash(key), key, hash);
+            }
+        }
+        assert!(index.slots.iter().all(|&s| s == 0));
+    }
+
+    #[test]
+    fn test_account_index() {
+        let mut t = TestSlab::new();
+        let a = t.add_account(1, 0);
+        let b = t.add_account(2, 0);
+        assert_eq!((a, b), (0, 1));
+        assert_eq!(t.find_or_create_account(&[1; 32]), Ok(a));
+        assert_eq!(t.find_account(&[2; 32]), Some(b));
+        assert_eq!(t.find_account(&[3; 32]), None);
+        assert_eq!(t.account_count, 2);
+    }
+
+    #[test]
+    fn test_account_pool_full() {
+        let mut t = TestSlab::new();
+        for i in 0..MAX_ACCOUNTS as u32 {
+            let mut key = [0u8; 32];
+            key[..4].copy_from_slice(&i.to_le_bytes());
+            assert_eq!(t.find_or_create_account(&key), Ok(i));
+        }
+        assert_eq!(t.find_or_create_account(&[0xFF; 32]), Err(()));
+
+        let mut key = [0u8; 32];
+        key[..4].copy_from_slice(&4_321u32.to_le_bytes());
+        assert_eq!(t.find_account(&key), Some(4_321));
+    }
+
+    #[test]
+    fn test_reservation_index_follows_pool() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let taker = t.add_account(2, 1_000_000_000);
+
+        let mut holds = [0u64; 3];
+        for hold in holds.iter_mut() {
+            let res =
+                reserve(&mut t, taker, iidx, Side::Buy, 1, 50_000, 1_000, [0; 32], 1, TimeInForce::IOC, 0)
+                    .unwrap();
+            *hold = res.hold_id;
+        }
+        for (i, hold) in holds.iter().enumerate() {
+            assert_eq!(t.find_reservation(*hold), Some(i as u32));
+        }
+
+        cancel(&mut t, holds[0]).unwrap();
+        commit(&mut t, holds[2], &[0; 16], 0).unwrap();
+        assert_eq!(t.find_reservation(holds[0]), None);
+        assert_eq!(t.find_reservation(holds[1]), Some(1));
+        assert_eq!(t.find_reservation(holds[2]), None);
+        assert_eq!(cancel(&mut t, holds[0]), Err(PercolatorError::ReservationNotFound));
+    }
+}
+
 #[cfg(test)]
 mod header_tests {
     use crate::state::header::SlabHeader;
@@ -237,6 +338,2890 @@ mod header_tests {
     }
 }
 
-// NOTE: Order book operation tests are deferred to integration tests with surfpool
-// Testing with the full 10MB SlabState is complex in unit tests due to stack limitations
-// Integration tests will provide better coverage of book operations in a realistic environment
+#[cfg(test)]
+mod wire_tests {
+    use crate::instructions::{
+        BatchOpenArgs, CancelAllArgs, CancelOrderArgs, CommitArgs, LiquidationCallArgs, ModifyOrderArgs,
+        PlaceOrderArgs, ReapExpiredArgs, ReserveArgs, SetParamsArgs, SettleFundingArgs, SettleFundingResult,
+        SlabParam, TopUpInsuranceArgs, UpdateFundingArgs, UpdateIndexPriceArgs,
+    };
+    use crate::matching::{
+        CancelAllResult, CancelOrderResult, CommitResult, FundingResult, IndexPriceResult,
+        InsuranceResult, LiquidationResult, ModifyOrderResult, PlaceOrderResult, ReapExpiredResult, ReserveResult,
+    };
+    use percolator_common::{
+        OrderState, PercolatorError, Side, StpMode, TimeInForce, ORDER_FLAG_REDUCE_ONLY,
+    };
+
+    fn sample_reserve_args() -> ReserveArgs {
+        ReserveArgs {
+            account_idx: 7,
+            instrument_idx: 2,
+            side: Side::Sell,
+            qty: 1_000,
+            limit_px: 49_500_000,
+            ttl_ms: 5_000,
+            commitment_hash: [0xAB; 32],
+            route_id: 42,
+            tif: TimeInForce::FOK,
+            flags: ORDER_FLAG_REDUCE_ONLY,
+        }
+    }
+
+    #[test]
+    fn test_reserve_args_roundtrip() {
+        let args = sample_reserve_args();
+        let bytes = args.pack();
+
+        assert_eq!(bytes.len(), ReserveArgs::LEN);
+        assert_eq!(bytes[0], ReserveArgs::VERSION);
+        assert_eq!(ReserveArgs::unpack(&bytes), Ok(args));
+    }
+
+    #[test]
+    fn test_reserve_args_rejects_bad_input() {
+        let bytes = sample_reserve_args().pack();
+
+        // Truncated
+        assert_eq!(
+            ReserveArgs::unpack(&bytes[..ReserveArgs::LEN - 1]),
+            Err(PercolatorError::InvalidInstruction)
+        );
+
+        // Unknown layout version
+        let mut wrong_version = bytes;
+        wrong_version[0] = ReserveArgs::VERSION + 1;
+        assert_eq!(
+            ReserveArgs::unpack(&wrong_version),
+            Err(PercolatorError::InvalidInstruction)
+        );
+
+        // Invalid side byte (offset: version + account_idx + instrument_idx)
+        let mut bad_side = bytes;
+        bad_side[7] = 2;
+        assert_eq!(ReserveArgs::unpack(&bad_side), Err(PercolatorError::InvalidSide));
+
+        // Invalid time-in-force byte (second to last)
+        let mut bad_tif = bytes;
+        bad_tif[ReserveArgs::LEN - 2] = 3;
+        assert_eq!(ReserveArgs::unpack(&bad_tif), Err(PercolatorError::InvalidTimeInForce));
+    }
+
+    #[test]
+    fn test_hold_and_batch_args_roundtrip() {
+        let commit = CommitArgs { hold_id: 99, salt: [3; 16] };
+        assert_eq!(CommitArgs::unpack(&commit.pack()), Ok(commit));
+
+        let batch = BatchOpenArgs { instrument_idx: 3 };
+        assert_eq!(BatchOpenArgs::unpack(&batch.pack()), Ok(batch));
+
+        let cancel = CancelOrderArgs { order_id: 77 };
+        assert_eq!(CancelOrderArgs::unpack(&cancel.pack()), Ok(cancel));
+
+        let cancel_all = CancelAllArgs { instrument_idx: CancelAllArgs::ALL_INSTRUMENTS };
+        assert_eq!(CancelAllArgs::unpack(&cancel_all.pack()), Ok(cancel_all));
+
+        let modify = ModifyOrderArgs { order_id: 77, new_price: 50_000, new_qty: 4 };
+        assert_eq!(ModifyOrderArgs::unpack(&modify.pack()), Ok(modify));
+
+        let params = SetParamsArgs { param: SlabParam::StpMode, value: 3 };
+        assert_eq!(SetParamsArgs::unpack(&params.pack()), Ok(params));
+
+        let funding = UpdateFundingArgs { instrument_idx: 4 };
+        assert_eq!(UpdateFundingArgs::unpack(&funding.pack()), Ok(funding));
+
+        let index = UpdateIndexPriceArgs { instrument_idx: 2 };
+        assert_eq!(UpdateIndexPriceArgs::unpack(&index.pack()), Ok(index));
+
+        let settle = SettleFundingArgs { account_idx: 11 };
+        assert_eq!(SettleFundingArgs::unpack(&settle.pack()), Ok(settle));
+        let settled = SettleFundingResult { funding_paid: -42 };
+        assert_eq!(SettleFundingResult::unpack(&settled.pack()), Ok(settled));
+
+        let reap = ReapExpiredArgs { max_scan: 64 };
+        assert_eq!(ReapExpiredArgs::unpack(&reap.pack()), Ok(reap));
+
+        let liquidation = LiquidationCallArgs { account_idx: 5 };
+        assert_eq!(LiquidationCallArgs::unpack(&liquidation.pack()), Ok(liquidation));
+
+        let top_up = TopUpInsuranceArgs { amount: 1 << 80 };
+        assert_eq!(TopUpInsuranceArgs::unpack(&top_up.pack()), Ok(top_up));
+
+        let place = PlaceOrderArgs {
+            instrument_idx: 1,
+            side: Side::Buy,
+            price: 49_990,
+            qty: 25,
+        };
+        assert_eq!(PlaceOrderArgs::unpack(&place.pack()), Ok(place));
+    }
+
+    #[test]
+    fn test_result_return_data_roundtrip() {
+        let reserve = ReserveResult {
+            hold_id: 1,
+            vwap_px: 50_000,
+            worst_px: 50_100,
+            max_charge: 5_005_000_000,
+            expiry_ms: 10_000,
+            book_seqno: 4,
+            filled_qty: 100,
+            stp_mode: StpMode::DecrementBoth,
+            stp_hits: 1,
+            stp_qty: 10,
+            mark_px: 50_050,
+        };
+        assert_eq!(ReserveResult::unpack(&reserve.pack()), Ok(reserve));
+
+        let commit = CommitResult {
+            filled_qty: 100,
+            avg_price: 50_000,
+            total_fee: 5_000,
+            total_debit: 5_005_000,
+            rested_order_id: 9,
+            rested_qty: 20,
+            rebate_withheld: 2_500,
+            withheld_fills: 1,
+            arg_tax: 1_250,
+            arg_clipped_qty: 7,
+            shortfall_qty: 3,
+        };
+        assert_eq!(CommitResult::unpack(&commit.pack()), Ok(commit));
+
+        let placed = PlaceOrderResult {
+            order_id: 12,
+            order_idx: 3,
+            state: OrderState::PENDING,
+        };
+        assert_eq!(PlaceOrderResult::unpack(&placed.pack()), Ok(placed));
+
+        let cancelled = CancelOrderResult { order_id: 12, cancelled_qty: 5 };
+        assert_eq!(CancelOrderResult::unpack(&cancelled.pack()), Ok(cancelled));
+
+        let cancelled_all = CancelAllResult { cancelled: 3, skipped: 1 };
+        assert_eq!(CancelAllResult::unpack(&cancelled_all.pack()), Ok(cancelled_all));
+
+        let modified = ModifyOrderResult {
+            order_id: 13,
+            order_idx: 3,
+            state: OrderState::LIVE,
+            requeued: true,
+        };
+        assert_eq!(ModifyOrderResult::unpack(&modified.pack()), Ok(modified));
+
+        let funding = FundingResult {
+            funding_rate: -12,
+            cum_funding: -1_234_567,
+            intervals: 2,
+            last_funding_ts: 7_200_000,
+        };
+        assert_eq!(FundingResult::unpack(&funding.pack()), Ok(funding));
+
+        let index = IndexPriceResult {
+            index_price: 50_100,
+            mark_price: 50_150,
+            publish_ts: 1_700_000_000_000,
+            conf_bps: 3,
+        };
+        assert_eq!(IndexPriceResult::unpack(&index.pack()), Ok(index));
+
+        let reaped = ReapExpiredResult { reaped: 3, next_cursor: 128 };
+        assert_eq!(ReapExpiredResult::unpack(&reaped.pack()), Ok(reaped));
+
+        let liquidated = LiquidationResult {
+            closed_qty: 8,
+            closed_notional: 380_000,
+            liq_fee: 1_900,
+            adl_qty: 2,
+            insurance_used: 950,
+            remaining_deficit: 17,
+        };
+        assert_eq!(LiquidationResult::unpack(&liquidated.pack()), Ok(liquidated));
+
+        let insurance = InsuranceResult { balance: 10_000, paid_out: 950 };
+        assert_eq!(InsuranceResult::unpack(&insurance.pack()), Ok(insurance));
+    }
+}
+
+/// Heap-backed slab for tests
+///
+/// `SlabState` is ~10 MB and cannot live on the test thread's stack, so tests
+/// allocate aligned account-sized storage and initialize it in place exactly
+/// like the Initialize instruction does on-chain.
+#[cfg(test)]
+pub(crate) mod harness {
+    extern crate std;
+
+    use crate::instructions::{process_add_instrument, AddInstrumentArgs};
+    use crate::matching::place_order;
+    use crate::state::{SlabHeader, SlabState};
+    use percolator_common::Side;
+    use pinocchio::pubkey::Pubkey;
+    use std::vec;
+    use std::vec::Vec;
+
+    pub struct TestSlab {
+        buf: Vec<u128>,
+    }
+
+    impl TestSlab {
+        /// Allocate zeroed, 16-byte aligned storage without initializing it
+        pub fn uninit() -> Self {
+            let words = SlabState::LEN.div_ceil(core::mem::size_of::<u128>());
+            Self { buf: vec![0u128; words] }
+        }
+
+        /// Allocate and initialize a slab with default test risk params
+        pub fn new() -> Self {
+            let mut t = Self::uninit();
+            let header = SlabHeader::new(
+                Pubkey::default(),
+                Pubkey::default(),
+                Pubkey::default(),
+                500,
+                250,
+                -5,
+                20,
+                100,
+                0,
+            );
+            SlabState::init_in_place(t.bytes_mut(), header).unwrap();
+            t
+        }
+
+        /// List an instrument with tick 10, lot 1, contract size 1 at index 50,000
+        pub fn add_instrument(&mut self, symbol: &[u8; 8]) -> u16 {
+            let args = AddInstrumentArgs {
+                symbol: *symbol,
+                contract_size: 1,
+                tick: 10,
+                lot: 1,
+                index_price: 50_000,
+                oracle: [0; 32],
+            };
+            let owner = self.header.lp_owner;
+            process_add_instrument(self, &owner, &args)
+                .unwrap()
+                .instrument_idx
+        }
+
+        /// BTC-PERP with a DLP maker `[1; 32]` resting `quotes` of
+        /// (side, price, qty) and a taker `[2; 32]`.
+        /// Returns (slab, instrument, maker, taker).
+        pub fn with_market(
+            maker_cash: i128,
+            taker_cash: i128,
+            quotes: &[(Side, u64, u64)],
+        ) -> (Self, u16, u32, u32) {
+            let mut t = Self::new();
+            let iidx = t.add_instrument(b"BTC-PERP");
+            let maker = t.add_account(1, maker_cash);
+            t.add_dlp(maker).unwrap();
+            let taker = t.add_account(2, taker_cash);
+            for &(side, price, qty) in quotes {
+                place_order(&mut t, maker, iidx, side, price, qty, 0).unwrap();
+            }
+            (t, iidx, maker, taker)
+        }
+
+        /// Open a slab account for `[id; 32]` funded with `cash`
+        pub fn add_account(&mut self, id: u8, cash: i128) -> u32 {
+            let idx = self.find_or_create_account(&[id; 32]).unwrap();
+            self.get_account_mut(idx).unwrap().cash = cash;
+            idx
+        }
+
+        pub fn bytes_mut(&mut self) -> &mut [u8] {
+            let len = self.buf.len() * core::mem::size_of::<u128>();
+            // SAFETY: the Vec owns `len` initialized bytes and u8 has no alignment needs
+            unsafe { core::slice::from_raw_parts_mut(self.buf.as_mut_ptr() as *mut u8, len) }
+        }
+    }
+
+    impl core::ops::Deref for TestSlab {
+        type Target = SlabState;
+
+        fn deref(&self) -> &SlabState {
+            // SAFETY: storage is aligned, sized for SlabState and initialized in place
+            unsafe { &*(self.buf.as_ptr() as *const SlabState) }
+        }
+    }
+
+    impl core::ops::DerefMut for TestSlab {
+        fn deref_mut(&mut self) -> &mut SlabState {
+            // SAFETY: see Deref
+            unsafe { &mut *(self.buf.as_mut_ptr() as *mut SlabState) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod initialize_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{process_initialize, InitializeArgs};
+    use crate::state::SlabHeader;
+    use percolator_common::*;
+    use pinocchio::pubkey::Pubkey;
+
+    fn args() -> InitializeArgs {
+        InitializeArgs {
+            router_id: [9; 32],
+            imr: 500,
+            mmr: 250,
+            maker_fee: -5,
+            taker_fee: 20,
+            batch_ms: 100,
+            bump: 254,
+        }
+    }
+
+    #[test]
+    fn test_initialize_in_place() {
+        let mut t = TestSlab::uninit();
+        let owner: Pubkey = [1; 32];
+
+        let slab = process_initialize(t.bytes_mut(), &Pubkey::default(), &owner, &args()).unwrap();
+
+        assert!(slab.header.validate());
+        assert_eq!(slab.header.lp_owner, owner);
+        assert_eq!(slab.header.router_id, [9; 32]);
+        assert_eq!(slab.header.bump, 254);
+        assert_eq!(slab.instrument_count, 0);
+        assert!(slab
+            .instruments
+            .iter()
+            .all(|i| i.bids_head == u32::MAX && i.asks_pending_head == u32::MAX));
+
+        // Freelists are threaded in index order
+        assert_eq!(slab.orders.used(), 0);
+        assert_eq!(slab.orders.alloc(), Some(0));
+        assert_eq!(slab.orders.alloc(), Some(1));
+        assert_eq!(slab.reservations.alloc(), Some(0));
+        assert_eq!(slab.slices.alloc(), Some(0));
+        assert_eq!(slab.positions.alloc(), Some(0));
+        assert_eq!(slab.aggressor_ledger.alloc(), Some(0));
+    }
+
+    #[test]
+    fn test_initialize_rejects_reinit() {
+        let mut t = TestSlab::new();
+        t.orders.alloc();
+
+        assert_eq!(
+            process_initialize(t.bytes_mut(), &Pubkey::default(), &[1; 32], &args()).err(),
+            Some(PercolatorError::AlreadyInitialized)
+        );
+
+        // Existing state untouched
+        assert_eq!(t.orders.used(), 1);
+        assert_eq!(&t.header.magic, SlabHeader::MAGIC);
+    }
+
+    #[test]
+    fn test_initialize_validates_params() {
+        let mut t = TestSlab::uninit();
+        let bad = InitializeArgs { mmr: 600, ..args() };
+
+        assert_eq!(
+            process_initialize(t.bytes_mut(), &Pubkey::default(), &[1; 32], &bad).err(),
+            Some(PercolatorError::InvalidRiskParams)
+        );
+
+        let mut short = [0u8; 64];
+        assert_eq!(
+            process_initialize(&mut short, &Pubkey::default(), &[1; 32], &args()).err(),
+            Some(PercolatorError::InvalidAccount)
+        );
+    }
+}
+
+#[cfg(test)]
+mod add_instrument_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{process_add_instrument, AddInstrumentArgs};
+    use percolator_common::*;
+
+    fn args(symbol: &[u8; 8]) -> AddInstrumentArgs {
+        AddInstrumentArgs {
+            symbol: *symbol,
+            contract_size: 1_000,
+            tick: 100,
+            lot: 10,
+            index_price: 50_000_000,
+            oracle: [7; 32],
+        }
+    }
+
+    #[test]
+    fn test_add_instrument() {
+        let mut t = TestSlab::new();
+        let owner = t.header.lp_owner;
+
+        let first = process_add_instrument(&mut t, &owner, &args(b"BTC-PERP")).unwrap();
+        let second = process_add_instrument(&mut t, &owner, &args(b"ETH-PERP")).unwrap();
+        assert_eq!(first.instrument_idx, 0);
+        assert_eq!(second.instrument_idx, 1);
+        assert_eq!(t.instrument_count, 2);
+
+        let instrument = t.get_instrument(1).unwrap();
+        assert_eq!(&instrument.symbol, b"ETH-PERP");
+        assert_eq!(instrument.index, 1);
+        assert_eq!(instrument.index_price, 50_000_000);
+        assert_eq!(instrument.bids_head, u32::MAX);
+        assert_eq!(instrument.asks_head, u32::MAX);
+        assert_eq!(instrument.bids_pending_head, u32::MAX);
+        assert_eq!(instrument.asks_pending_head, u32::MAX);
+        assert_eq!(instrument.cum_funding, 0);
+        assert_eq!(instrument.funding_rate, 0);
+    }
+
+    #[test]
+    fn test_add_instrument_requires_lp_owner() {
+        let mut t = TestSlab::new();
+
+        assert_eq!(
+            process_add_instrument(&mut t, &[7; 32], &args(b"BTC-PERP")),
+            Err(PercolatorError::Unauthorized)
+        );
+        assert_eq!(t.instrument_count, 0);
+    }
+
+    #[test]
+    fn test_add_instrument_validates_spec() {
+        let mut t = TestSlab::new();
+        let owner = t.header.lp_owner;
+
+        let zero_tick = AddInstrumentArgs { tick: 0, ..args(b"BTC-PERP") };
+        let zero_lot = AddInstrumentArgs { lot: 0, ..args(b"BTC-PERP") };
+        let zero_size = AddInstrumentArgs { contract_size: 0, ..args(b"BTC-PERP") };
+        for bad in [zero_tick, zero_lot, zero_size] {
+            assert_eq!(
+                process_add_instrument(&mut t, &owner, &bad),
+                Err(PercolatorError::InvalidInstrument)
+            );
+        }
+
+        process_add_instrument(&mut t, &owner, &args(b"BTC-PERP")).unwrap();
+        assert_eq!(
+            process_add_instrument(&mut t, &owner, &args(b"BTC-PERP")),
+            Err(PercolatorError::InvalidInstrument)
+        );
+        assert_eq!(t.instrument_count, 1);
+    }
+}
+
+#[cfg(test)]
+mod place_order_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{process_batch_open, process_place_order, PlaceOrderArgs};
+    use crate::matching::place_order;
+    use percolator_common::*;
+
+    #[test]
+    fn test_dlp_order_goes_live() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+
+        let placed = place_order(&mut t, maker, iidx, Side::Sell, 50_010, 5, 1_000).unwrap();
+        assert_eq!(placed.state, OrderState::LIVE);
+        assert_eq!(t.get_instrument(iidx).unwrap().asks_head, placed.order_idx);
+
+        let order = t.orders.get(placed.order_idx).unwrap();
+        assert_eq!(order.maker_class, MakerClass::DLP);
+        assert_eq!(order.eligible_epoch, 0);
+        assert_eq!(order.created_ms, 1_000);
+        assert_eq!(order.qty_orig, 5);
+    }
+
+    #[test]
+    fn test_reg_order_waits_one_batch() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        t.add_account(2, 1_000_000_000);
+
+        let args = PlaceOrderArgs {
+            instrument_idx: iidx,
+            side: Side::Buy,
+            price: 49_990,
+            qty: 3,
+        };
+        let placed = process_place_order(&mut t, &[2; 32], &args, 1_000).unwrap();
+
+        assert_eq!(placed.state, OrderState::PENDING);
+        assert_eq!(t.get_instrument(iidx).unwrap().bids_head, u32::MAX);
+        assert_eq!(
+            t.get_instrument(iidx).unwrap().bids_pending_head,
+            placed.order_idx
+        );
+        assert_eq!(t.orders.get(placed.order_idx).unwrap().eligible_epoch, 1);
+
+        process_batch_open(&mut t, &[9; 32], iidx, 2_000).unwrap();
+        assert_eq!(t.get_instrument(iidx).unwrap().bids_head, placed.order_idx);
+        assert_eq!(
+            t.orders.get(placed.order_idx).unwrap().state,
+            OrderState::LIVE
+        );
+    }
+
+    #[test]
+    fn test_order_ids_monotonic() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+
+        let a = place_order(&mut t, maker, iidx, Side::Buy, 49_000, 1, 0).unwrap();
+        let b = place_order(&mut t, maker, iidx, Side::Buy, 49_000, 1, 0).unwrap();
+        assert_eq!(b.order_id, a.order_id + 1);
+        assert_eq!(t.header.next_order_id, b.order_id + 1);
+    }
+
+    #[test]
+    fn test_place_order_rejections() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000);
+
+        assert_eq!(
+            place_order(&mut t, maker, iidx, Side::Buy, 49_995, 1, 0),
+            Err(PercolatorError::PriceNotAligned)
+        );
+        assert_eq!(
+            place_order(&mut t, maker, iidx, Side::Buy, 49_990, 0, 0),
+            Err(PercolatorError::InvalidQuantity)
+        );
+        // IM for 10 @ 50,000 at 5% is 25,000 > 1,000 cash
+        assert_eq!(
+            place_order(&mut t, maker, iidx, Side::Sell, 50_000, 10, 0),
+            Err(PercolatorError::InsufficientMargin)
+        );
+        assert_eq!(t.orders.used(), 0);
+    }
+}
+
+#[cfg(test)]
+mod cancel_order_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{process_cancel_all, process_cancel_order, CancelAllArgs, CancelOrderArgs};
+    use crate::matching::{cancel_all, cancel_order, commit, place_order, reserve, CancelAllResult};
+    use percolator_common::*;
+
+    /// Walk an account's order list, newest first, and compare the slots
+    fn assert_orders(t: &TestSlab, account_idx: u32, expected: &[u32]) {
+        let mut order_idx = t.get_account(account_idx).unwrap().order_head;
+        let mut prev = u32::MAX;
+        for &idx in expected {
+            assert_eq!(order_idx, idx);
+            let order = t.orders.get(order_idx).unwrap();
+            assert_eq!(order.prev_in_account, prev);
+            prev = order_idx;
+            order_idx = order.next_in_account;
+        }
+        assert_eq!(order_idx, u32::MAX);
+    }
+
+    #[test]
+    fn test_cancel_live_and_pending() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let reg = t.add_account(2, 1_000_000_000);
+
+        let live = place_order(&mut t, maker, iidx, Side::Sell, 50_010, 5, 0).unwrap();
+        let pending = place_order(&mut t, reg, iidx, Side::Buy, 49_990, 3, 0).unwrap();
+
+        let args = CancelOrderArgs { order_id: live.order_id };
+        let res = process_cancel_order(&mut t, &[1; 32], &args).unwrap();
+        assert_eq!(res.cancelled_qty, 5);
+        assert_eq!(t.get_instrument(iidx).unwrap().asks_head, u32::MAX);
+
+        cancel_order(&mut t, reg, pending.order_id).unwrap();
+        assert_eq!(t.get_instrument(iidx).unwrap().bids_pending_head, u32::MAX);
+        assert_eq!(t.orders.used(), 0);
+
+        // Already gone
+        assert_eq!(
+            cancel_order(&mut t, reg, pending.order_id),
+            Err(PercolatorError::OrderNotFound)
+        );
+    }
+
+    #[test]
+    fn test_cancel_requires_owner() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_account(2, 0);
+
+        let placed = place_order(&mut t, maker, iidx, Side::Sell, 50_010, 5, 0).unwrap();
+        let args = CancelOrderArgs { order_id: placed.order_id };
+
+        assert_eq!(
+            process_cancel_order(&mut t, &[2; 32], &args),
+            Err(PercolatorError::InvalidAccount)
+        );
+        assert_eq!(
+            process_cancel_order(&mut t, &[9; 32], &args),
+            Err(PercolatorError::InvalidAccount)
+        );
+        assert_eq!(t.orders.used(), 1);
+    }
+
+    #[test]
+    fn test_cancel_rejects_reserved_order() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let taker = t.add_account(2, 1_000_000_000);
+
+        let placed = place_order(&mut t, maker, iidx, Side::Sell, 50_010, 5, 0).unwrap();
+        reserve(&mut t, taker, iidx, Side::Buy, 2, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0).unwrap();
+
+        assert_eq!(
+            cancel_order(&mut t, maker, placed.order_id),
+            Err(PercolatorError::ReservedQtyExceeded)
+        );
+
+        let res = cancel_all(&mut t, maker, None).unwrap();
+        assert_eq!(res, CancelAllResult { cancelled: 0, skipped: 1 });
+        assert_eq!(t.get_instrument(iidx).unwrap().asks_head, placed.order_idx);
+    }
+
+    #[test]
+    fn test_cancel_all_scoped_to_instrument() {
+        let mut t = TestSlab::new();
+        let btc = t.add_instrument(b"BTC-PERP");
+        let eth = t.add_instrument(b"ETH-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        let other = t.add_account(2, 1_000_000_000);
+
+        place_order(&mut t, maker, btc, Side::Buy, 49_000, 1, 0).unwrap();
+        place_order(&mut t, maker, btc, Side::Sell, 51_000, 1, 0).unwrap();
+        place_order(&mut t, maker, eth, Side::Buy, 49_000, 1, 0).unwrap();
+        place_order(&mut t, other, btc, Side::Buy, 48_000, 1, 0).unwrap();
+
+        let scoped = CancelAllArgs { instrument_idx: btc };
+        let res = process_cancel_all(&mut t, &[1; 32], &scoped).unwrap();
+        assert_eq!((res.cancelled, res.skipped), (2, 0));
+        assert_eq!(t.orders.used(), 2);
+
+        let all = CancelAllArgs { instrument_idx: CancelAllArgs::ALL_INSTRUMENTS };
+        let res = process_cancel_all(&mut t, &[1; 32], &all).unwrap();
+        assert_eq!((res.cancelled, res.skipped), (1, 0));
+
+        // Other account's order is untouched
+        assert_eq!(t.orders.used(), 1);
+        assert_ne!(t.get_instrument(btc).unwrap().bids_pending_head, u32::MAX);
+    }
+
+    #[test]
+    fn test_order_lists_follow_orders() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let other = t.add_account(2, 1_000_000_000);
+        let taker = t.add_account(3, 1_000_000_000);
+
+        let a = place_order(&mut t, maker, iidx, Side::Sell, 50_010, 5, 0).unwrap().order_idx;
+        let b = place_order(&mut t, maker, iidx, Side::Sell, 50_020, 5, 0).unwrap();
+        let c = place_order(&mut t, maker, iidx, Side::Buy, 49_990, 5, 0).unwrap().order_idx;
+        let d = place_order(&mut t, other, iidx, Side::Sell, 50_030, 5, 0).unwrap().order_idx;
+        assert_orders(&t, maker, &[c, b.order_idx, a]);
+        assert_orders(&t, other, &[d]);
+
+        cancel_order(&mut t, maker, b.order_id).unwrap();
+        assert_orders(&t, maker, &[c, a]);
+
+        // A full fill unlinks the maker order too
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 5, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0).unwrap();
+        commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        assert_orders(&t, maker, &[c]);
+
+        let res = cancel_all(&mut t, maker, None).unwrap();
+        assert_eq!((res.cancelled, res.skipped), (1, 0));
+        assert_orders(&t, maker, &[]);
+        assert_orders(&t, other, &[d]);
+    }
+
+    #[test]
+    fn test_order_index_follows_orders() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let taker = t.add_account(2, 1_000_000_000);
+
+        let a = place_order(&mut t, maker, iidx, Side::Sell, 50_010, 5, 0).unwrap();
+        let b = place_order(&mut t, maker, iidx, Side::Sell, 50_020, 5, 0).unwrap();
+        assert_eq!(t.find_order(a.order_id), Some(a.order_idx));
+        assert_eq!(t.find_order(b.order_id), Some(b.order_idx));
+
+        // A new order in the freed slot is found under its own id only
+        cancel_order(&mut t, maker, b.order_id).unwrap();
+        let c = place_order(&mut t, maker, iidx, Side::Sell, 50_030, 5, 0).unwrap();
+        assert_eq!(c.order_idx, b.order_idx);
+        assert_eq!(t.find_order(b.order_id), None);
+        assert_eq!(t.find_order(c.order_id), Some(c.order_idx));
+        assert_eq!(cancel_order(&mut t, maker, b.order_id), Err(PercolatorError::OrderNotFound));
+
+        // Filled orders leave the index
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 5, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0).unwrap();
+        commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        assert_eq!(t.find_order(a.order_id), None);
+    }
+}
+
+#[cfg(test)]
+mod modify_order_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{process_batch_open, process_modify_order, ModifyOrderArgs};
+    use crate::matching::{modify_order, place_order, reserve, PlaceOrderResult};
+    use percolator_common::*;
+
+    /// Two DLP asks at the same level; returns (instrument, maker, first, second)
+    fn two_asks(t: &mut TestSlab) -> (u16, u32, PlaceOrderResult, PlaceOrderResult) {
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+
+        let first = place_order(t, maker, iidx, Side::Sell, 50_010, 5, 0).unwrap();
+        let second = place_order(t, maker, iidx, Side::Sell, 50_010, 5, 0).unwrap();
+        (iidx, maker, first, second)
+    }
+
+    fn best_ask_id(t: &TestSlab, iidx: u16) -> u64 {
+        let head = t.get_instrument(iidx).unwrap().asks_head;
+        t.orders.get(head).unwrap().order_id
+    }
+
+    #[test]
+    fn test_size_down_keeps_priority() {
+        let mut t = TestSlab::new();
+        let (iidx, maker, first, _) = two_asks(&mut t);
+
+        let res = modify_order(&mut t, maker, first.order_id, 50_010, 2, 10).unwrap();
+        assert!(!res.requeued);
+        assert_eq!(res.order_id, first.order_id);
+        assert_eq!(best_ask_id(&t, iidx), first.order_id);
+        assert_eq!(t.orders.get(res.order_idx).unwrap().qty, 2);
+    }
+
+    #[test]
+    fn test_size_up_requeues() {
+        let mut t = TestSlab::new();
+        let (iidx, _, first, second) = two_asks(&mut t);
+
+        let args = ModifyOrderArgs { order_id: first.order_id, new_price: 50_010, new_qty: 8 };
+        let res = process_modify_order(&mut t, &[1; 32], &args, 10).unwrap();
+        assert!(res.requeued);
+        assert_eq!(res.order_idx, first.order_idx);
+        assert!(res.order_id > second.order_id);
+        assert_eq!(best_ask_id(&t, iidx), second.order_id);
+
+        // The old id no longer addresses the order
+        assert_eq!(
+            process_modify_order(&mut t, &[1; 32], &args, 20),
+            Err(PercolatorError::OrderNotFound)
+        );
+
+        let order = t.orders.get(res.order_idx).unwrap();
+        assert_eq!((order.qty, order.qty_orig, order.created_ms), (8, 8, 10));
+    }
+
+    #[test]
+    fn test_reprice_requeues_reg_to_pending() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let reg = t.add_account(2, 1_000_000_000);
+
+        let placed = place_order(&mut t, reg, iidx, Side::Buy, 49_990, 3, 0).unwrap();
+        process_batch_open(&mut t, &[9; 32], iidx, 1_000).unwrap();
+        assert_eq!(t.orders.get(placed.order_idx).unwrap().state, OrderState::LIVE);
+
+        let res = modify_order(&mut t, reg, placed.order_id, 49_980, 3, 1_500).unwrap();
+        assert!(res.requeued);
+        assert_eq!(res.state, OrderState::PENDING);
+        assert_eq!(t.get_instrument(iidx).unwrap().bids_head, u32::MAX);
+        assert_eq!(t.get_instrument(iidx).unwrap().bids_pending_head, placed.order_idx);
+        assert_eq!(t.orders.get(placed.order_idx).unwrap().eligible_epoch, 2);
+    }
+
+    #[test]
+    fn test_modify_respects_reserved_qty() {
+        let mut t = TestSlab::new();
+        let (iidx, maker, first, _) = two_asks(&mut t);
+        let taker = t.add_account(3, 1_000_000_000);
+        reserve(&mut t, taker, iidx, Side::Buy, 3, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0).unwrap();
+
+        assert_eq!(
+            modify_order(&mut t, maker, first.order_id, 50_010, 2, 10),
+            Err(PercolatorError::ReservedQtyExceeded)
+        );
+        assert_eq!(
+            modify_order(&mut t, maker, first.order_id, 50_020, 5, 10),
+            Err(PercolatorError::ReservedQtyExceeded)
+        );
+        assert_eq!(
+            modify_order(&mut t, maker, first.order_id, 50_010, 8, 10),
+            Err(PercolatorError::ReservedQtyExceeded)
+        );
+
+        // Shrinking down to the locked amount is fine once the batch's freeze lifts
+        assert_eq!(
+            modify_order(&mut t, maker, first.order_id, 50_010, 3, 10),
+            Err(PercolatorError::OrderFrozen)
+        );
+        process_batch_open(&mut t, &[9; 32], iidx, 1_000).unwrap();
+        let res = modify_order(&mut t, maker, first.order_id, 50_010, 3, 10).unwrap();
+        assert!(!res.requeued);
+    }
+
+    #[test]
+    fn test_modify_requires_owner_and_alignment() {
+        let mut t = TestSlab::new();
+        let (_, maker, first, _) = two_asks(&mut t);
+        let other = t.add_account(2, 1_000_000_000);
+
+        assert_eq!(
+            modify_order(&mut t, other, first.order_id, 50_010, 1, 0),
+            Err(PercolatorError::InvalidAccount)
+        );
+        assert_eq!(
+            modify_order(&mut t, maker, first.order_id, 50_015, 5, 0),
+            Err(PercolatorError::PriceNotAligned)
+        );
+        assert_eq!(
+            modify_order(&mut t, maker, first.order_id, 50_010, 0, 0),
+            Err(PercolatorError::InvalidQuantity)
+        );
+    }
+}
+
+#[cfg(test)]
+mod hold_owner_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{process_cancel, process_commit, process_reserve};
+    use crate::matching::place_order;
+    use percolator_common::*;
+
+    /// DLP ask of 5 at 50,010 and a funded taker with key [2; 32]
+    fn setup() -> (TestSlab, u16, u32) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let taker = t.add_account(2, 1_000_000_000);
+        place_order(&mut t, maker, iidx, Side::Sell, 50_010, 5, 0).unwrap();
+        (t, iidx, taker)
+    }
+
+    fn reserve_as(t: &mut TestSlab, owner: &[u8; 32], iidx: u16, taker: u32) -> Result<u64, PercolatorError> {
+        process_reserve(t, owner, taker, iidx, Side::Buy, 2, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0)
+            .map(|res| res.hold_id)
+    }
+
+    #[test]
+    fn test_reserve_requires_account_owner() {
+        let (mut t, iidx, taker) = setup();
+
+        assert_eq!(reserve_as(&mut t, &[1; 32], iidx, taker), Err(PercolatorError::InvalidAccount));
+        assert_eq!(reserve_as(&mut t, &[9; 32], iidx, taker), Err(PercolatorError::InvalidAccount));
+        assert_eq!(t.reservations.used(), 0);
+
+        reserve_as(&mut t, &[2; 32], iidx, taker).unwrap();
+    }
+
+    #[test]
+    fn test_commit_and_cancel_require_hold_owner() {
+        let (mut t, iidx, taker) = setup();
+        let hold_id = reserve_as(&mut t, &[2; 32], iidx, taker).unwrap();
+
+        assert_eq!(
+            process_commit(&mut t, &[1; 32], hold_id, &[0; 16], 1).map(|res| res.filled_qty),
+            Err(PercolatorError::InvalidAccount)
+        );
+        assert_eq!(process_cancel(&mut t, &[1; 32], hold_id), Err(PercolatorError::InvalidAccount));
+        assert_eq!(t.reservations.used(), 1);
+
+        process_cancel(&mut t, &[2; 32], hold_id).unwrap();
+        assert_eq!(
+            process_cancel(&mut t, &[2; 32], hold_id),
+            Err(PercolatorError::ReservationNotFound)
+        );
+
+        let hold_id = reserve_as(&mut t, &[2; 32], iidx, taker).unwrap();
+        let res = process_commit(&mut t, &[2; 32], hold_id, &[0; 16], 1).unwrap();
+        assert_eq!(res.filled_qty, 2);
+    }
+}
+
+#[cfg(test)]
+mod time_in_force_tests {
+    use super::harness::TestSlab;
+    use crate::matching::{commit, get_position_qty, place_order, reserve};
+    use percolator_common::*;
+
+    /// DLP maker with 5 @ 50,010 and 5 @ 50,020 on the ask side
+    fn book(t: &mut TestSlab) -> (u16, u32) {
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let taker = t.add_account(2, 1_000_000_000);
+
+        place_order(t, maker, iidx, Side::Sell, 50_010, 5, 0).unwrap();
+        place_order(t, maker, iidx, Side::Sell, 50_020, 5, 0).unwrap();
+        (iidx, taker)
+    }
+
+    fn buy(
+        t: &mut TestSlab,
+        iidx: u16,
+        taker: u32,
+        qty: u64,
+        limit_px: u64,
+        tif: TimeInForce,
+        flags: u8,
+    ) -> Result<crate::matching::ReserveResult, PercolatorError> {
+        reserve(t, taker, iidx, Side::Buy, qty, limit_px, 1_000, [0; 32], 1, tif, flags)
+    }
+
+    #[test]
+    fn test_fok_requires_full_fill() {
+        let mut t = TestSlab::new();
+        let (iidx, taker) = book(&mut t);
+
+        assert_eq!(
+            buy(&mut t, iidx, taker, 11, 50_020, TimeInForce::FOK, 0),
+            Err(PercolatorError::InsufficientLiquidity)
+        );
+        // Nothing stays locked after the kill
+        assert_eq!(t.slices.used(), 0);
+        assert_eq!(t.reservations.used(), 0);
+        let head = t.get_instrument(iidx).unwrap().asks_head;
+        assert_eq!(t.orders.get(head).unwrap().reserved_qty, 0);
+
+        let res = buy(&mut t, iidx, taker, 10, 50_020, TimeInForce::FOK, 0).unwrap();
+        assert_eq!(res.filled_qty, 10);
+    }
+
+    #[test]
+    fn test_ioc_remainder_does_not_rest() {
+        let mut t = TestSlab::new();
+        let (iidx, taker) = book(&mut t);
+
+        let res = buy(&mut t, iidx, taker, 8, 50_010, TimeInForce::IOC, 0).unwrap();
+        assert_eq!(res.filled_qty, 5);
+
+        let done = commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        assert_eq!((done.filled_qty, done.rested_qty), (5, 0));
+        assert_eq!(t.get_instrument(iidx).unwrap().bids_pending_head, u32::MAX);
+    }
+
+    #[test]
+    fn test_gtc_remainder_rests_on_commit() {
+        let mut t = TestSlab::new();
+        let (iidx, taker) = book(&mut t);
+
+        let res = buy(&mut t, iidx, taker, 8, 50_010, TimeInForce::GTC, 0).unwrap();
+        let done = commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        assert_eq!((done.filled_qty, done.rested_qty), (5, 3));
+
+        // Taker is a regular account, so the remainder waits in the pending book
+        let rested = t.get_instrument(iidx).unwrap().bids_pending_head;
+        let order = t.orders.get(rested).unwrap();
+        assert_eq!(order.order_id, done.rested_order_id);
+        assert_eq!((order.price, order.qty, order.account_idx), (50_010, 3, taker));
+    }
+
+    #[test]
+    fn test_post_only_rejects_crossing() {
+        let mut t = TestSlab::new();
+        let (iidx, taker) = book(&mut t);
+
+        assert_eq!(
+            buy(&mut t, iidx, taker, 1, 50_010, TimeInForce::GTC, ORDER_FLAG_POST_ONLY),
+            Err(PercolatorError::PostOnlyWouldCross)
+        );
+        assert_eq!(
+            buy(&mut t, iidx, taker, 1, 50_000, TimeInForce::IOC, ORDER_FLAG_POST_ONLY),
+            Err(PercolatorError::InvalidTimeInForce)
+        );
+
+        let res = buy(&mut t, iidx, taker, 1, 50_000, TimeInForce::GTC, ORDER_FLAG_POST_ONLY).unwrap();
+        assert_eq!(res.filled_qty, 0);
+        let done = commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        assert_eq!(done.rested_qty, 1);
+    }
+
+    #[test]
+    fn test_reduce_only_clips_to_position() {
+        let mut t = TestSlab::new();
+        let (iidx, taker) = book(&mut t);
+
+        // Flat: nothing to reduce
+        assert_eq!(
+            buy(&mut t, iidx, taker, 1, 50_020, TimeInForce::IOC, ORDER_FLAG_REDUCE_ONLY),
+            Err(PercolatorError::ReduceOnlyViolation)
+        );
+
+        // Go short 2 against a bid, then reduce-only buy 10 is clipped to 2
+        let bidder = t.add_account(3, 1_000_000_000);
+        t.add_dlp(bidder).unwrap();
+        place_order(&mut t, bidder, iidx, Side::Buy, 50_000, 2, 0).unwrap();
+        let sell =
+            reserve(&mut t, taker, iidx, Side::Sell, 2, 50_000, 1_000, [0; 32], 1, TimeInForce::IOC, 0)
+                .unwrap();
+        commit(&mut t, sell.hold_id, &[0; 16], 0).unwrap();
+
+        let res =
+            buy(&mut t, iidx, taker, 10, 50_020, TimeInForce::GTC, ORDER_FLAG_REDUCE_ONLY).unwrap();
+        assert_eq!(res.filled_qty, 2);
+        let done = commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        assert_eq!(done.rested_qty, 0);
+    }
+
+    #[test]
+    fn test_reduce_only_reclipped_at_commit() {
+        let mut t = TestSlab::new();
+        let (iidx, taker) = book(&mut t);
+
+        // Short 2, with a reduce-only buy of 2 on hold
+        let bidder = t.add_account(3, 1_000_000_000);
+        t.add_dlp(bidder).unwrap();
+        place_order(&mut t, bidder, iidx, Side::Buy, 50_000, 2, 0).unwrap();
+        let sell =
+            reserve(&mut t, taker, iidx, Side::Sell, 2, 50_000, 1_000, [0; 32], 1, TimeInForce::IOC, 0)
+                .unwrap();
+        commit(&mut t, sell.hold_id, &[0; 16], 0).unwrap();
+        let held = buy(&mut t, iidx, taker, 2, 50_020, TimeInForce::IOC, ORDER_FLAG_REDUCE_ONLY).unwrap();
+
+        // Another fill buys 1 back before the hold commits
+        let other = buy(&mut t, iidx, taker, 1, 50_020, TimeInForce::IOC, 0).unwrap();
+        commit(&mut t, other.hold_id, &[0; 16], 0).unwrap();
+
+        let done = commit(&mut t, held.hold_id, &[0; 16], 0).unwrap();
+        assert_eq!(done.filled_qty, 1);
+        assert_eq!(get_position_qty(&t, taker, iidx), 0);
+
+        // Flat: a second reduce-only hold can no longer fill at all
+        place_order(&mut t, bidder, iidx, Side::Buy, 50_000, 1, 0).unwrap();
+        let sell =
+            reserve(&mut t, taker, iidx, Side::Sell, 1, 50_000, 1_000, [0; 32], 1, TimeInForce::IOC, 0)
+                .unwrap();
+        commit(&mut t, sell.hold_id, &[0; 16], 0).unwrap();
+        let held = buy(&mut t, iidx, taker, 1, 50_020, TimeInForce::IOC, ORDER_FLAG_REDUCE_ONLY).unwrap();
+        let other = buy(&mut t, iidx, taker, 1, 50_020, TimeInForce::IOC, 0).unwrap();
+        commit(&mut t, other.hold_id, &[0; 16], 0).unwrap();
+        assert_eq!(
+            commit(&mut t, held.hold_id, &[0; 16], 0),
+            Err(PercolatorError::ReduceOnlyViolation)
+        );
+    }
+
+    #[test]
+    fn test_unknown_flags_rejected() {
+        let mut t = TestSlab::new();
+        let (iidx, taker) = book(&mut t);
+
+        assert_eq!(
+            buy(&mut t, iidx, taker, 1, 50_010, TimeInForce::IOC, 1 << 7),
+            Err(PercolatorError::InvalidInstruction)
+        );
+    }
+}
+
+#[cfg(test)]
+mod set_params_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{process_set_params, SetParamsArgs, SlabParam};
+    use percolator_common::*;
+
+    #[test]
+    fn test_set_params() {
+        let mut t = TestSlab::new();
+        let owner = t.header.lp_owner;
+
+        let args = SetParamsArgs { param: SlabParam::StpMode, value: StpMode::Skip as u64 };
+        process_set_params(&mut t, &owner, &args).unwrap();
+        assert_eq!(t.header.stp_mode, StpMode::Skip);
+
+        let args = SetParamsArgs { param: SlabParam::KillBandBps, value: 250 };
+        process_set_params(&mut t, &owner, &args).unwrap();
+        assert_eq!(t.header.kill_band_bps, 250);
+    }
+
+    #[test]
+    fn test_set_params_validation() {
+        let mut t = TestSlab::new();
+        let owner = t.header.lp_owner;
+
+        let args = SetParamsArgs { param: SlabParam::StpMode, value: 1 };
+        assert_eq!(
+            process_set_params(&mut t, &[9; 32], &args),
+            Err(PercolatorError::Unauthorized)
+        );
+
+        for (param, value) in [
+            (SlabParam::StpMode, 4),
+            (SlabParam::JitPenaltyOn, 2),
+            (SlabParam::FreezeLevels, u64::from(u16::MAX) + 1),
+            (SlabParam::KillBandBps, 10_001),
+            (SlabParam::MarkPremiumClampBps, 10_001),
+            (SlabParam::MarkEmaWindowMs, 0),
+            (SlabParam::ArgMode, 3),
+            (SlabParam::ShrinkPolicy, 2),
+            (SlabParam::LiqBandBps, 10_001),
+            (SlabParam::LiqFeeBps, 10_001),
+            (SlabParam::InsuranceLiqShareBps, 10_001),
+            (SlabParam::InsuranceTakerShareBps, 10_001),
+        ] {
+            let args = SetParamsArgs { param, value };
+            assert_eq!(
+                process_set_params(&mut t, &owner, &args),
+                Err(PercolatorError::InvalidRiskParams)
+            );
+        }
+        assert_eq!(t.header.stp_mode, StpMode::CancelResting);
+    }
+}
+
+#[cfg(test)]
+mod stp_tests {
+    use super::harness::TestSlab;
+    use crate::matching::{commit, place_order, reserve, ReserveResult};
+    use percolator_common::*;
+
+    /// Taker rests its own ask 4 @ 50,010 ahead of another maker's 5 @ 50,020.
+    /// Returns (instrument, taker, own ask idx).
+    fn book(t: &mut TestSlab, mode: StpMode) -> (u16, u32, u32) {
+        t.header.stp_mode = mode;
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let taker = t.add_account(1, 1_000_000_000);
+        let maker = t.add_account(2, 1_000_000_000);
+        t.add_dlp(taker).unwrap();
+        t.add_dlp(maker).unwrap();
+
+        let own = place_order(t, taker, iidx, Side::Sell, 50_010, 4, 0).unwrap();
+        place_order(t, maker, iidx, Side::Sell, 50_020, 5, 0).unwrap();
+        (iidx, taker, own.order_idx)
+    }
+
+    fn buy(t: &mut TestSlab, iidx: u16, taker: u32, qty: u64, tif: TimeInForce) -> ReserveResult {
+        reserve(t, taker, iidx, Side::Buy, qty, 50_020, 1_000, [0; 32], 1, tif, 0).unwrap()
+    }
+
+    #[test]
+    fn test_stp_cancel_resting() {
+        let mut t = TestSlab::new();
+        let (iidx, taker, own) = book(&mut t, StpMode::CancelResting);
+
+        let res = buy(&mut t, iidx, taker, 3, TimeInForce::IOC);
+        assert_eq!(res.stp_mode, StpMode::CancelResting);
+        assert_eq!((res.stp_hits, res.stp_qty, res.filled_qty), (1, 4, 3));
+        assert_eq!(res.vwap_px, 50_020);
+        assert!(t.orders.get(own).is_none());
+    }
+
+    #[test]
+    fn test_stp_cancel_taker() {
+        let mut t = TestSlab::new();
+        let (iidx, taker, own) = book(&mut t, StpMode::CancelTaker);
+
+        let res = buy(&mut t, iidx, taker, 3, TimeInForce::GTC);
+        assert_eq!((res.stp_hits, res.stp_qty, res.filled_qty), (1, 3, 0));
+        assert!(t.orders.get(own).is_some());
+
+        // Cancelled remainder must not rest on commit
+        let done = commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        assert_eq!(done.rested_qty, 0);
+    }
+
+    #[test]
+    fn test_stp_decrement_both() {
+        let mut t = TestSlab::new();
+        let (iidx, taker, own) = book(&mut t, StpMode::DecrementBoth);
+
+        let res = buy(&mut t, iidx, taker, 6, TimeInForce::IOC);
+        // 4 cancelled on both sides, remaining 2 fill against the other maker
+        assert_eq!((res.stp_hits, res.stp_qty, res.filled_qty), (1, 4, 2));
+        assert!(t.orders.get(own).is_none());
+
+        // FOK is satisfied by the decremented size
+        let mut t = TestSlab::new();
+        let (iidx, taker, own) = book(&mut t, StpMode::DecrementBoth);
+        let res = buy(&mut t, iidx, taker, 2, TimeInForce::FOK);
+        assert_eq!((res.stp_qty, res.filled_qty), (2, 0));
+        assert_eq!(t.orders.get(own).unwrap().qty, 2);
+    }
+
+    #[test]
+    fn test_stp_skip() {
+        let mut t = TestSlab::new();
+        let (iidx, taker, own) = book(&mut t, StpMode::Skip);
+
+        let res = buy(&mut t, iidx, taker, 3, TimeInForce::IOC);
+        assert_eq!((res.stp_hits, res.stp_qty, res.filled_qty), (1, 4, 3));
+        assert_eq!(t.orders.get(own).unwrap().qty, 4);
+        assert_eq!(t.orders.get(own).unwrap().reserved_qty, 0);
+    }
+}
+
+#[cfg(test)]
+mod funding_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{process_update_funding, UpdateFundingArgs};
+    use crate::matching::{place_order, update_funding};
+    use percolator_common::*;
+
+    const HOUR: u64 = 3_600_000;
+    const T0: u64 = 1_700_000_000_000;
+
+    /// Instrument with a DLP quoting 50,490 / 50,510 around index 50,000 (+100 bps)
+    fn setup() -> (TestSlab, u16) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        place_order(&mut t, maker, iidx, Side::Buy, 50_490, 1, 0).unwrap();
+        place_order(&mut t, maker, iidx, Side::Sell, 50_510, 1, 0).unwrap();
+        (t, iidx)
+    }
+
+    #[test]
+    fn test_first_crank_anchors_and_sets_capped_rate() {
+        let (mut t, iidx) = setup();
+
+        let res = update_funding(&mut t, iidx, T0).unwrap();
+        assert_eq!(res.intervals, 0);
+        assert_eq!(res.cum_funding, 0);
+        assert_eq!(res.last_funding_ts, T0);
+        // +100 bps premium capped at the default 75 bps
+        assert_eq!(res.funding_rate, 75);
+    }
+
+    /// RM6: funding applied exactly once per interval
+    #[test]
+    fn test_funding_applied_once_per_interval() {
+        let (mut t, iidx) = setup();
+        let args = UpdateFundingArgs { instrument_idx: iidx };
+        process_update_funding(&mut t, &args, T0).unwrap();
+
+        // Same interval: rejected, nothing accrues
+        assert_eq!(
+            process_update_funding(&mut t, &args, T0 + HOUR - 1),
+            Err(PercolatorError::FundingNotDue)
+        );
+        assert_eq!(t.get_instrument(iidx).unwrap().cum_funding, 0);
+
+        // 75 bps/hour on 50,000 for one hour
+        let res = process_update_funding(&mut t, &args, T0 + HOUR + 10).unwrap();
+        assert_eq!((res.intervals, res.cum_funding), (1, 375));
+        assert_eq!(res.last_funding_ts, T0 + HOUR);
+
+        assert_eq!(
+            process_update_funding(&mut t, &args, T0 + 2 * HOUR - 1),
+            Err(PercolatorError::FundingNotDue)
+        );
+    }
+
+    #[test]
+    fn test_missed_intervals_catch_up() {
+        let (mut t, iidx) = setup();
+        update_funding(&mut t, iidx, T0).unwrap();
+
+        let res = update_funding(&mut t, iidx, T0 + 3 * HOUR + 5).unwrap();
+        assert_eq!((res.intervals, res.cum_funding), (3, 3 * 375));
+        assert_eq!(res.last_funding_ts, T0 + 3 * HOUR);
+    }
+
+    #[test]
+    fn test_rate_cap_applies_per_interval() {
+        let (mut t, iidx) = setup();
+        t.header.funding_interval_ms = 4 * HOUR;
+
+        // 75 bps per 4 hour interval caps the hourly rate at 18
+        let res = update_funding(&mut t, iidx, T0).unwrap();
+        assert_eq!(res.funding_rate, 18);
+        let res = update_funding(&mut t, iidx, T0 + 4 * HOUR).unwrap();
+        assert_eq!((res.intervals, res.cum_funding), (1, 360));
+    }
+
+    #[test]
+    fn test_empty_book_has_zero_rate() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+
+        update_funding(&mut t, iidx, T0).unwrap();
+        let res = update_funding(&mut t, iidx, T0 + HOUR).unwrap();
+        assert_eq!((res.funding_rate, res.cum_funding), (0, 0));
+    }
+}
+
+#[cfg(test)]
+mod settle_funding_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{process_settle_funding, SettleFundingArgs};
+    use crate::matching::{calculate_equity, commit, place_order, reserve};
+    use percolator_common::*;
+
+    const CASH: i128 = 1_000_000_000;
+
+    /// BTC-PERP with a DLP maker and a regular taker
+    ///
+    /// The taker roundtrips within one batch, so the roundtrip guard is off to
+    /// keep its tax out of the cash arithmetic.
+    fn setup() -> (TestSlab, u16, u32, u32) {
+        let mut t = TestSlab::new();
+        t.header.arg_mode = ArgMode::Off;
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, CASH);
+        t.add_dlp(maker).unwrap();
+        let taker = t.add_account(2, CASH);
+        (t, iidx, maker, taker)
+    }
+
+    /// Taker trades `qty` at `px` against a fresh maker quote
+    fn trade(t: &mut TestSlab, iidx: u16, maker: u32, taker: u32, side: Side, qty: u64, px: u64) {
+        let contra = match side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+        place_order(t, maker, iidx, contra, px, qty, 0).unwrap();
+        let res =
+            reserve(t, taker, iidx, side, qty, px, 1_000, [0; 32], 1, TimeInForce::IOC, 0).unwrap();
+        commit(t, res.hold_id, &[0; 16], 0).unwrap();
+    }
+
+    fn taker_position(t: &TestSlab, taker: u32) -> Option<Position> {
+        let head = t.get_account(taker).unwrap().position_head;
+        t.positions.get(head).copied()
+    }
+
+    #[test]
+    fn test_settle_funding_instruction() {
+        let (mut t, iidx, maker, taker) = setup();
+        trade(&mut t, iidx, maker, taker, Side::Buy, 2, 50_000);
+
+        t.get_instrument_mut(iidx).unwrap().cum_funding += 100;
+        let equity_before = calculate_equity(&t, taker).unwrap();
+
+        let args = SettleFundingArgs { account_idx: taker };
+        let res = process_settle_funding(&mut t, &args).unwrap();
+        assert_eq!(res.funding_paid, 200);
+        assert_eq!(t.get_account(taker).unwrap().cash, CASH - 200);
+        assert_eq!(taker_position(&t, taker).unwrap().last_funding, 100);
+        assert_eq!(calculate_equity(&t, taker).unwrap(), equity_before);
+
+        // Nothing left to settle; the short maker received the same amount
+        assert_eq!(process_settle_funding(&mut t, &args).unwrap().funding_paid, 0);
+        let res = process_settle_funding(&mut t, &SettleFundingArgs { account_idx: maker }).unwrap();
+        assert_eq!(res.funding_paid, -200);
+    }
+
+    #[test]
+    fn test_funding_realized_on_increase_and_reduce() {
+        let (mut t, iidx, maker, taker) = setup();
+        trade(&mut t, iidx, maker, taker, Side::Buy, 2, 50_000);
+
+        t.get_instrument_mut(iidx).unwrap().cum_funding += 100;
+        trade(&mut t, iidx, maker, taker, Side::Buy, 2, 50_000);
+        assert_eq!(t.get_account(taker).unwrap().cash, CASH - 200);
+        assert_eq!(taker_position(&t, taker).unwrap().qty, 4);
+
+        // Reduce at a higher price: funding on 4 plus PnL on the 1 closed
+        t.get_instrument_mut(iidx).unwrap().cum_funding += 10;
+        trade(&mut t, iidx, maker, taker, Side::Sell, 1, 50_100);
+        assert_eq!(t.get_account(taker).unwrap().cash, CASH - 200 - 40 + 100);
+
+        let pos = taker_position(&t, taker).unwrap();
+        assert_eq!((pos.qty, pos.entry_px, pos.last_funding), (3, 50_000, 110));
+    }
+
+    #[test]
+    fn test_funding_realized_on_close_and_flip() {
+        let (mut t, iidx, maker, taker) = setup();
+        trade(&mut t, iidx, maker, taker, Side::Buy, 2, 50_000);
+
+        // Flip long 2 -> short 1
+        t.get_instrument_mut(iidx).unwrap().cum_funding += 100;
+        trade(&mut t, iidx, maker, taker, Side::Sell, 3, 50_000);
+        assert_eq!(t.get_account(taker).unwrap().cash, CASH - 200);
+        let pos = taker_position(&t, taker).unwrap();
+        assert_eq!((pos.qty, pos.last_funding), (-1, 100));
+
+        // Close short 1: the short receives funding
+        t.get_instrument_mut(iidx).unwrap().cum_funding += 50;
+        trade(&mut t, iidx, maker, taker, Side::Buy, 1, 50_000);
+        assert_eq!(t.get_account(taker).unwrap().cash, CASH - 200 + 50);
+        assert!(taker_position(&t, taker).is_none());
+    }
+}
+
+#[cfg(test)]
+mod oracle_price_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{process_update_index_price, UpdateIndexPriceArgs};
+    use crate::matching::{check_margin_pre_trade, place_order};
+    use percolator_common::*;
+
+    const ORACLE: [u8; 32] = [9; 32];
+    const NOW: u64 = 1_700_000_000_000;
+
+    /// BTC-PERP bound to `ORACLE`
+    fn setup() -> (TestSlab, u16) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        t.get_instrument_mut(iidx).unwrap().oracle = ORACLE;
+        (t, iidx)
+    }
+
+    /// Oracle account bytes for a price with 6 decimals
+    fn reading(price: i64, conf: u64, publish_ms: u64) -> [u8; OraclePrice::LEN] {
+        OraclePrice {
+            price,
+            conf,
+            expo: -6,
+            publish_time: (publish_ms / 1_000) as i64,
+        }
+        .pack()
+    }
+
+    fn update(t: &mut TestSlab, iidx: u16, data: &[u8], now: u64) -> Result<u64, PercolatorError> {
+        let args = UpdateIndexPriceArgs { instrument_idx: iidx };
+        process_update_index_price(t, &args, &ORACLE, data, now).map(|r| r.index_price)
+    }
+
+    #[test]
+    fn test_update_index_price() {
+        let (mut t, iidx) = setup();
+        let args = UpdateIndexPriceArgs { instrument_idx: iidx };
+        let data = reading(51_000, 5, NOW);
+        let res = process_update_index_price(&mut t, &args, &ORACLE, &data, NOW + 2_000).unwrap();
+
+        assert_eq!(res.index_price, 51_000);
+        assert_eq!(res.publish_ts, NOW);
+        assert_eq!(res.conf_bps, 1);
+        let inst = t.get_instrument(iidx).unwrap();
+        assert_eq!(inst.index_price, 51_000);
+        assert_eq!(inst.index_publish_ts, NOW);
+    }
+
+    #[test]
+    fn test_update_index_price_rejects_stale_reading() {
+        let (mut t, iidx) = setup();
+        let max = t.header.max_oracle_staleness_ms;
+
+        assert_eq!(
+            update(&mut t, iidx, &reading(51_000, 5, NOW), NOW + max + 1_000),
+            Err(PercolatorError::OracleStale)
+        );
+
+        // A reading older than the recorded one is stale even if within the window
+        update(&mut t, iidx, &reading(51_000, 5, NOW), NOW).unwrap();
+        assert_eq!(
+            update(&mut t, iidx, &reading(52_000, 5, NOW - 1_000), NOW),
+            Err(PercolatorError::OracleStale)
+        );
+        assert_eq!(t.get_instrument(iidx).unwrap().index_price, 51_000);
+    }
+
+    #[test]
+    fn test_update_index_price_rejects_future_reading() {
+        let (mut t, iidx) = setup();
+
+        assert_eq!(
+            update(&mut t, iidx, &reading(60_000, 5, NOW + 60_000), NOW),
+            Err(PercolatorError::InvalidOracle)
+        );
+        assert_eq!(t.get_instrument(iidx).unwrap().index_publish_ts, 0);
+
+        // Honest readings keep flowing
+        assert_eq!(update(&mut t, iidx, &reading(51_000, 5, NOW), NOW), Ok(51_000));
+    }
+
+    #[test]
+    fn test_update_index_price_rejects_wide_confidence() {
+        let (mut t, iidx) = setup();
+
+        // 1% of price is the default limit; 1.2% is refused
+        assert_eq!(
+            update(&mut t, iidx, &reading(50_000, 600, NOW), NOW),
+            Err(PercolatorError::OracleConfidenceTooWide)
+        );
+        assert_eq!(update(&mut t, iidx, &reading(50_000, 500, NOW), NOW), Ok(50_000));
+    }
+
+    #[test]
+    fn test_update_index_price_rejects_wrong_oracle() {
+        let (mut t, iidx) = setup();
+        let args = UpdateIndexPriceArgs { instrument_idx: iidx };
+        let data = reading(51_000, 5, NOW);
+
+        assert_eq!(
+            process_update_index_price(&mut t, &args, &[8; 32], &data, NOW),
+            Err(PercolatorError::InvalidAccount)
+        );
+
+        // Malformed account data
+        assert_eq!(update(&mut t, iidx, &data[..20], NOW), Err(PercolatorError::InvalidOracle));
+
+        // Instruments listed without an oracle cannot be updated
+        let unbound = t.add_instrument(b"ETH-PERP");
+        assert_eq!(update(&mut t, unbound, &data, NOW), Err(PercolatorError::InvalidAccount));
+    }
+
+    #[test]
+    fn test_margin_refuses_stale_index() {
+        let (mut t, iidx) = setup();
+        let acct = t.add_account(1, 1_000_000_000);
+
+        // Bound but never updated
+        t.header.update_timestamp(NOW);
+        assert_eq!(check_margin_pre_trade(&t, acct, iidx, 1), Err(PercolatorError::OracleStale));
+
+        update(&mut t, iidx, &reading(50_000, 5, NOW), NOW).unwrap();
+        assert_eq!(check_margin_pre_trade(&t, acct, iidx, 1), Ok(true));
+        place_order(&mut t, acct, iidx, Side::Buy, 49_000, 1, 0).unwrap();
+
+        let max = t.header.max_oracle_staleness_ms;
+        t.header.update_timestamp(NOW + max + 1);
+        assert_eq!(check_margin_pre_trade(&t, acct, iidx, 1), Err(PercolatorError::OracleStale));
+        assert_eq!(
+            place_order(&mut t, acct, iidx, Side::Buy, 49_000, 1, 0),
+            Err(PercolatorError::OracleStale)
+        );
+    }
+}
+
+#[cfg(test)]
+mod mark_price_tests {
+    use super::harness::TestSlab;
+    use crate::matching::{calculate_equity, commit, place_order, reserve, update_mark_price};
+    use percolator_common::*;
+
+    const CASH: i128 = 1_000_000_000;
+
+    /// BTC-PERP with a DLP maker quoting 50_500 / 50_600 over a 50_000 index
+    fn setup() -> (TestSlab, u16, u32) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, CASH);
+        t.add_dlp(maker).unwrap();
+        place_order(&mut t, maker, iidx, Side::Buy, 50_500, 10, 0).unwrap();
+        place_order(&mut t, maker, iidx, Side::Sell, 50_600, 10, 0).unwrap();
+        (t, iidx, maker)
+    }
+
+    #[test]
+    fn test_mark_starts_at_index() {
+        let (t, iidx, _) = setup();
+        let inst = t.get_instrument(iidx).unwrap();
+        assert_eq!(inst.mark_price, 50_000);
+        assert_eq!(inst.premium_ema_bps, 0);
+    }
+
+    #[test]
+    fn test_mark_premium_is_time_averaged() {
+        let (mut t, iidx, _) = setup();
+        t.header.mark_premium_clamp_bps = 10_000;
+        let window = t.header.mark_ema_window_ms;
+
+        // First sample anchors the clock; the 110 bps premium phases in over the window
+        assert_eq!(update_mark_price(&mut t, iidx, 1_000), Ok(50_000));
+        assert_eq!(update_mark_price(&mut t, iidx, 1_000 + window / 2), Ok(50_275));
+        assert_eq!(t.get_instrument(iidx).unwrap().premium_ema_bps, 55);
+
+        assert_eq!(update_mark_price(&mut t, iidx, 1_000 + 10 * window), Ok(50_550));
+        assert_eq!(t.get_instrument(iidx).unwrap().index_price, 50_000);
+    }
+
+    #[test]
+    fn test_sample_after_gap_does_not_jump_mark() {
+        let mut t = TestSlab::new();
+        t.header.mark_premium_clamp_bps = 10_000;
+        let window = t.header.mark_ema_window_ms;
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, CASH);
+        t.add_dlp(maker).unwrap();
+        place_order(&mut t, maker, iidx, Side::Buy, 50_500, 10, 0).unwrap();
+
+        // One-sided book: no premium
+        assert_eq!(update_mark_price(&mut t, iidx, 1_000), Ok(50_000));
+
+        // A quote appearing after a long quiet spell has not held yet
+        place_order(&mut t, maker, iidx, Side::Sell, 50_600, 10, 0).unwrap();
+        assert_eq!(update_mark_price(&mut t, iidx, 1_000 + 10 * window), Ok(50_000));
+        assert_eq!(t.get_instrument(iidx).unwrap().premium_sample_bps, 110);
+
+        // It counts in proportion to how long it then stays
+        assert_eq!(update_mark_price(&mut t, iidx, 1_000 + 10 * window + window / 2), Ok(50_275));
+    }
+
+    #[test]
+    fn test_mark_premium_is_clamped() {
+        let (mut t, iidx, _) = setup();
+        let window = t.header.mark_ema_window_ms;
+
+        update_mark_price(&mut t, iidx, 1_000).unwrap();
+        assert_eq!(update_mark_price(&mut t, iidx, 1_000 + window), Ok(50_250));
+        assert_eq!(t.get_instrument(iidx).unwrap().premium_ema_bps, 110);
+    }
+
+    #[test]
+    fn test_equity_uses_mark() {
+        let (mut t, iidx, _) = setup();
+        let taker = t.add_account(2, CASH);
+
+        let res =
+            reserve(&mut t, taker, iidx, Side::Buy, 1, 50_600, 1_000, [0; 32], 1, TimeInForce::IOC, 0)
+                .unwrap();
+        commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        let equity = calculate_equity(&t, taker).unwrap();
+
+        // Moving the index alone does not revalue the position
+        t.get_instrument_mut(iidx).unwrap().index_price = 40_000;
+        assert_eq!(calculate_equity(&t, taker).unwrap(), equity);
+
+        // Entry at 50_600 was valued at the 50_000 mark; 50_700 is 700 higher
+        t.get_instrument_mut(iidx).unwrap().mark_price = 50_700;
+        assert_eq!(calculate_equity(&t, taker).unwrap(), equity + 700);
+    }
+}
+
+#[cfg(test)]
+mod kill_band_tests {
+    use super::harness::TestSlab;
+    use crate::matching::{commit, place_order, reserve, ReserveResult};
+    use percolator_common::*;
+
+    /// DLP maker offering 10 @ 50,010; taker reserves 5 against a 50,000 mark
+    fn setup() -> (TestSlab, u16, ReserveResult) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let taker = t.add_account(2, 1_000_000_000);
+
+        place_order(&mut t, maker, iidx, Side::Sell, 50_010, 10, 0).unwrap();
+        let res =
+            reserve(&mut t, taker, iidx, Side::Buy, 5, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0)
+                .unwrap();
+        (t, iidx, res)
+    }
+
+    fn set_mark(t: &mut TestSlab, iidx: u16, mark: u64) {
+        t.get_instrument_mut(iidx).unwrap().mark_price = mark;
+    }
+
+    /// M10: kill band rejections are deterministic; router can pre-check
+    #[test]
+    fn test_commit_rejected_outside_kill_band() {
+        let (mut t, iidx, res) = setup();
+        assert_eq!(res.mark_px, 50_000);
+        let band = t.header.kill_band_bps;
+
+        // 100 bps band: 50,501 is outside, and the router's pre-check agrees
+        set_mark(&mut t, iidx, 50_501);
+        assert!(exceeds_kill_band(res.mark_px, 50_501, band));
+        assert_eq!(commit(&mut t, res.hold_id, &[0; 16], 0), Err(PercolatorError::KillBandExceeded));
+        assert_eq!(commit(&mut t, res.hold_id, &[0; 16], 0), Err(PercolatorError::KillBandExceeded));
+
+        // The reservation is untouched and commits once the mark is back inside
+        assert_eq!(t.slices.used(), 1);
+        set_mark(&mut t, iidx, 49_500);
+        assert!(!exceeds_kill_band(res.mark_px, 49_500, band));
+        assert_eq!(commit(&mut t, res.hold_id, &[0; 16], 0).unwrap().filled_qty, 5);
+    }
+
+    #[test]
+    fn test_kill_band_follows_params() {
+        let (mut t, iidx, res) = setup();
+
+        set_mark(&mut t, iidx, 50_600);
+        assert_eq!(commit(&mut t, res.hold_id, &[0; 16], 0), Err(PercolatorError::KillBandExceeded));
+
+        t.header.kill_band_bps = 120;
+        assert_eq!(commit(&mut t, res.hold_id, &[0; 16], 0).unwrap().filled_qty, 5);
+    }
+}
+
+#[cfg(test)]
+mod freeze_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::process_batch_open;
+    use crate::matching::{cancel_all, cancel_order, commit, modify_order, place_order, reserve};
+    use percolator_common::*;
+
+    /// DLP maker with 5 asks at each of 50,010 / 50,020 / 50,030 / 50,040 and
+    /// a second DLP maker; a taker has reserved 3 at the top
+    fn setup() -> (TestSlab, u16, u32, u32, [u64; 4], u64) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let late = t.add_account(2, 1_000_000_000);
+        t.add_dlp(late).unwrap();
+        let taker = t.add_account(3, 1_000_000_000);
+
+        let mut ids = [0; 4];
+        for (i, px) in [50_010, 50_020, 50_030, 50_040].into_iter().enumerate() {
+            ids[i] = place_order(&mut t, maker, iidx, Side::Sell, px, 5, 0).unwrap().order_id;
+        }
+        let hold_id =
+            reserve(&mut t, taker, iidx, Side::Buy, 3, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0)
+                .unwrap()
+                .hold_id;
+        (t, iidx, maker, late, ids, hold_id)
+    }
+
+    /// M8: Top-K freeze keeps reserved slices at the front vs late pop-ins
+    #[test]
+    fn test_freeze_blocks_pop_ins() {
+        let (mut t, iidx, _, late, _, hold_id) = setup();
+        let inst = t.get_instrument(iidx).unwrap();
+        assert_eq!(inst.asks_freeze_px, 50_030);
+        assert_eq!(inst.freeze_until_ms, 100);
+
+        for px in [50_000, 50_010, 50_020] {
+            assert_eq!(
+                place_order(&mut t, late, iidx, Side::Sell, px, 5, 0),
+                Err(PercolatorError::OrderFrozen)
+            );
+        }
+        // Joining behind the frozen levels, or the untouched bid side, is fine
+        place_order(&mut t, late, iidx, Side::Sell, 50_030, 5, 0).unwrap();
+        place_order(&mut t, late, iidx, Side::Sell, 50_050, 5, 0).unwrap();
+        place_order(&mut t, late, iidx, Side::Buy, 49_990, 5, 0).unwrap();
+
+        let res = commit(&mut t, hold_id, &[0; 16], 0).unwrap();
+        assert_eq!((res.filled_qty, res.avg_price), (3, 50_010));
+    }
+
+    #[test]
+    fn test_freeze_blocks_cancels_until_next_batch() {
+        let (mut t, iidx, maker, _, ids, _) = setup();
+
+        assert_eq!(cancel_order(&mut t, maker, ids[1]), Err(PercolatorError::OrderFrozen));
+        assert_eq!(
+            modify_order(&mut t, maker, ids[2], 50_030, 4, 0),
+            Err(PercolatorError::OrderFrozen)
+        );
+        cancel_order(&mut t, maker, ids[3]).unwrap();
+
+        // Reserved top order and two frozen levels stay put
+        let res = cancel_all(&mut t, maker, Some(iidx)).unwrap();
+        assert_eq!((res.cancelled, res.skipped), (0, 3));
+
+        process_batch_open(&mut t, &[9; 32], iidx, 1_000).unwrap();
+        assert_eq!(t.get_instrument(iidx).unwrap().asks_freeze_px, 0);
+        cancel_order(&mut t, maker, ids[1]).unwrap();
+    }
+
+    #[test]
+    fn test_freeze_expires_and_can_be_disabled() {
+        let (mut t, iidx, maker, late, ids, _) = setup();
+
+        t.header.update_timestamp(100);
+        cancel_order(&mut t, maker, ids[1]).unwrap();
+        place_order(&mut t, late, iidx, Side::Sell, 50_020, 5, 0).unwrap();
+
+        // With no frozen levels a reservation leaves the book open
+        t.header.freeze_levels = 0;
+        let taker = t.add_account(4, 1_000_000_000);
+        reserve(&mut t, taker, iidx, Side::Buy, 1, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0).unwrap();
+        cancel_order(&mut t, maker, ids[2]).unwrap();
+    }
+
+    #[test]
+    fn test_batch_open_is_paced_for_keepers() {
+        let (mut t, iidx, _, _, _, _) = setup();
+
+        // A keeper cannot lift the freeze before the batch window ends
+        assert_eq!(
+            process_batch_open(&mut t, &[9; 32], iidx, 50),
+            Err(PercolatorError::BatchNotDue)
+        );
+        assert_eq!(t.get_instrument(iidx).unwrap().asks_freeze_px, 50_030);
+
+        // The LP owner is not paced
+        process_batch_open(&mut t, &[0; 32], iidx, 50).unwrap();
+        assert_eq!(t.get_instrument(iidx).unwrap().epoch, 1);
+
+        assert_eq!(
+            process_batch_open(&mut t, &[9; 32], iidx, 149),
+            Err(PercolatorError::BatchNotDue)
+        );
+        process_batch_open(&mut t, &[9; 32], iidx, 150).unwrap();
+        assert_eq!(t.get_instrument(iidx).unwrap().epoch, 2);
+    }
+}
+
+#[cfg(test)]
+mod jit_penalty_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::process_batch_open;
+    use crate::matching::{commit, place_order, reserve, CommitResult};
+    use percolator_common::*;
+
+    const CASH: i128 = 1_000_000_000;
+
+    /// DLP maker and taker on BTC-PERP with a batch opened at t=1,000
+    fn setup() -> (TestSlab, u16, u32, u32) {
+        let (mut t, iidx, maker, taker) = TestSlab::with_market(CASH, CASH, &[]);
+        process_batch_open(&mut t, &[9; 32], iidx, 1_000).unwrap();
+        (t, iidx, maker, taker)
+    }
+
+    /// Maker quotes 5 @ 50,010 at `placed_ms`; taker lifts it at `commit_ms`
+    fn fill(
+        t: &mut TestSlab,
+        iidx: u16,
+        maker: u32,
+        taker: u32,
+        placed_ms: u64,
+        commit_ms: u64,
+    ) -> CommitResult {
+        t.header.update_timestamp(placed_ms);
+        place_order(t, maker, iidx, Side::Sell, 50_010, 5, placed_ms).unwrap();
+        let res =
+            reserve(t, taker, iidx, Side::Buy, 5, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0).unwrap();
+        t.header.update_timestamp(commit_ms);
+        commit(t, res.hold_id, &[0; 16], commit_ms).unwrap()
+    }
+
+    #[test]
+    fn test_rebate_paid_to_resting_maker() {
+        let (mut t, iidx, maker, taker) = setup();
+
+        // 5 bps of 250,050 notional
+        let res = fill(&mut t, iidx, maker, taker, 500, 1_500);
+        assert_eq!((res.rebate_withheld, res.withheld_fills), (0, 0));
+        assert_eq!(t.get_account(maker).unwrap().cash, CASH + 125);
+    }
+
+    #[test]
+    fn test_jit_order_forfeits_rebate() {
+        let (mut t, iidx, maker, taker) = setup();
+
+        let res = fill(&mut t, iidx, maker, taker, 1_000, 1_500);
+        assert_eq!((res.rebate_withheld, res.withheld_fills), (125, 1));
+        assert_eq!(t.get_account(maker).unwrap().cash, CASH);
+        assert_eq!((t.header.rebates_withheld, t.header.rebates_withheld_count), (125, 1));
+
+        // Penalty off: the same order earns its rebate
+        t.header.jit_penalty_on = false;
+        let res = fill(&mut t, iidx, maker, taker, 1_000, 1_500);
+        assert_eq!(res.withheld_fills, 0);
+        assert_eq!(t.get_account(maker).unwrap().cash, CASH + 125);
+    }
+
+    #[test]
+    fn test_rebate_requires_min_rest_time() {
+        let (mut t, iidx, maker, taker) = setup();
+        t.header.jit_penalty_on = false;
+        let min_ms = t.header.maker_rebate_min_ms;
+
+        let res = fill(&mut t, iidx, maker, taker, 1_200, 1_200 + min_ms - 1);
+        assert_eq!((res.rebate_withheld, res.withheld_fills), (125, 1));
+
+        let res = fill(&mut t, iidx, maker, taker, 1_200, 1_200 + min_ms);
+        assert_eq!(res.withheld_fills, 0);
+        assert_eq!(t.get_account(maker).unwrap().cash, CASH + 125);
+        assert_eq!((t.header.rebates_withheld, t.header.rebates_withheld_count), (125, 1));
+    }
+}
+
+#[cfg(test)]
+mod arg_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::process_batch_open;
+    use crate::matching::{commit, place_order, reserve, CommitResult};
+    use percolator_common::*;
+
+    const CASH: i128 = 1_000_000_000;
+
+    /// Taker has bought 5 @ 50,010 from a DLP maker this batch
+    fn setup() -> (TestSlab, u16, u32, u32) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, CASH);
+        t.add_dlp(maker).unwrap();
+        let taker = t.add_account(2, CASH);
+
+        place_order(&mut t, maker, iidx, Side::Sell, 50_010, 5, 0).unwrap();
+        let res = taker_leg(&mut t, iidx, taker, Side::Buy, 5, 50_010).unwrap();
+        assert_eq!(res.arg_tax, 0);
+        (t, iidx, maker, taker)
+    }
+
+    fn taker_leg(
+        t: &mut TestSlab,
+        iidx: u16,
+        taker: u32,
+        side: Side,
+        qty: u64,
+        px: u64,
+    ) -> Result<CommitResult, PercolatorError> {
+        let res = reserve(t, taker, iidx, side, qty, px, 1_000, [0; 32], 1, TimeInForce::IOC, 0)?;
+        commit(t, res.hold_id, &[0; 16], 0)
+    }
+
+    /// M11: ARG clips/taxes only overlapping aggressive legs
+    #[test]
+    fn test_profitable_roundtrip_is_taxed() {
+        let (mut t, iidx, maker, taker) = setup();
+        place_order(&mut t, maker, iidx, Side::Buy, 50_020, 8, 0).unwrap();
+        let cash = t.get_account(taker).unwrap().cash;
+
+        // Only the 5 bought back are taxed: 50 bps of 5 * 50,020
+        let res = taker_leg(&mut t, iidx, taker, Side::Sell, 8, 50_020).unwrap();
+        assert_eq!(res.filled_qty, 8);
+        assert_eq!(res.arg_tax, 1_250);
+        assert_eq!(t.header.arg_tax_collected, 1_250);
+
+        // Sale PnL of +50 is realized; the tax comes out of the same cash
+        assert_eq!(t.get_account(taker).unwrap().cash, cash + 50 - 1_250);
+
+        // The maker's passive legs never enter the ledger
+        assert_eq!(t.aggressor_ledger.used(), 1);
+    }
+
+    #[test]
+    fn test_losing_roundtrip_is_exempt() {
+        let (mut t, iidx, maker, taker) = setup();
+        place_order(&mut t, maker, iidx, Side::Buy, 50_000, 5, 0).unwrap();
+
+        let res = taker_leg(&mut t, iidx, taker, Side::Sell, 5, 50_000).unwrap();
+        assert_eq!((res.filled_qty, res.arg_tax), (5, 0));
+    }
+
+    #[test]
+    fn test_roundtrip_clip() {
+        let (mut t, iidx, maker, taker) = setup();
+        t.header.arg_mode = ArgMode::Clip;
+        let bid = place_order(&mut t, maker, iidx, Side::Buy, 50_020, 8, 0).unwrap();
+
+        // Only the part beyond the earlier buy fills; the rest goes back to the maker
+        let res = taker_leg(&mut t, iidx, taker, Side::Sell, 8, 50_020).unwrap();
+        assert_eq!((res.filled_qty, res.arg_clipped_qty, res.arg_tax), (3, 5, 0));
+        let order = t.orders.get(bid.order_idx).unwrap();
+        assert_eq!((order.qty, order.reserved_qty), (5, 0));
+
+        // The ledger still holds 2 bought and not sold back: a leg made up
+        // entirely of overlap is refused outright
+        assert_eq!(
+            taker_leg(&mut t, iidx, taker, Side::Sell, 2, 50_020),
+            Err(PercolatorError::RoundtripDetected)
+        );
+        // The hold stays open for the taker to cancel
+        assert_eq!(t.orders.get(bid.order_idx).unwrap().reserved_qty, 2);
+    }
+
+    #[test]
+    fn test_ledger_recycled_at_epoch_rollover() {
+        let (mut t, iidx, maker, taker) = setup();
+        assert_eq!(t.aggressor_ledger.used(), 1);
+
+        process_batch_open(&mut t, &[9; 32], iidx, 1_000).unwrap();
+        assert_eq!(t.aggressor_ledger.used(), 0);
+
+        place_order(&mut t, maker, iidx, Side::Buy, 50_020, 5, 0).unwrap();
+        let res = taker_leg(&mut t, iidx, taker, Side::Sell, 5, 50_020).unwrap();
+        assert_eq!((res.filled_qty, res.arg_tax), (5, 0));
+    }
+
+    #[test]
+    fn test_ledger_recycles_only_its_instrument() {
+        let (mut t, btc, maker, taker) = setup();
+        let eth = t.add_instrument(b"ETH-PERP");
+        place_order(&mut t, maker, eth, Side::Sell, 50_010, 5, 0).unwrap();
+        taker_leg(&mut t, eth, taker, Side::Buy, 5, 50_010).unwrap();
+
+        let btc_entry = t.find_aggressor_entry(taker, btc).unwrap();
+        let eth_entry = t.find_aggressor_entry(taker, eth).unwrap();
+        assert_eq!(t.get_instrument(btc).unwrap().aggressor_head, btc_entry);
+        assert_eq!(t.get_instrument(eth).unwrap().aggressor_head, eth_entry);
+
+        process_batch_open(&mut t, &[9; 32], btc, 1_000).unwrap();
+        assert_eq!(t.aggressor_ledger.used(), 1);
+        assert_eq!(t.find_aggressor_entry(taker, btc), None);
+        assert_eq!(t.find_aggressor_entry(taker, eth), Some(eth_entry));
+        assert_eq!(t.get_instrument(btc).unwrap().aggressor_head, u32::MAX);
+
+        // The ETH leg still roundtrips; the BTC ledger starts afresh
+        place_order(&mut t, maker, eth, Side::Buy, 50_020, 5, 0).unwrap();
+        assert_eq!(taker_leg(&mut t, eth, taker, Side::Sell, 5, 50_020).unwrap().arg_tax, 1_250);
+        place_order(&mut t, maker, btc, Side::Buy, 50_020, 5, 0).unwrap();
+        assert_eq!(taker_leg(&mut t, btc, taker, Side::Sell, 5, 50_020).unwrap().arg_tax, 0);
+    }
+}
+
+#[cfg(test)]
+mod commit_reveal_tests {
+    use super::harness::TestSlab;
+    use crate::matching::{commit, place_order, reserve};
+    use percolator_common::*;
+
+    const SALT: [u8; 16] = [0x5a; 16];
+
+    /// DLP maker offering 10 @ 50,010 and a taker account
+    fn setup() -> (TestSlab, u16, u32, u32) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let taker = t.add_account(2, 1_000_000_000);
+
+        place_order(&mut t, maker, iidx, Side::Sell, 50_010, 10, 0).unwrap();
+        (t, iidx, maker, taker)
+    }
+
+    #[test]
+    fn test_commit_with_revealed_salt() {
+        let (mut t, iidx, _, taker) = setup();
+        let hash = compute_commitment(7, iidx, Side::Buy, 5, 50_010, &SALT);
+
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 5, 50_010, 1_000, hash, 7, TimeInForce::IOC, 0)
+            .unwrap();
+
+        assert_eq!(commit(&mut t, res.hold_id, &[0; 16], 0), Err(PercolatorError::InvalidCommitment));
+        assert_eq!(commit(&mut t, res.hold_id, &SALT, 0).unwrap().filled_qty, 5);
+        assert_eq!(t.reservations.used(), 0);
+    }
+
+    /// M6: a reservation whose order differs from the committed one never fills
+    #[test]
+    fn test_commit_rejects_altered_order() {
+        let (mut t, iidx, _, taker) = setup();
+        let hash = compute_commitment(7, iidx, Side::Buy, 5, 50_000, &SALT);
+
+        // Limit price raised after committing
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 5, 50_010, 1_000, hash, 7, TimeInForce::IOC, 0)
+            .unwrap();
+        assert_eq!(commit(&mut t, res.hold_id, &SALT, 0), Err(PercolatorError::InvalidCommitment));
+
+        // Quantity and route id are bound too
+        let hash = compute_commitment(7, iidx, Side::Buy, 5, 50_010, &SALT);
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 4, 50_010, 1_000, hash, 7, TimeInForce::IOC, 0)
+            .unwrap();
+        assert_eq!(commit(&mut t, res.hold_id, &SALT, 0), Err(PercolatorError::InvalidCommitment));
+
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 5, 50_010, 1_000, hash, 8, TimeInForce::IOC, 0)
+            .unwrap();
+        assert_eq!(commit(&mut t, res.hold_id, &SALT, 0), Err(PercolatorError::InvalidCommitment));
+
+        // Nothing was executed
+        assert_eq!(t.trade_count, 0);
+    }
+
+    #[test]
+    fn test_reduce_only_commitment_uses_submitted_qty() {
+        let (mut t, iidx, maker, taker) = setup();
+        place_order(&mut t, maker, iidx, Side::Buy, 49_990, 10, 0).unwrap();
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 3, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0)
+            .unwrap();
+        commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+
+        // Reduce-only sell of 5 clips to the 3 held, but the hash covers 5
+        let hash = compute_commitment(2, iidx, Side::Sell, 5, 49_990, &SALT);
+        let res = reserve(
+            &mut t,
+            taker,
+            iidx,
+            Side::Sell,
+            5,
+            49_990,
+            1_000,
+            hash,
+            2,
+            TimeInForce::IOC,
+            ORDER_FLAG_REDUCE_ONLY,
+        )
+        .unwrap();
+        assert_eq!(commit(&mut t, res.hold_id, &SALT, 0).unwrap().filled_qty, 3);
+    }
+}
+
+#[cfg(test)]
+mod reaper_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{process_reap_expired, ReapExpiredArgs};
+    use crate::matching::{commit, place_order, reap_expired, reserve, ReapExpiredResult};
+    use percolator_common::*;
+
+    /// DLP maker offering 10 @ 50,010 and a taker account
+    fn setup() -> (TestSlab, u16, u32, u32) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let taker = t.add_account(2, 1_000_000_000);
+
+        place_order(&mut t, maker, iidx, Side::Sell, 50_010, 10, 0).unwrap();
+        (t, iidx, maker, taker)
+    }
+
+    fn hold(t: &mut TestSlab, taker: u32, iidx: u16, qty: u64) -> u64 {
+        reserve(t, taker, iidx, Side::Buy, qty, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0)
+            .unwrap()
+            .hold_id
+    }
+
+    #[test]
+    fn test_commit_frees_reservation() {
+        let (mut t, iidx, _, taker) = setup();
+        let hold_id = hold(&mut t, taker, iidx, 5);
+        assert_eq!(t.reservations.used(), 1);
+
+        commit(&mut t, hold_id, &[0; 16], 0).unwrap();
+        assert_eq!(t.reservations.used(), 0);
+        assert_eq!(t.slices.used(), 0);
+        assert_eq!(commit(&mut t, hold_id, &[0; 16], 0), Err(PercolatorError::ReservationNotFound));
+    }
+
+    #[test]
+    fn test_reap_releases_expired_holds() {
+        let (mut t, iidx, _, taker) = setup();
+        hold(&mut t, taker, iidx, 4);
+        assert_eq!(t.orders.items[0].reserved_qty, 4);
+
+        // Still committable at its expiry; a full sweep leaves the cursor in place
+        let res = reap_expired(&mut t, MAX_RESERVATIONS as u32, 1_000).unwrap();
+        assert_eq!(res.reaped, 0);
+        assert_eq!(t.reservations.used(), 1);
+
+        let res = reap_expired(&mut t, 16, 1_001).unwrap();
+        assert_eq!(res.next_cursor, 16);
+        assert_eq!(res.reaped, 1);
+        assert_eq!(t.reservations.used(), 0);
+        assert_eq!(t.slices.used(), 0);
+        assert_eq!(t.orders.items[0].reserved_qty, 0);
+    }
+
+    #[test]
+    fn test_reap_scan_is_bounded() {
+        let (mut t, iidx, _, taker) = setup();
+        for _ in 0..3 {
+            hold(&mut t, taker, iidx, 1);
+        }
+
+        let res = reap_expired(&mut t, 2, 2_000).unwrap();
+        assert_eq!(res, ReapExpiredResult { reaped: 2, next_cursor: 2 });
+        assert_eq!(t.reservations.used(), 1);
+
+        let res = reap_expired(&mut t, 2, 2_000).unwrap();
+        assert_eq!(res.reaped, 1);
+        assert_eq!(res.next_cursor, 4);
+        assert_eq!(t.orders.items[0].reserved_qty, 0);
+
+        assert_eq!(
+            process_reap_expired(&mut t, &ReapExpiredArgs { max_scan: 0 }, 2_000),
+            Err(PercolatorError::InvalidInstruction)
+        );
+    }
+
+    #[test]
+    fn test_expired_holds_no_longer_exhaust_pool() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let taker = t.add_account(2, 1_000_000_000);
+
+        // Empty book: every IOC hold locks nothing but still takes a slot
+        for _ in 0..MAX_RESERVATIONS {
+            hold(&mut t, taker, iidx, 1);
+        }
+        assert_eq!(
+            reserve(&mut t, taker, iidx, Side::Buy, 1, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0),
+            Err(PercolatorError::PoolFull)
+        );
+
+        let res = reap_expired(&mut t, MAX_RESERVATIONS as u32, 1_001).unwrap();
+        assert_eq!(res.reaped, MAX_RESERVATIONS as u32);
+        hold(&mut t, taker, iidx, 1);
+    }
+}
+
+#[cfg(test)]
+mod price_level_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::process_batch_open;
+    use crate::matching::{best_level, cancel_order, get_best_prices, next_level, place_order, prev_level};
+    use percolator_common::*;
+
+    /// Walk one live book list and check it against its level index:
+    /// strict price-time order, and every run of one price is exactly a level
+    fn check_side(t: &TestSlab, iidx: u16, side: Side) -> usize {
+        let instrument = t.get_instrument(iidx).unwrap();
+        let mut curr = match side {
+            Side::Buy => instrument.bids_head,
+            Side::Sell => instrument.asks_head,
+        };
+        let mut level = best_level(t, iidx, side, OrderState::LIVE).unwrap();
+        let mut prev: Option<(u64, u64)> = None;
+        let mut orders = 0;
+
+        while let Some(level_idx) = level {
+            let lvl = *t.price_levels.get(level_idx).unwrap();
+            assert_eq!(lvl.head, curr);
+            for n in 0..lvl.count {
+                let order = t.orders.get(curr).unwrap();
+                assert_eq!(order.price, lvl.price);
+                if let Some((px, id)) = prev {
+                    let behind = match side {
+                        Side::Buy => order.price < px,
+                        Side::Sell => order.price > px,
+                    };
+                    assert!(behind || (order.price == px && order.order_id > id));
+                }
+                if n + 1 == lvl.count {
+                    assert_eq!(lvl.tail, curr);
+                }
+                prev = Some((order.price, order.order_id));
+                curr = order.next;
+                orders += 1;
+            }
+            level = next_level(t, iidx, side, OrderState::LIVE, lvl.price).unwrap();
+        }
+
+        assert_eq!(curr, u32::MAX);
+        orders
+    }
+
+    #[test]
+    fn test_levels_track_book_through_churn() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000_000_000);
+        t.add_dlp(maker).unwrap();
+
+        // Deterministic scatter of prices across both sides
+        let mut seed = 7u64;
+        let mut ids = [0u64; 200];
+        for (n, id) in ids.iter_mut().enumerate() {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let ticks = (seed >> 33) % 40;
+            let (side, price) = if n % 2 == 0 {
+                (Side::Buy, 49_000 - ticks * 10)
+            } else {
+                (Side::Sell, 51_000 + ticks * 10)
+            };
+            *id = place_order(&mut t, maker, iidx, side, price, 1, 0).unwrap().order_id;
+        }
+        assert_eq!(check_side(&t, iidx, Side::Buy), 100);
+        assert_eq!(check_side(&t, iidx, Side::Sell), 100);
+
+        // Cancel every third order, including level heads, tails and sole orders
+        for id in ids.iter().step_by(3) {
+            cancel_order(&mut t, maker, *id).unwrap();
+        }
+        let remaining = check_side(&t, iidx, Side::Buy) + check_side(&t, iidx, Side::Sell);
+        assert_eq!(remaining, 200 - ids.iter().step_by(3).count());
+
+        let (bid, ask) = get_best_prices(&t, iidx).unwrap();
+        let best_bid = t.orders.get(t.get_instrument(iidx).unwrap().bids_head).unwrap().price;
+        let best_ask = t.orders.get(t.get_instrument(iidx).unwrap().asks_head).unwrap().price;
+        assert_eq!((bid, ask), (Some(best_bid), Some(best_ask)));
+
+        // Emptying the book releases every level and tree node
+        for id in ids.iter().skip(1).step_by(3).chain(ids.iter().skip(2).step_by(3)) {
+            cancel_order(&mut t, maker, *id).unwrap();
+        }
+        assert_eq!(get_best_prices(&t, iidx).unwrap(), (None, None));
+        assert_eq!(t.price_levels.used(), 0);
+        assert_eq!(t.level_nodes.used(), 0);
+    }
+
+    #[test]
+    fn test_neighbor_levels_of_absent_prices() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000_000);
+        t.add_dlp(maker).unwrap();
+        for px in [50_100, 50_300, 50_700] {
+            place_order(&mut t, maker, iidx, Side::Sell, px, 1, 0).unwrap();
+        }
+
+        let price = |level: Option<u32>| level.map(|idx| t.price_levels.get(idx).unwrap().price);
+        let live = OrderState::LIVE;
+
+        assert_eq!(price(next_level(&t, iidx, Side::Sell, live, 50_000).unwrap()), Some(50_100));
+        assert_eq!(price(next_level(&t, iidx, Side::Sell, live, 50_300).unwrap()), Some(50_700));
+        assert_eq!(price(next_level(&t, iidx, Side::Sell, live, 50_500).unwrap()), Some(50_700));
+        assert_eq!(price(next_level(&t, iidx, Side::Sell, live, 50_700).unwrap()), None);
+        assert_eq!(price(prev_level(&t, iidx, Side::Sell, live, 50_500).unwrap()), Some(50_300));
+        assert_eq!(price(prev_level(&t, iidx, Side::Sell, live, 50_100).unwrap()), None);
+        assert_eq!(price(prev_level(&t, iidx, Side::Sell, live, 60_000).unwrap()), Some(50_700));
+    }
+
+    #[test]
+    fn test_promoted_order_keeps_time_priority_in_level() {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let dlp = t.add_account(1, 1_000_000_000_000);
+        t.add_dlp(dlp).unwrap();
+        let maker = t.add_account(2, 1_000_000_000_000);
+
+        // The pending order is older than the live one at the same price
+        let pending = place_order(&mut t, maker, iidx, Side::Buy, 49_990, 1, 0).unwrap();
+        let live = place_order(&mut t, dlp, iidx, Side::Buy, 49_990, 1, 0).unwrap();
+        assert_eq!(pending.state, OrderState::PENDING);
+
+        process_batch_open(&mut t, &[9; 32], iidx, 1_000).unwrap();
+        let instrument = t.get_instrument(iidx).unwrap();
+        assert_eq!(instrument.bids_head, pending.order_idx);
+        assert_eq!(t.orders.get(pending.order_idx).unwrap().next, live.order_idx);
+        assert_eq!(check_side(&t, iidx, Side::Buy), 2);
+        assert_eq!(t.price_levels.used(), 1);
+    }
+}
+
+#[cfg(test)]
+mod shrink_policy_tests {
+    use super::harness::TestSlab;
+    use crate::matching::{commit, get_position_qty, place_order, remove_order, reserve, ReserveResult};
+    use percolator_common::*;
+
+    /// DLP maker offering 10 @ 50,010; taker holds 5 of it.
+    /// Returns (instrument, maker, taker, maker order idx, reservation).
+    fn setup(tif: TimeInForce) -> (TestSlab, u16, u32, u32, u32, ReserveResult) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let taker = t.add_account(2, 1_000_000_000);
+
+        let placed = place_order(&mut t, maker, iidx, Side::Sell, 50_010, 10, 0).unwrap();
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 5, 50_010, 1_000, [0; 32], 1, tif, 0).unwrap();
+        (t, iidx, maker, taker, placed.order_idx, res)
+    }
+
+    /// Shrink a maker order behind the engine's back (the reserved-qty guards
+    /// keep regular instructions from doing this)
+    fn shrink(t: &mut TestSlab, order_idx: u32, qty: u64) {
+        t.orders.get_mut(order_idx).unwrap().qty = qty;
+        t.header.increment_book_seqno();
+    }
+
+    /// ADV2: a reservation can never fill more than its makers still offer
+    #[test]
+    fn test_fail_policy_rejects_shrunk_maker() {
+        let (mut t, iidx, _, taker, order_idx, res) = setup(TimeInForce::IOC);
+        assert_eq!(t.header.shrink_policy, ShrinkPolicy::Fail);
+
+        shrink(&mut t, order_idx, 3);
+        assert_eq!(commit(&mut t, res.hold_id, &[0; 16], 0), Err(PercolatorError::ReservationStale));
+        assert_eq!(get_position_qty(&t, taker, iidx), 0);
+        assert_eq!(t.reservations.used(), 1);
+    }
+
+    /// ADV2: under `Reduce` the fill is cut to what is left and reported
+    #[test]
+    fn test_reduce_policy_fills_what_is_left() {
+        let (mut t, iidx, maker, taker, order_idx, res) = setup(TimeInForce::IOC);
+        t.header.shrink_policy = ShrinkPolicy::Reduce;
+
+        shrink(&mut t, order_idx, 3);
+        let result = commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        assert_eq!((result.filled_qty, result.shortfall_qty), (3, 2));
+        assert_eq!(get_position_qty(&t, taker, iidx), 3);
+        assert_eq!(get_position_qty(&t, maker, iidx), -3);
+        assert!(t.orders.get(order_idx).is_none());
+    }
+
+    #[test]
+    fn test_reused_order_slot_is_not_filled() {
+        let (mut t, iidx, maker, taker, order_idx, res) = setup(TimeInForce::IOC);
+        t.header.shrink_policy = ShrinkPolicy::Reduce;
+
+        // The reserved order vanishes and a new one takes its pool slot
+        remove_order(&mut t, iidx, order_idx).unwrap();
+        t.free_order(order_idx);
+        let other = place_order(&mut t, maker, iidx, Side::Sell, 50_010, 7, 0).unwrap();
+        assert_eq!(other.order_idx, order_idx);
+
+        let result = commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        assert_eq!((result.filled_qty, result.shortfall_qty), (0, 5));
+        assert_eq!(get_position_qty(&t, taker, iidx), 0);
+
+        let order = t.orders.get(order_idx).unwrap();
+        assert_eq!((order.qty, order.reserved_qty), (7, 0));
+    }
+
+    #[test]
+    fn test_swapped_order_is_not_filled_without_seqno_bump() {
+        let (mut t, iidx, _, taker, order_idx, res) = setup(TimeInForce::IOC);
+        t.header.arg_mode = ArgMode::Clip;
+
+        // Another order sits in the reserved slot but the seqno never moved
+        t.orders.get_mut(order_idx).unwrap().order_id += 1_000;
+
+        let result = commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        assert_eq!((result.filled_qty, result.arg_clipped_qty), (0, 0));
+        assert_eq!(get_position_qty(&t, taker, iidx), 0);
+        assert_eq!(t.orders.get(order_idx).unwrap().qty, 10);
+    }
+
+    #[test]
+    fn test_fok_fails_even_under_reduce() {
+        let (mut t, _, _, _, order_idx, res) = setup(TimeInForce::FOK);
+        t.header.shrink_policy = ShrinkPolicy::Reduce;
+
+        shrink(&mut t, order_idx, 4);
+        assert_eq!(commit(&mut t, res.hold_id, &[0; 16], 0), Err(PercolatorError::ReservationStale));
+    }
+}
+
+#[cfg(test)]
+mod margin_tests {
+    use super::harness::TestSlab;
+    use crate::matching::{calculate_margin_requirements, commit, get_position_qty, place_order, reserve, CommitResult};
+    use percolator_common::*;
+
+    /// DLP maker quoting 10 @ 49,990 / 10 @ 50,010 and a taker with 10,000 cash.
+    /// At 5% IMR and a 50,000 mark each contract needs 2,500 of IM.
+    fn setup() -> (TestSlab, u16, u32, u32) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let taker = t.add_account(2, 10_000);
+
+        place_order(&mut t, maker, iidx, Side::Buy, 49_990, 10, 0).unwrap();
+        place_order(&mut t, maker, iidx, Side::Sell, 50_010, 10, 0).unwrap();
+        (t, iidx, maker, taker)
+    }
+
+    fn trade(t: &mut TestSlab, iidx: u16, taker: u32, side: Side, qty: u64) -> Result<CommitResult, PercolatorError> {
+        let limit_px = match side {
+            Side::Buy => 50_010,
+            Side::Sell => 49_990,
+        };
+        let res = reserve(t, taker, iidx, side, qty, limit_px, 1_000, [0; 32], 1, TimeInForce::IOC, 0)?;
+        commit(t, res.hold_id, &[0; 16], 0)
+    }
+
+    #[test]
+    fn test_reserve_rejects_over_leveraged_taker() {
+        let (mut t, iidx, _, taker) = setup();
+
+        assert_eq!(
+            reserve(&mut t, taker, iidx, Side::Buy, 5, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0),
+            Err(PercolatorError::InsufficientMargin)
+        );
+        // Nothing stays locked after the rejection
+        assert_eq!(t.slices.used(), 0);
+        assert_eq!(t.reservations.used(), 0);
+
+        assert_eq!(trade(&mut t, iidx, taker, Side::Buy, 3).unwrap().filled_qty, 3);
+        assert_eq!(get_position_qty(&t, taker, iidx), 3);
+    }
+
+    #[test]
+    fn test_fills_refresh_margin_caches() {
+        let (mut t, iidx, maker, taker) = setup();
+
+        trade(&mut t, iidx, taker, Side::Buy, 3).unwrap();
+        for account_idx in [taker, maker] {
+            let account = t.get_account(account_idx).unwrap();
+            assert_eq!((account.im, account.mm), (7_500, 3_750));
+            assert_eq!(calculate_margin_requirements(&t, account_idx).unwrap(), (7_500, 3_750));
+        }
+    }
+
+    #[test]
+    fn test_reducing_fill_needs_no_margin() {
+        let (mut t, iidx, _, taker) = setup();
+        trade(&mut t, iidx, taker, Side::Buy, 3).unwrap();
+
+        // Losses leave the taker below its IM; it may still cut the position
+        t.get_account_mut(taker).unwrap().cash = 0;
+        assert_eq!(
+            trade(&mut t, iidx, taker, Side::Buy, 1),
+            Err(PercolatorError::InsufficientMargin)
+        );
+        assert_eq!(trade(&mut t, iidx, taker, Side::Sell, 2).unwrap().filled_qty, 2);
+        assert_eq!(get_position_qty(&t, taker, iidx), 1);
+        assert_eq!(t.get_account(taker).unwrap().im, 2_500);
+    }
+
+    #[test]
+    fn test_commit_rechecks_taker() {
+        let (mut t, iidx, _, taker) = setup();
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 3, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0).unwrap();
+
+        // Equity drops between reserve and commit
+        t.get_account_mut(taker).unwrap().cash = 5_000;
+        assert_eq!(commit(&mut t, res.hold_id, &[0; 16], 0), Err(PercolatorError::InsufficientMargin));
+    }
+
+    #[test]
+    fn test_commit_skips_under_margined_maker() {
+        let (mut t, iidx, maker, taker) = setup();
+        let other = t.add_account(3, 1_000_000_000);
+        t.add_dlp(other).unwrap();
+        place_order(&mut t, other, iidx, Side::Sell, 50_010, 5, 0).unwrap();
+        t.get_account_mut(taker).unwrap().cash = 1_000_000;
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 12, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0).unwrap();
+        assert_eq!(res.filled_qty, 12);
+
+        // The first maker's collateral is gone by the time its quote fills:
+        // its order is pulled and the fill carries on with the next one
+        t.get_account_mut(maker).unwrap().cash = 1_000;
+        let result = commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        assert_eq!((result.filled_qty, result.shortfall_qty), (2, 10));
+        assert_eq!(get_position_qty(&t, maker, iidx), 0);
+        assert_eq!(get_position_qty(&t, other, iidx), -2);
+        assert_eq!(t.orders.used(), 2);
+    }
+
+    #[test]
+    fn test_fok_fails_on_under_margined_maker() {
+        let (mut t, iidx, maker, taker) = setup();
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 3, 50_010, 1_000, [0; 32], 1, TimeInForce::FOK, 0).unwrap();
+
+        t.get_account_mut(maker).unwrap().cash = 1_000;
+        assert_eq!(commit(&mut t, res.hold_id, &[0; 16], 0), Err(PercolatorError::ReservationStale));
+    }
+}
+
+#[cfg(test)]
+mod liquidation_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{process_liquidation_call, LiquidationCallArgs};
+    use crate::matching::{commit, get_position_qty, is_liquidatable, place_order, reserve};
+    use percolator_common::*;
+
+    /// Taker long 10 @ 50,010 on 30,000 cash, with a DLP maker on the other side
+    fn setup() -> (TestSlab, u16, u32, u32) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let taker = t.add_account(2, 30_000);
+
+        place_order(&mut t, maker, iidx, Side::Sell, 50_010, 10, 0).unwrap();
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 10, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0).unwrap();
+        commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        (t, iidx, maker, taker)
+    }
+
+    /// Move index and mark together (the test instrument has no oracle)
+    fn set_price(t: &mut TestSlab, iidx: u16, price: u64) {
+        let instrument = t.get_instrument_mut(iidx).unwrap();
+        instrument.index_price = price;
+        instrument.mark_price = price;
+    }
+
+    fn liquidate(t: &mut TestSlab, account_idx: u32) -> Result<crate::matching::LiquidationResult, PercolatorError> {
+        process_liquidation_call(t, &LiquidationCallArgs { account_idx }, 1)
+    }
+
+    #[test]
+    fn test_healthy_account_is_refused() {
+        let (mut t, iidx, _, taker) = setup();
+
+        assert_eq!(liquidate(&mut t, taker), Err(PercolatorError::NotLiquidatable));
+        assert_eq!(get_position_qty(&t, taker, iidx), 10);
+    }
+
+    #[test]
+    fn test_partial_liquidation_stops_at_maintenance() {
+        let (mut t, iidx, maker, taker) = setup();
+        place_order(&mut t, maker, iidx, Side::Buy, 47_500, 10, 0).unwrap();
+        place_order(&mut t, taker, iidx, Side::Sell, 60_000, 5, 0).unwrap();
+
+        // Equity 4,900 against MM 11,875
+        set_price(&mut t, iidx, 47_500);
+        assert!(is_liquidatable(&t, taker).unwrap());
+
+        let result = liquidate(&mut t, taker).unwrap();
+        assert_eq!((result.closed_qty, result.closed_notional), (8, 380_000));
+        assert_eq!((result.liq_fee, result.remaining_deficit), (1_900, 0));
+        assert_eq!(get_position_qty(&t, taker, iidx), 2);
+        assert!(!is_liquidatable(&t, taker).unwrap());
+
+        // Resting orders were pulled; fee and guard state are accounted
+        assert_eq!(t.orders.used(), 1);
+        assert_eq!(t.header.liq_fees_collected, 1_900);
+        assert_eq!(t.header.arg_mode, ArgMode::Tax);
+        assert_eq!(t.reservations.used(), 0);
+    }
+
+    /// L3: fills never land outside the band around mark
+    #[test]
+    fn test_sweep_respects_price_band() {
+        let (mut t, iidx, maker, taker) = setup();
+        // 5% under 47,500 is 45,125
+        place_order(&mut t, maker, iidx, Side::Buy, 45_120, 10, 0).unwrap();
+        set_price(&mut t, iidx, 47_500);
+
+        let result = liquidate(&mut t, taker).unwrap();
+        assert_eq!(result.closed_qty, 0);
+        assert_eq!(result.remaining_deficit, 6_975);
+        assert_eq!(get_position_qty(&t, taker, iidx), 10);
+        assert_eq!((t.reservations.used(), t.slices.used()), (0, 0));
+
+        // Widening the band lets the sweep reach the bid
+        t.header.liq_band_bps = 600;
+        assert_eq!(liquidate(&mut t, taker).unwrap().closed_qty, 10);
+    }
+
+    #[test]
+    fn test_bankrupt_account_reports_deficit() {
+        let (mut t, iidx, maker, taker) = setup();
+        place_order(&mut t, maker, iidx, Side::Buy, 45_500, 10, 0).unwrap();
+        set_price(&mut t, iidx, 47_000);
+        // No insurance to fall back on
+        t.header.insurance_balance = 0;
+        t.header.insurance_liq_share_bps = 0;
+        t.header.insurance_taker_share_bps = 0;
+
+        // Equity is gone, so there is nothing left to charge a fee on
+        let result = liquidate(&mut t, taker).unwrap();
+        assert_eq!((result.closed_qty, result.liq_fee), (10, 0));
+        assert_eq!(get_position_qty(&t, taker, iidx), 0);
+        assert_eq!(t.get_account(taker).unwrap().cash, -15_100);
+        assert_eq!((result.insurance_used, result.remaining_deficit), (0, 15_100));
+    }
+}
+
+#[cfg(test)]
+mod insurance_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{
+        process_liquidation_call, process_query_insurance, process_top_up_insurance, LiquidationCallArgs,
+        TopUpInsuranceArgs,
+    };
+    use crate::matching::{commit, place_order, reserve, InsuranceResult};
+    use percolator_common::*;
+
+    /// Taker buys 10 @ 50,010 on `cash`, paying a 1,000 taker fee
+    fn setup(cash: i128) -> (TestSlab, u16, u32, u32) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let maker = t.add_account(1, 1_000_000_000);
+        t.add_dlp(maker).unwrap();
+        let taker = t.add_account(2, cash);
+
+        place_order(&mut t, maker, iidx, Side::Sell, 50_010, 10, 0).unwrap();
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 10, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0).unwrap();
+        assert_eq!(commit(&mut t, res.hold_id, &[0; 16], 0).unwrap().total_fee, 1_000);
+        (t, iidx, maker, taker)
+    }
+
+    /// Taker goes bankrupt: the only bid is at 45,500 with mark at 47,000
+    fn crash(t: &mut TestSlab, iidx: u16, maker: u32) {
+        place_order(t, maker, iidx, Side::Buy, 45_500, 10, 0).unwrap();
+        let instrument = t.get_instrument_mut(iidx).unwrap();
+        instrument.index_price = 47_000;
+        instrument.mark_price = 47_000;
+    }
+
+    #[test]
+    fn test_fees_feed_the_fund() {
+        let (mut t, iidx, maker, taker) = setup(30_000);
+        // 10% of the taker fee
+        assert_eq!(process_query_insurance(&t).balance, 100);
+
+        // Partial liquidation of 8 into a bid at mark
+        place_order(&mut t, maker, iidx, Side::Buy, 47_500, 10, 0).unwrap();
+        let instrument = t.get_instrument_mut(iidx).unwrap();
+        instrument.index_price = 47_500;
+        instrument.mark_price = 47_500;
+        let result = process_liquidation_call(&mut t, &LiquidationCallArgs { account_idx: taker }, 1).unwrap();
+        assert_eq!((result.closed_qty, result.liq_fee, result.insurance_used), (8, 1_900, 0));
+
+        // + 10% of the 760 sweep taker fee + 50% of the liquidation fee
+        assert_eq!(process_query_insurance(&t), InsuranceResult { balance: 100 + 76 + 950, paid_out: 0 });
+    }
+
+    #[test]
+    fn test_fund_brings_bankrupt_account_to_zero() {
+        let (mut t, iidx, maker, taker) = setup(30_000);
+        let owner = t.header.lp_owner;
+        t.add_account(owner[0], 50_000);
+        process_top_up_insurance(&mut t, &owner, &TopUpInsuranceArgs { amount: 50_000 }).unwrap();
+
+        crash(&mut t, iidx, maker);
+        let result = process_liquidation_call(&mut t, &LiquidationCallArgs { account_idx: taker }, 1).unwrap();
+        assert_eq!((result.insurance_used, result.remaining_deficit), (15_100, 0));
+        assert_eq!(t.get_account(taker).unwrap().cash, 0);
+        // The sweep's taker fee fed 91 in on the way
+        assert_eq!(
+            process_query_insurance(&t),
+            InsuranceResult { balance: 50_000 + 100 + 91 - 15_100, paid_out: 15_100 }
+        );
+    }
+
+    #[test]
+    fn test_top_up_requires_lp_owner() {
+        let mut t = TestSlab::new();
+        let owner = t.header.lp_owner;
+
+        let args = TopUpInsuranceArgs { amount: 5_000 };
+        assert_eq!(process_top_up_insurance(&mut t, &[9; 32], &args), Err(PercolatorError::Unauthorized));
+        assert_eq!(
+            process_top_up_insurance(&mut t, &owner, &TopUpInsuranceArgs { amount: 0 }),
+            Err(PercolatorError::InvalidInstruction)
+        );
+        // The owner needs a slab account to pay from
+        assert_eq!(process_top_up_insurance(&mut t, &owner, &args), Err(PercolatorError::InvalidAccount));
+
+        let owner_idx = t.add_account(owner[0], 5_000);
+        assert_eq!(process_top_up_insurance(&mut t, &owner, &args).unwrap().balance, 5_000);
+        assert_eq!(t.get_account(owner_idx).unwrap().cash, 0);
+        assert_eq!(
+            process_top_up_insurance(&mut t, &owner, &TopUpInsuranceArgs { amount: u128::MAX }),
+            Err(PercolatorError::Overflow)
+        );
+        assert_eq!(process_query_insurance(&t).balance, 5_000);
+    }
+
+    #[test]
+    fn test_top_up_is_paid_from_free_collateral() {
+        let (mut t, iidx, maker, _) = setup(31_000);
+        let owner = t.header.lp_owner;
+        let owner_idx = t.add_account(owner[0], 30_000);
+
+        // Short 4 @ 49,990 ties up 10,000 of IM
+        place_order(&mut t, maker, iidx, Side::Buy, 49_990, 4, 0).unwrap();
+        let res = reserve(&mut t, owner_idx, iidx, Side::Sell, 4, 49_990, 1_000, [0; 32], 1, TimeInForce::IOC, 0).unwrap();
+        commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+
+        assert_eq!(
+            process_top_up_insurance(&mut t, &owner, &TopUpInsuranceArgs { amount: 20_000 }),
+            Err(PercolatorError::InsufficientFunds)
+        );
+        let balance = process_query_insurance(&t).balance;
+        let result = process_top_up_insurance(&mut t, &owner, &TopUpInsuranceArgs { amount: 19_000 }).unwrap();
+        assert_eq!(result.balance, balance + 19_000);
+        assert_eq!(t.get_account(owner_idx).unwrap().cash, 30_000 - 19_000);
+    }
+}
+
+#[cfg(test)]
+mod adl_tests {
+    use super::harness::TestSlab;
+    use crate::instructions::{process_liquidation_call, LiquidationCallArgs};
+    use crate::matching::commit::update_position;
+    use crate::matching::{commit, get_position_qty, place_order, reserve};
+    use percolator_common::*;
+
+    /// Taker long 10 @ 50,010 on 30,000 cash, bought 5 from a well funded
+    /// maker and then 5 from a thinly funded one; the bid side is empty.
+    /// Returns (instrument, rich maker, thin maker, taker).
+    fn setup() -> (TestSlab, u16, u32, u32, u32) {
+        let mut t = TestSlab::new();
+        let iidx = t.add_instrument(b"BTC-PERP");
+        let rich = t.add_account(1, 1_000_000_000);
+        let thin = t.add_account(2, 100_000);
+        t.add_dlp(rich).unwrap();
+        t.add_dlp(thin).unwrap();
+        let taker = t.add_account(3, 30_000);
+
+        place_order(&mut t, rich, iidx, Side::Sell, 50_010, 5, 0).unwrap();
+        place_order(&mut t, thin, iidx, Side::Sell, 50_010, 5, 0).unwrap();
+        let res = reserve(&mut t, taker, iidx, Side::Buy, 10, 50_010, 1_000, [0; 32], 1, TimeInForce::IOC, 0).unwrap();
+        commit(&mut t, res.hold_id, &[0; 16], 0).unwrap();
+        (t, iidx, rich, thin, taker)
+    }
+
+    fn crash(t: &mut TestSlab, iidx: u16, price: u64) {
+        let instrument = t.get_instrument_mut(iidx).unwrap();
+        instrument.index_price = price;
+        instrument.mark_price = price;
+    }
+
+    #[test]
+    fn test_adl_closes_at_bankruptcy_price_by_rank() {
+        let (mut t, iidx, rich, thin, taker) = setup();
+        // Equity 30,000 - 31,100 = -1,100, with no insurance to cover it
+        crash(&mut t, iidx, 46_900);
+        t.header.insurance_balance = 0;
+        let thin_cash = t.get_account(thin).unwrap().cash;
+
+        let result = process_liquidation_call(&mut t, &LiquidationCallArgs { account_idx: taker }, 1).unwrap();
+        assert_eq!((result.closed_qty, result.adl_qty), (0, 10));
+        assert_eq!(result.remaining_deficit, 0);
+        assert_eq!(get_position_qty(&t, taker, iidx), 0);
+        assert_eq!(get_position_qty(&t, rich, iidx), 0);
+        assert_eq!(get_position_qty(&t, thin, iidx), 0);
+        // Bankruptcy price 46,900 + 1,100 / 10 leaves the taker at exactly zero
+        assert_eq!(t.get_account(taker).unwrap().cash, 0);
+        assert_eq!(t.get_account(thin).unwrap().cash, thin_cash + 5 * (50_010 - 47_010));
+
+        // The more leveraged short goes first; both are told via the ring
+        let adl: [Trade; 2] = [t.trades[2], t.trades[3]];
+        assert_eq!(t.trade_count, 4);
+        for (trade, counterparty) in adl.iter().zip([thin, rich]) {
+            assert_eq!(trade.kind, TradeKind::Adl);
+            assert_eq!((trade.order_id_maker, trade.order_id_taker), (counterparty as u64, taker as u64));
+            assert_eq!((trade.side, trade.price, trade.qty), (Side::Sell, 47_010, 5));
+        }
+        assert_eq!(t.trades[0].kind, TradeKind::Fill);
+    }
+
+    /// Check the accounts on one side of an instrument's position list
+    fn assert_side(t: &TestSlab, iidx: u16, long: bool, expected: &[u32]) {
+        let instrument = t.get_instrument(iidx).unwrap();
+        let mut pos_idx = if long { instrument.longs_head } else { instrument.shorts_head };
+        let mut prev = u32::MAX;
+        for &account_idx in expected {
+            let pos = t.positions.get(pos_idx).unwrap();
+            assert_eq!((pos.account_idx, pos.qty > 0, pos.prev_in_instrument), (account_idx, long, prev));
+            prev = pos_idx;
+            pos_idx = pos.next_in_instrument;
+        }
+        assert_eq!(pos_idx, u32::MAX);
+    }
+
+    #[test]
+    fn test_side_lists_follow_positions() {
+        let (mut t, iidx, rich, thin, taker) = setup();
+        assert_side(&t, iidx, true, &[taker]);
+        assert_side(&t, iidx, false, &[thin, rich]);
+
+        // Close one short, flip another, shrink the long
+        update_position(&mut t, thin, iidx, 5, 50_010, 0).unwrap();
+        update_position(&mut t, rich, iidx, 8, 50_010, 0).unwrap();
+        update_position(&mut t, taker, iidx, -4, 50_010, 0).unwrap();
+        assert_side(&t, iidx, true, &[rich, taker]);
+        assert_side(&t, iidx, false, &[]);
+
+        update_position(&mut t, taker, iidx, -6, 50_010, 0).unwrap();
+        assert_side(&t, iidx, true, &[rich]);
+        assert_eq!(t.positions.used(), 1);
+    }
+
+    #[test]
+    fn test_no_adl_while_insurance_can_cover() {
+        let (mut t, iidx, rich, _, taker) = setup();
+        crash(&mut t, iidx, 46_900);
+        t.header.insurance_balance = 5_000;
+
+        // The fund can carry the loss, so opposing traders keep their positions
+        let result = process_liquidation_call(&mut t, &LiquidationCallArgs { account_idx: taker }, 1).unwrap();
+        assert_eq!(result.adl_qty, 0);
+        assert_eq!(get_position_qty(&t, taker, iidx), 10);
+        assert_eq!(get_position_qty(&t, rich, iidx), -5);
+    }
+
+    #[test]
+    fn test_no_adl_while_equity_is_positive() {
+        let (mut t, iidx, rich, _, taker) = setup();
+        // Equity 8,900 against MM 12,000, but nobody is bidding
+        crash(&mut t, iidx, 48_000);
+
+        let result = process_liquidation_call(&mut t, &LiquidationCallArgs { account_idx: taker }, 1).unwrap();
+        assert_eq!((result.closed_qty, result.adl_qty), (0, 0));
+        assert_eq!(get_position_qty(&t, taker, iidx), 10);
+        assert_eq!(get_position_qty(&t, rich, iidx), -5);
+    }
+}
+
+// NOTE: Book, matching and risk tests build the slab on the heap via `harness::TestSlab`.
+// End-to-end flows through the entrypoint are covered by integration tests with surfpool.